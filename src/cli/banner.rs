@@ -1,9 +1,21 @@
-use crate::cli::colors::Colors;
+use crate::cli::colors::{Borders, Colors};
+use crate::cli::output;
+
+// Width of the banner box, borders included
+const BANNER_WIDTH: usize = 64;
 
 pub fn display_banner() {
+    if output::is_quiet() {
+        return;
+    }
+    if output::is_ascii() {
+        display_ascii_banner();
+        return;
+    }
+
     // Create a colorful gradient banner with alternating colors
     let border_color = Colors::primary;
-    let cursed_lines = vec![
+    let cursed_lines = [
         "   ██████╗██╗   ██╗██████╗ ███████╗███████╗██████╗            ",
         "  ██╔════╝██║   ██║██╔══██╗██╔════╝██╔════╝██╔══██╗           ",
         "  ██║     ██║   ██║██████╔╝███████╗█████╗  ██║  ██║           ",
@@ -12,7 +24,7 @@ pub fn display_banner() {
         "   ╚═════╝ ╚═════╝ ╚═╝  ╚═╝╚══════╝╚══════╝╚═════╝            ",
     ];
     
-    let coddy_lines = vec![
+    let coddy_lines = [
         "         ██████╗  ██████╗ ██████╗ ██╗   ██╗                   ",
         "        ██╔════╝ ██╔═══██╗██╔══██╗╚██╗ ██╔╝                   ",
         "        ██║      ██║   ██║██║  ██║ ╚████╔╝                    ",
@@ -57,3 +69,15 @@ pub fn display_banner() {
     println!("{}", border_color("╚══════════════════════════════════════════════════════════════╝"));
     println!();
 }
+
+// The block letters are drawn with box-drawing glyphs, so ASCII mode gets the name in plain text
+fn display_ascii_banner() {
+    println!("{}", Colors::primary(&Borders::top(BANNER_WIDTH)));
+    println!("{}", Colors::primary(&Borders::box_line_centered("", BANNER_WIDTH)));
+    println!("{}", Colors::warning(&Borders::box_line_centered("C U R S E D   C O D D Y", BANNER_WIDTH)));
+    println!("{}", Colors::primary(&Borders::box_line_centered("", BANNER_WIDTH)));
+    println!("{}", Colors::accent(&Borders::box_line_centered("CLI Coding Education Platform", BANNER_WIDTH)));
+    println!("{}", Colors::primary(&Borders::box_line_centered("", BANNER_WIDTH)));
+    println!("{}", Colors::primary(&Borders::bottom(BANNER_WIDTH)));
+    println!();
+}
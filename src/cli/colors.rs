@@ -1,108 +1,266 @@
+use crate::cli::output::{char_width, display_width, glyph};
+use crate::config::{Difficulty, Language};
 use colored::*;
+use std::sync::OnceLock;
 
-/// ANSI 256 color codes for terminal-friendly colors
-/// Compatible with ghostty, kitty, and other modern terminals
+/// Semantic colors for the UI. They are defined as 24-bit colors and rendered
+/// with the closest 256- or 16-color equivalent on terminals without truecolor.
 pub struct Colors;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Palette {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+// Detected once: CURSED_CODDY_COLOR (truecolor, 256 or 16) overrides, otherwise
+// COLORTERM and TERM decide
+fn palette() -> Palette {
+    static PALETTE: OnceLock<Palette> = OnceLock::new();
+    *PALETTE.get_or_init(|| {
+        let forced = std::env::var("CURSED_CODDY_COLOR").unwrap_or_default().to_lowercase();
+        match forced.as_str() {
+            "truecolor" | "24bit" => return Palette::TrueColor,
+            "256" => return Palette::Ansi256,
+            "16" => return Palette::Ansi16,
+            _ => {}
+        }
+
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default().to_lowercase();
+        let term = std::env::var("TERM").unwrap_or_default().to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" || term.contains("truecolor") || term.contains("direct") {
+            Palette::TrueColor
+        } else if term.contains("256color") {
+            Palette::Ansi256
+        } else {
+            Palette::Ansi16
+        }
+    })
+}
+
+// Renders text in the given color using the best palette the terminal supports.
+// `basic` is the hand-picked 16-color stand-in, which reads better than the
+// nearest match for saturated colors.
+fn paint(text: impl AsRef<str>, (r, g, b): (u8, u8, u8), basic: Color) -> ColoredString {
+    let text = text.as_ref();
+    // colored only emits 24-bit colors when COLORTERM says so and has no 256-color
+    // mode, so those escape codes are written directly, honouring NO_COLOR and
+    // non-tty output the same way colored does
+    let code = match palette() {
+        _ if !colored::control::SHOULD_COLORIZE.should_colorize() => return text.normal(),
+        Palette::TrueColor => format!("38;2;{};{};{}", r, g, b),
+        Palette::Ansi256 => format!("38;5;{}", ansi256_index(r, g, b)),
+        Palette::Ansi16 => return text.color(basic),
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text).normal()
+}
+
+// Nearest xterm-256 color: either a step of the 6x6x6 color cube or the grayscale ramp
+fn ansi256_index(r: u8, g: u8, b: u8) -> u8 {
+    const STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let cube_step = |v: u8| {
+        STEPS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &s)| (s as i32 - v as i32).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let (cr, cg, cb) = (cube_step(r), cube_step(g), cube_step(b));
+    let cube_index = 16 + 36 * cr + 6 * cg + cb;
+
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    let cube_rgb = (STEPS[cr as usize], STEPS[cg as usize], STEPS[cb as usize]);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = ((average.saturating_sub(8)) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_step;
+    if distance((gray_level, gray_level, gray_level)) < distance(cube_rgb) {
+        232 + gray_step
+    } else {
+        cube_index
+    }
+}
+
 impl Colors {
+    /// The color support in use, for the settings view
+    pub fn support() -> &'static str {
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return "off (NO_COLOR)";
+        }
+        match palette() {
+            Palette::TrueColor => "truecolor",
+            Palette::Ansi256 => "256 colors",
+            Palette::Ansi16 => "16 colors",
+        }
+    }
+
     // Primary colors
     pub fn primary(text: &str) -> ColoredString {
-        text.truecolor(51, 255, 255) // Bright cyan
+        paint(text, (51, 255, 255), Color::BrightCyan) // Bright cyan
     }
     
     pub fn success(text: &str) -> ColoredString {
-        text.truecolor(46, 255, 87) // Bright green
+        paint(text, (46, 255, 87), Color::BrightGreen) // Bright green
     }
     
     pub fn warning(text: &str) -> ColoredString {
-        text.truecolor(226, 255, 87) // Bright yellow
+        paint(text, (226, 255, 87), Color::BrightYellow) // Bright yellow
     }
     
     // Secondary colors
     pub fn info(text: &str) -> ColoredString {
-        text.truecolor(39, 148, 255) // Bright blue
+        paint(text, (39, 148, 255), Color::BrightBlue) // Bright blue
     }
     
     pub fn accent(text: &str) -> ColoredString {
-        text.truecolor(201, 97, 255) // Bright magenta
+        paint(text, (201, 97, 255), Color::BrightMagenta) // Bright magenta
     }
     
     pub fn error(text: &str) -> ColoredString {
-        text.truecolor(196, 0, 0) // Bright red
+        paint(text, (196, 0, 0), Color::Red) // Bright red
     }
     
     // Neutral colors
     pub fn text(text: &str) -> ColoredString {
-        text.truecolor(255, 255, 255) // White
+        paint(text, (255, 255, 255), Color::BrightWhite) // White
     }
     
     pub fn muted(text: &str) -> ColoredString {
-        text.truecolor(244, 244, 244) // Light gray
+        paint(text, (244, 244, 244), Color::White) // Light gray
     }
     
     // Status labels
     pub fn label_pass(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(46, 255, 87)
+        paint(format!("[{}]", text), (46, 255, 87), Color::BrightGreen)
     }
     
     pub fn label_fail(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(196, 0, 0)
+        paint(format!("[{}]", text), (196, 0, 0), Color::Red)
     }
     
     pub fn label_warn(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(226, 255, 87)
+        paint(format!("[{}]", text), (226, 255, 87), Color::BrightYellow)
     }
     
     pub fn label_info(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(39, 148, 255)
+        paint(format!("[{}]", text), (39, 148, 255), Color::BrightBlue)
     }
     
     pub fn label_input(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(51, 255, 255)
+        paint(format!("[{}]", text), (51, 255, 255), Color::BrightCyan)
     }
     
     pub fn label_output(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(201, 97, 255)
+        paint(format!("[{}]", text), (201, 97, 255), Color::BrightMagenta)
     }
     
     pub fn label_tip(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(226, 255, 87)
+        paint(format!("[{}]", text), (226, 255, 87), Color::BrightYellow)
     }
     
     pub fn label_gpu(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(201, 97, 255)
+        paint(format!("[{}]", text), (201, 97, 255), Color::BrightMagenta)
     }
     
     pub fn label_cpu(text: &str) -> ColoredString {
-        format!("[{}]", text).truecolor(244, 244, 244)
+        paint(format!("[{}]", text), (244, 244, 244), Color::White)
+    }
+
+    // Tags with a fixed color per language/difficulty, so they can be told apart at a glance
+    pub fn language_badge(language: Language) -> ColoredString {
+        let label = format!("[{}]", language.display_name());
+        match language {
+            Language::JavaScript => paint(label, (247, 223, 30), Color::Yellow), // JS yellow
+            Language::Cpp => paint(label, (0, 137, 214), Color::Blue), // C++ blue
+            Language::Rust => paint(label, (222, 120, 50), Color::Red), // Rust orange
+        }
+    }
+
+    pub fn difficulty_badge(difficulty: Difficulty) -> ColoredString {
+        let label = format!("[{}]", difficulty.display_name());
+        match difficulty {
+            Difficulty::Beginner => paint(label, (46, 255, 87), Color::BrightGreen), // Green
+            Difficulty::Intermediate => paint(label, (255, 170, 0), Color::Yellow), // Amber
+            Difficulty::Advanced => paint(label, (255, 60, 60), Color::BrightRed), // Red
+        }
     }
 }
 
-/// Helper to create box drawing borders
+/// Helper to create box drawing borders (plain +-| in ASCII mode)
 pub struct Borders;
 
 impl Borders {
+    /// Prints the full-width rule under headings, unless output is quiet
+    pub fn print_rule() {
+        if !crate::cli::output::is_quiet() {
+            println!("{}", Colors::primary(&"=".repeat(60)));
+        }
+    }
+
     pub fn top(width: usize) -> String {
-        format!("╔{}╗", "═".repeat(width.saturating_sub(2)))
+        format!("{}{}{}", glyph("╔", "+"), glyph("═", "-").repeat(width.saturating_sub(2)), glyph("╗", "+"))
     }
     
     pub fn bottom(width: usize) -> String {
-        format!("╚{}╝", "═".repeat(width.saturating_sub(2)))
+        format!("{}{}{}", glyph("╚", "+"), glyph("═", "-").repeat(width.saturating_sub(2)), glyph("╝", "+"))
     }
     
     pub fn separator(width: usize) -> String {
-        "─".repeat(width)
+        glyph("─", "-").repeat(width)
     }
     
     pub fn box_line(content: &str, width: usize) -> String {
-        let content_len = content.chars().count();
+        let content_len = display_width(content);
         let padding = width.saturating_sub(content_len + 4);
-        format!("║ {} {}{} ║", content, " ".repeat(padding.saturating_sub(1)), if padding > 0 { "" } else { "" })
+        format!("{} {} {} {}", glyph("║", "|"), content, " ".repeat(padding.saturating_sub(1)), glyph("║", "|"))
     }
     
     pub fn box_line_left(content: &str, width: usize) -> String {
-        let content_len = content.chars().count();
+        let content_len = display_width(content);
         let padding = width.saturating_sub(content_len + 4);
-        format!("║ {}{} ║", content, " ".repeat(padding))
+        format!("{} {}{} {}", glyph("║", "|"), content, " ".repeat(padding), glyph("║", "|"))
+    }
+
+    /// A line of a box with the content centered and no inner margin
+    pub fn box_line_centered(content: &str, width: usize) -> String {
+        let padding = width.saturating_sub(display_width(content) + 2);
+        let left = padding / 2;
+        format!("{}{}{}{}{}", glyph("║", "|"), " ".repeat(left), content, " ".repeat(padding - left), glyph("║", "|"))
+    }
+
+    /// Cuts text to `width` columns, ending with an ellipsis when it's too long
+    pub fn truncate(text: &str, width: usize) -> String {
+        if display_width(text) <= width {
+            return text.to_string();
+        }
+        let ellipsis = glyph("…", "...");
+        let mut room = width.saturating_sub(display_width(ellipsis));
+        let mut cut = String::new();
+        for c in text.chars() {
+            // A wide character that would straddle the edge is left out whole
+            let Some(rest) = room.checked_sub(char_width(c)) else { break };
+            room = rest;
+            cut.push(c);
+        }
+        cut.push_str(ellipsis);
+        cut
+    }
+
+    /// A fixed-width bar such as [██████░░░░░░░░░░░░░░], filled in proportion to
+    /// `completed` out of `total`
+    pub fn progress_bar(completed: usize, total: usize, width: usize) -> String {
+        let filled = (completed.min(total) * width).checked_div(total).unwrap_or(0);
+        format!("[{}{}]", glyph("█", "#").repeat(filled), glyph("░", "-").repeat(width - filled))
+    }
+
+    /// Pads text with spaces to `width` columns, for table columns with wide characters
+    /// (format!'s width counts characters, not columns)
+    pub fn pad(text: &str, width: usize) -> String {
+        format!("{}{}", text, " ".repeat(width.saturating_sub(display_width(text))))
     }
 }
@@ -1,17 +1,34 @@
-use crate::cli::colors::Colors;
-use crate::config::{Difficulty, Language, LessonType};
-use crate::lessons::{JourneyManager, LessonManager};
-use crate::progress::Tracker;
+use crate::cli::colors::{Borders, Colors};
+use crate::cli::{banner, exit_code, output};
+use crate::config::curriculum::Curriculum;
+use crate::config::{env_vars, Difficulty, HintBudget, Language, LessonType, Verbosity, MAX_EXERCISES};
+use crate::execution::{format_size, max_output_bytes, structured_diagnostics, FileManager, RustRunner};
+use crate::lessons::roadmap::{Roadmap, RoadmapFormat};
+use crate::lessons::{print_topic_reference, HumanLessonSource, HumanLessons, JourneyManager, LessonManager, SelfTest};
+use crate::ollama::{formatter::{max_section_chars, GeneratedContent}, request_limit, ApiMode, Generator};
+use crate::progress::{Activity, CachedLesson, LessonRating, MergeStrategy, Preferences, Tracker};
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use inquire::{Select, Text};
+use inquire::{Confirm, Select, Text};
 use rand::Rng;
+use std::io::IsTerminal;
+use std::time::Instant;
 
 #[derive(Parser)]
 #[command(name = "cursed-coddy")]
 #[command(about = "A CLI coding education platform", long_about = None)]
+#[command(disable_help_subcommand = true)]
 pub struct Cli {
+    /// Hide the banner, decorative rules and spinners (for scripts and dashboards)
+    #[arg(long, global = true)]
+    quiet: bool,
+    /// Draw borders, spinners and symbols with plain ASCII (also CURSED_CODDY_ASCII=true or CODDY_ASCII=true)
+    #[arg(long, global = true)]
+    ascii: bool,
+    /// Keep progress and caches in this directory instead of ~/.cursed-coddy (or CURSED_CODDY_HOME / CODDY_HOME)
+    #[arg(long, global = true, value_name = "DIR")]
+    home: Option<std::path::PathBuf>,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -19,97 +36,378 @@ pub struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start a new lesson
-    Start,
-    /// Continue from where you left off
+    Start(StartArgs),
+    /// Continue your lesson or journey, whichever you were doing last
     Continue,
+    /// Pick a recent lesson to continue, review or redo
+    Resume,
+    /// Regenerate the exercises of the last lesson, keeping its content
+    RegenExercises,
+    /// Play a lesson from a JSON file without generating anything
+    Play {
+        /// Lesson pack (.coddy.json) or lesson file (same format as ~/.cursed-coddy/last_lesson.json)
+        file: std::path::PathBuf,
+    },
+    /// Export the last lesson as a shareable .coddy.json pack
+    Export {
+        /// Where to write the pack (defaults to <topic>.coddy.json)
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
     /// Start or continue learning journey (campaign mode)
-    Journey,
+    Journey {
+        #[command(subcommand)]
+        action: Option<JourneyAction>,
+        /// Take journey lessons at this difficulty instead of their own, for this session
+        #[arg(long, value_enum)]
+        difficulty: Option<Difficulty>,
+    },
     /// Learn how to compile/build programs
     Compile,
     /// Show your progress
-    Progress,
+    Progress {
+        /// Print the saved progress as JSON instead of formatted text
+        #[arg(long)]
+        json: bool,
+        /// Show how you rated your lessons, by model and by topic
+        #[arg(long)]
+        ratings: bool,
+    },
+    /// Merge progress exported on another machine ('progress --json') into this one's
+    Import {
+        /// Progress file to import
+        file: std::path::PathBuf,
+        /// How to settle a journey, lesson in progress or preference both machines have
+        #[arg(long, value_enum, default_value_t = MergeStrategy::Union)]
+        strategy: MergeStrategy,
+        /// Show what would change without saving anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Save without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Compare local models on generation speed and output quality
+    Bench {
+        /// Models to compare, comma-separated (defaults to all installed models)
+        #[arg(long, value_delimiter = ',')]
+        models: Vec<String>,
+    },
+    /// Generate the same lesson with two models and compare the results
+    CompareModels {
+        /// First model
+        #[arg(long)]
+        a: String,
+        /// Second model
+        #[arg(long)]
+        b: String,
+        /// Lesson topic (defaults to the benchmark topic)
+        #[arg(long)]
+        topic: Option<String>,
+        /// Lesson language (defaults to the benchmark language)
+        #[arg(long, value_enum)]
+        language: Option<Language>,
+    },
+    /// Check that lesson files, compilers and grading work on this machine
+    Selftest,
+    /// Explain a topic in a few sentences and one example, without a lesson
+    Explain {
+        /// Topic to look up, e.g. closures
+        #[arg(required = true, num_args = 1..)]
+        topic: Vec<String>,
+        /// Language to explain it in (defaults to your saved language)
+        #[arg(long, value_enum)]
+        language: Option<Language>,
+        /// Ask the model again instead of showing the saved explanation
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Show a language's whole curriculum, with the topics you've done ticked off
+    Roadmap {
+        /// Curriculum to show (defaults to your saved language)
+        #[arg(long, value_enum)]
+        language: Option<Language>,
+        /// 'text' for the terminal, 'md' for a Markdown document
+        #[arg(long, value_enum, default_value_t = RoadmapFormat::Text)]
+        format: RoadmapFormat,
+        /// Write the roadmap to this file instead of printing it
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Generate and cache every lesson in a curriculum stage for offline study
+    Prefetch {
+        /// Language whose curriculum to prefetch
+        #[arg(long, value_enum)]
+        language: Language,
+        /// Stage number to prefetch (defaults to the whole curriculum)
+        #[arg(long, value_name = "N")]
+        stage: Option<usize>,
+    },
+    /// Check your setup and get a short tour (shown automatically on the first run)
+    Onboard,
+    /// Set the language, difficulty and lesson type 'start' picks by default
+    Prefs {
+        /// Forget the saved defaults
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Show the configuration in effect and where each value comes from
+    Settings,
     /// Show help
     Help,
 }
 
+/// Options for 'start'
+#[derive(clap::Args)]
+struct StartArgs {
+    /// Lesson language, instead of asking
+    #[arg(long, value_enum)]
+    language: Option<Language>,
+    /// Lesson difficulty, instead of asking
+    #[arg(long, value_enum)]
+    difficulty: Option<Difficulty>,
+    /// Lesson length, instead of asking
+    #[arg(long = "type", value_enum, value_name = "TYPE", conflicts_with = "minutes")]
+    lesson_type: Option<LessonType>,
+    /// Lesson topic, instead of asking; an empty one picks a random topic
+    #[arg(long)]
+    topic: Option<String>,
+    /// Skip the lesson content and go straight to the exercises
+    #[arg(long)]
+    exercise_only: bool,
+    /// Show a short summary of the lesson before generating it
+    #[arg(long)]
+    preview: bool,
+    /// Number of exercises to generate, regardless of lesson type (max 10)
+    #[arg(long, value_name = "N")]
+    exercises: Option<usize>,
+    /// About how many minutes the lesson should take, instead of picking a lesson type
+    #[arg(long, value_name = "MIN")]
+    minutes: Option<usize>,
+    /// How much explanation to ask for and show (defaults to CURSED_CODDY_VERBOSITY)
+    #[arg(long, value_enum)]
+    verbosity: Option<Verbosity>,
+    /// Hints you can reveal per exercise, or 'unlimited' to show them all (defaults to CURSED_CODDY_HINT_BUDGET)
+    #[arg(long, value_name = "N", value_parser = HintBudget::parse)]
+    hint_budget: Option<HintBudget>,
+    /// Use prefetched or built-in lessons instead of generating one
+    #[arg(long)]
+    offline: bool,
+    /// Use your saved language, difficulty and lesson type instead of asking
+    #[arg(long)]
+    use_defaults: bool,
+    /// Compile each generated code example and mark the ones that don't compile (slower)
+    #[arg(long)]
+    verify_examples: bool,
+    /// Run the tests every time you save the exercise file instead of pressing Enter
+    #[arg(long)]
+    watch: bool,
+    /// Don't offer to download the model when it isn't installed
+    #[arg(long)]
+    no_pull: bool,
+    /// Keep the lesson's notes, tests and your solutions in PATH/<language>/<topic>
+    #[arg(long, value_name = "PATH")]
+    project_dir: Option<std::path::PathBuf>,
+}
+
+impl Commands {
+    // Commands that prompt the learner and can't run with piped or missing stdin
+    fn needs_terminal(&self) -> bool {
+        match self {
+            Commands::Start(_)
+            | Commands::Continue
+            | Commands::Resume
+            | Commands::RegenExercises
+            | Commands::Play { .. }
+            | Commands::Journey { .. }
+            | Commands::Compile
+            | Commands::Onboard => true,
+            Commands::Prefs { clear } => !clear,
+            Commands::Import { dry_run, yes, .. } => !dry_run && !yes,
+            Commands::Export { .. }
+            | Commands::Progress { .. }
+            | Commands::Bench { .. }
+            | Commands::CompareModels { .. }
+            | Commands::Selftest
+            | Commands::Explain { .. }
+            | Commands::Roadmap { .. }
+            | Commands::Prefetch { .. }
+            | Commands::Settings
+            | Commands::Help => false,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum JourneyAction {
+    /// Restore the journey removed by the last reset
+    Undo,
+    /// Go back to before the last lesson you completed or skipped
+    Back,
+    /// Learn a whole curriculum stage in one combined lesson instead of a lesson per topic
+    Stage {
+        /// Language whose curriculum to use (asks if not given)
+        #[arg(long, value_enum)]
+        language: Option<Language>,
+        /// Stage number (asks if not given)
+        #[arg(long, value_name = "N")]
+        stage: Option<usize>,
+        /// Generate the lesson at this difficulty instead of the stage's own
+        /// (falls back to the journey's --difficulty)
+        #[arg(long, value_enum)]
+        difficulty: Option<Difficulty>,
+    },
+}
+
 pub fn run() -> Result<()> {
     let cli = Cli::parse();
 
+    // JSON and Markdown output have to be the only thing on stdout, so they imply quiet
+    let document = matches!(
+        cli.command,
+        Some(Commands::Progress { json: true, .. }) | Some(Commands::Roadmap { format: RoadmapFormat::Md, output: None, .. })
+    );
+    output::set_quiet(cli.quiet || document);
+    output::set_ascii(cli.ascii || output::ascii_from_env());
+    let home_from_flag = cli.home.is_some();
+    if let Some(home) = cli.home {
+        crate::progress::set_data_dir(home);
+    }
+
+    // Fail before the first prompt rather than with a misleading "cancelled" error
+    if cli.command.as_ref().is_some_and(Commands::needs_terminal) && !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "This command needs an interactive terminal, but stdin is not one. \
+             In scripts, use 'progress --json', 'selftest', 'prefetch' or 'export' instead."
+        );
+    }
+    banner::display_banner();
+
     match cli.command {
-        Some(Commands::Start) => handle_start()?,
+        Some(Commands::Start(args)) => handle_start(args)?,
         Some(Commands::Continue) => handle_continue()?,
-        Some(Commands::Journey) => handle_journey()?,
+        Some(Commands::Resume) => handle_resume()?,
+        Some(Commands::RegenExercises) => handle_regen_exercises()?,
+        Some(Commands::Play { file }) => handle_play(&file)?,
+        Some(Commands::Export { output }) => handle_export(output)?,
+        Some(Commands::Journey { action: Some(JourneyAction::Undo), .. }) => handle_journey_undo()?,
+        Some(Commands::Journey { action: Some(JourneyAction::Back), .. }) => handle_journey_back()?,
+        Some(Commands::Journey { action: Some(JourneyAction::Stage { language, stage, difficulty }), difficulty: journey_difficulty }) => {
+            handle_journey_stage(language, stage, difficulty.or(journey_difficulty))?
+        }
+        Some(Commands::Journey { action: None, difficulty }) => handle_journey(difficulty)?,
         Some(Commands::Compile) => handle_compile()?,
-        Some(Commands::Progress) => handle_progress()?,
-        Some(Commands::Help) | None => handle_help(),
+        Some(Commands::Progress { json, ratings }) => handle_progress(json, ratings)?,
+        Some(Commands::Bench { models }) => handle_bench(models)?,
+        Some(Commands::CompareModels { a, b, topic, language }) => handle_compare_models(a, b, topic, language)?,
+        Some(Commands::Selftest) => handle_selftest()?,
+        Some(Commands::Import { file, strategy, dry_run, yes }) => handle_import(&file, strategy, dry_run, yes)?,
+        Some(Commands::Explain { topic, language, refresh }) => handle_explain(&topic.join(" "), language, refresh)?,
+        Some(Commands::Roadmap { language, format, output }) => handle_roadmap(language, format, output)?,
+        Some(Commands::Prefetch { language, stage }) => handle_prefetch(language, stage)?,
+        Some(Commands::Onboard) => handle_onboard()?,
+        Some(Commands::Prefs { clear }) => handle_prefs(clear)?,
+        Some(Commands::Settings) => handle_settings(home_from_flag)?,
+        Some(Commands::Help) => handle_help(),
+        // A bare run shows the onboarding the first time, and the help after that
+        None if std::io::stdin().is_terminal() && !Tracker::new()?.is_onboarded() => handle_onboard()?,
+        None => handle_help(),
     }
 
     Ok(())
 }
 
-fn handle_start() -> Result<()> {
+fn handle_start(args: StartArgs) -> Result<()> {
+    let StartArgs {
+        language,
+        difficulty,
+        lesson_type,
+        topic,
+        exercise_only,
+        preview,
+        exercises,
+        minutes,
+        verbosity,
+        hint_budget,
+        offline,
+        use_defaults,
+        verify_examples,
+        watch,
+        no_pull,
+        project_dir,
+    } = args;
+    if let Some(language) = language.filter(|l| !l.is_enabled()) {
+        return Err(anyhow::anyhow!(
+            "{} is disabled in this configuration (see CURSED_CODDY_LANGUAGES)",
+            language.display_name()
+        ));
+    }
     println!("{}", Colors::primary("Welcome to Cursed Coddy!").bold());
-    println!("{}", Colors::primary(&"=".repeat(60)));
-
-    // Select language
-    let language_options = vec![
-        Language::JavaScript,
-        Language::Cpp,
-        Language::Rust,
-    ];
-    let language = Select::new("Select a language:", language_options)
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?;
+    Borders::print_rule();
 
-    // Select difficulty
-    let difficulty_options = vec![
-        Difficulty::Beginner,
-        Difficulty::Intermediate,
-        Difficulty::Advanced,
-    ];
-    let difficulty = Select::new("Select difficulty:", difficulty_options)
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?;
+    // Keep the override within a range the prompt can reasonably ask for
+    let exercise_count = exercises.map(|n| {
+        let clamped = n.clamp(1, MAX_EXERCISES);
+        if clamped != n {
+            println!("{}", Colors::warning(&format!("--exercises must be between 1 and {}. Using {}.", MAX_EXERCISES, clamped)));
+        }
+        clamped
+    });
 
-    // Select lesson type
-    let lesson_type_options = vec![LessonType::Short, LessonType::Medium, LessonType::Long];
-    let lesson_type = Select::new("Select lesson type:", lesson_type_options)
-        .prompt()
-        .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?;
+    // Choices given as options aren't asked for. The rest are asked with the usual
+    // choices pre-selected, or those are used outright with --use-defaults.
+    let tracker = Tracker::new()?;
+    let preferences = tracker.preferences()?;
+    let (chosen_language, chosen_difficulty, chosen_type) = (language, difficulty, lesson_type);
+    let language = match language {
+        Some(language) => language,
+        None => choose("Select a language:", "Language", Language::enabled(), preferences.language, use_defaults)?,
+    };
+    let difficulty = match difficulty {
+        Some(difficulty) => difficulty,
+        None => choose(
+            "Select difficulty:",
+            "Difficulty",
+            vec![Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Advanced],
+            preferences.difficulty,
+            use_defaults,
+        )?,
+    };
+    // A target time picks the lesson type and exercise count for this lesson only,
+    // so the saved lesson type is left as it was
+    let (lesson_type, exercise_count) = match (lesson_type, minutes) {
+        (Some(lesson_type), _) => (lesson_type, exercise_count),
+        (None, Some(minutes)) => {
+            let (lesson_type, exercises) = LessonType::for_minutes(minutes);
+            let exercise_count = exercise_count.unwrap_or(exercises);
+            println!(
+                "Lesson length: about {} min {}",
+                minutes,
+                Colors::muted(&format!("({}, {} exercise{})", lesson_type, exercise_count, if exercise_count == 1 { "" } else { "s" }))
+            );
+            (lesson_type, Some(exercise_count))
+        }
+        (None, None) => (choose_lesson_type("How much time do you have?", preferences.lesson_type, use_defaults)?, exercise_count),
+    };
+    // Only what was picked at a prompt becomes the new default; options are for this lesson
+    tracker.save_preferences(Preferences {
+        language: if chosen_language.is_some() { preferences.language } else { Some(language) },
+        difficulty: if chosen_difficulty.is_some() { preferences.difficulty } else { Some(difficulty) },
+        lesson_type: if chosen_type.is_some() || minutes.is_some() { preferences.lesson_type } else { Some(lesson_type) },
+    })?;
 
     // Enter topic (or leave blank for random selection)
     let topic = {
-        let input = Text::new("Enter a topic (e.g., 'variables', 'functions', 'loops') or leave blank for random:")
-            .prompt()
-            .map_err(|e| anyhow::anyhow!("Input cancelled: {}", e))?;
-        
+        let input = match topic {
+            Some(topic) => topic,
+            None => Text::new("Enter a topic (e.g., 'variables', 'functions', 'loops') or leave blank for random:")
+                .prompt()
+                .map_err(|e| exit_code::prompt_error("Input", e))?,
+        };
+
         let trimmed = input.trim();
         if trimmed.is_empty() {
-            // Select a random topic from common programming topics
-            let random_topics = vec![
-                "variables",
-                "functions",
-                "loops",
-                "conditionals",
-                "arrays",
-                "strings",
-                "data types",
-                "operators",
-                "control flow",
-                "error handling",
-                "recursion",
-                "object-oriented programming",
-                "memory management",
-                "pointers",
-                "iterators",
-                "closures",
-                "modules",
-                "packages",
-                "testing",
-                "debugging",
-            ];
-            let mut rng = rand::thread_rng();
-            let random_topic = random_topics[rng.gen_range(0..random_topics.len())].to_string();
+            let random_topic = random_topic(language, difficulty);
             println!("{}", Colors::warning(&format!("No topic entered. Selected random topic: {}", random_topic)).bold());
             random_topic
         } else {
@@ -118,39 +416,389 @@ fn handle_start() -> Result<()> {
     };
 
     // Start lesson
-    let manager = LessonManager::new()?;
+    let mut manager = LessonManager::new()?
+        .with_exercise_only(exercise_only)
+        .with_exercise_count(exercise_count)
+        .with_verbosity(verbosity)
+        .with_hint_budget(hint_budget)
+        .with_verify_examples(verify_examples)
+        .with_watch(watch)
+        .with_project_dir(project_dir);
+    if offline {
+        manager = manager.with_source(Box::new(HumanLessonSource));
+    } else {
+        ensure_model_installed(language, !no_pull)?;
+    }
+    // Previews are written by the model, so there's none offline
+    if preview && offline {
+        println!("{}", Colors::muted("Previews need a model, so --preview is ignored with --offline."));
+    } else if preview && !manager.preview_lesson(language, difficulty, lesson_type, &topic)? {
+        println!("{}", Colors::warning("Lesson not generated. Run 'start' again to pick another topic."));
+        return Ok(());
+    }
     manager.start_lesson(language, difficulty, lesson_type, topic)?;
 
     Ok(())
 }
 
-fn handle_continue() -> Result<()> {
+// Offers to pull the lesson's model when Ollama doesn't have it, before any time
+// goes into the lesson. Nothing is checked when Ollama can't be reached; generating
+// reports that itself.
+fn ensure_model_installed(language: Language, allow_pull: bool) -> Result<()> {
+    let generator = Generator::new();
+    let model = generator.model_for(language);
+    let model_var = generator.model_var_in_effect(language);
+    let installed = match generator.installed_models() {
+        Ok(installed) => installed,
+        Err(_) => return Ok(()),
+    };
+    if Generator::is_installed(&installed, &model) {
+        return Ok(());
+    }
+
+    // An OpenAI-compatible server has its models loaded some other way; there's nothing to pull
+    if generator.api() == ApiMode::OpenAi {
+        return Err(anyhow::anyhow!(
+            "The server at {} doesn't serve model {}. Load it there, pick one it serves with {}, or use --offline.",
+            generator.base_url(),
+            model,
+            model_var
+        ));
+    }
+    let not_installed = format!("Model {} isn't installed in Ollama.", model);
+    let manual = format!("Run 'ollama pull {}', pick another model with {}, or use --offline.", model, model_var);
+    if !allow_pull || !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!("{} {}", not_installed, manual));
+    }
+    let pull = inquire::Confirm::new(&format!("{} Pull it now? (this downloads several GB)", not_installed))
+        .with_default(true)
+        .prompt()
+        .map_err(|e| exit_code::prompt_error("Confirmation", e))?;
+    if !pull {
+        return Err(anyhow::anyhow!("{} {}", not_installed, manual));
+    }
+
+    // ollama draws its own progress bars, so it gets the terminal
+    println!();
+    let status = std::process::Command::new("ollama")
+        .args(["pull", &model])
+        .status()
+        .map_err(|e| anyhow::anyhow!("Couldn't run 'ollama pull {}': {}. Is the ollama command on your PATH?", model, e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("'ollama pull {}' failed ({}). {}", model, status, manual));
+    }
+    println!();
+    println!("{}", Colors::success(&format!("Model {} is ready.", model)));
+    Ok(())
+}
+
+// Asks for one of the options with the preferred one highlighted. With
+// use_preferred, a preferred option is taken without asking.
+fn choose<T: std::fmt::Display + PartialEq + Copy>(
+    message: &str,
+    label: &str,
+    options: Vec<T>,
+    preferred: Option<T>,
+    use_preferred: bool,
+) -> Result<T> {
+    // A preference may name a language that has since been disabled
+    let cursor = preferred.and_then(|p| options.iter().position(|o| *o == p));
+    if let (Some(index), true) = (cursor, use_preferred) {
+        println!("{}: {} {}", label, options[index], Colors::muted("(your default)"));
+        return Ok(options[index]);
+    }
+    Select::new(message, options)
+        .with_starting_cursor(cursor.unwrap_or(0))
+        .prompt()
+        .map_err(|e| exit_code::prompt_error("Selection", e))
+}
+
+// A lesson type offered by the time it takes, e.g. "About 15 min (Medium)"
+#[derive(Clone, Copy, PartialEq)]
+struct LessonLength(LessonType);
+
+impl std::fmt::Display for LessonLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "About {} min ({})", self.0.target_minutes(), self.0)
+    }
+}
+
+fn choose_lesson_type(message: &str, preferred: Option<LessonType>, use_preferred: bool) -> Result<LessonType> {
+    let options = [LessonType::Short, LessonType::Medium, LessonType::Long].map(LessonLength).to_vec();
+    Ok(choose(message, "Lesson length", options, preferred.map(LessonLength), use_preferred)?.0)
+}
+
+fn handle_prefs(clear: bool) -> Result<()> {
     let tracker = Tracker::new()?;
-    let progress = tracker.load()?;
+    if clear {
+        tracker.save_preferences(Preferences::default())?;
+        println!("{}", Colors::success("Saved defaults cleared. 'start' will ask for everything again."));
+        return Ok(());
+    }
 
-    if let Some(lesson) = progress.current_lesson {
-        println!("{}", Colors::primary("Resuming lesson..."));
-        println!("Language: {}", lesson.language.display_name());
-        println!("Difficulty: {}", lesson.difficulty.display_name());
-        println!("Topic: {}", lesson.topic);
-        println!(
-            "Progress: {}/{} exercises",
-            lesson.current_exercise, lesson.total_exercises
-        );
-        println!("{}", Colors::warning("Resume functionality coming soon!"));
+    println!("{}", Colors::primary("Lesson Defaults").bold());
+    Borders::print_rule();
+    println!("{}", Colors::muted("'start' highlights these choices, and 'start --use-defaults' uses them without asking."));
+    println!("{}", Colors::muted("They're also updated to whatever you pick in 'start'."));
+    println!();
+
+    let preferences = tracker.preferences()?;
+    let language = choose("Default language:", "Language", Language::enabled(), preferences.language, false)?;
+    let difficulty = choose(
+        "Default difficulty:",
+        "Difficulty",
+        vec![Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Advanced],
+        preferences.difficulty,
+        false,
+    )?;
+    let lesson_type = choose_lesson_type("Default lesson length:", preferences.lesson_type, false)?;
+    tracker.save_preferences(Preferences {
+        language: Some(language),
+        difficulty: Some(difficulty),
+        lesson_type: Some(lesson_type),
+    })?;
+
+    println!();
+    println!("{}", Colors::success(&format!("Defaults saved: {}, {}, {}.", language, difficulty, lesson_type)));
+    Ok(())
+}
+
+fn handle_settings(home_from_flag: bool) -> Result<()> {
+    println!("{}", Colors::primary("Settings").bold());
+    Borders::print_rule();
+    println!("{}", Colors::muted("Change these with the environment variables shown, 'prefs' or --home."));
+
+    let generator = Generator::new();
+    print_settings_section("Lesson generation");
+    print_setting("Ollama URL", generator.base_url(), &env_source("OLLAMA_URL"));
+    let api_source = alias_source("CURSED_CODDY_API");
+    print_setting("API", generator.api().display_name(), &api_source);
+    print_setting("Model", generator.model(), &env_source("OLLAMA_MODEL"));
+    for language in Language::enabled() {
+        let var = Generator::model_var(language);
+        let source = match std::env::var(var) {
+            Ok(value) if !value.trim().is_empty() => format!("env {}", var),
+            _ => "same as Model".to_string(),
+        };
+        print_setting(&format!("{} model", language.display_name()), generator.model_for(language), &source);
+    }
+    let think = match generator.think() {
+        Some(true) => "on",
+        Some(false) => "off",
+        None => "model default",
+    };
+    print_setting("Reasoning", think, &env_source("OLLAMA_THINK"));
+    print_setting("Token limit", generator.num_predict(), &alias_source("CURSED_CODDY_NUM_PREDICT"));
+    print_setting("Section length limit", format!("{} characters", max_section_chars()), &env_source("CURSED_CODDY_MAX_SECTION_CHARS"));
+    print_setting("Concurrent requests", request_limit::max_concurrent_requests(), &env_source("CURSED_CODDY_MAX_REQUESTS"));
+    print_setting("Streaming", on_off(Generator::streaming_enabled()), &env_source("CURSED_CODDY_STREAM"));
+    print_setting("Continuations", Generator::max_continuations(), &env_source("CURSED_CODDY_CONTINUATIONS"));
+    print_setting("JSON retry", on_off(Generator::json_retry_enabled()), &env_source("CURSED_CODDY_JSON_RETRY"));
+    print_setting("Verbosity", Verbosity::from_env(), &env_source("CURSED_CODDY_VERBOSITY"));
+    print_setting("Min code examples", generator.min_code_examples(), &env_source("CURSED_CODDY_MIN_EXAMPLES"));
+    print_setting("Quiz", on_off(crate::config::quiz_enabled()), &env_source("CURSED_CODDY_QUIZ"));
+    print_setting("Exercise progress", on_off(crate::config::exercise_progress_enabled()), &env_source("CURSED_CODDY_EXERCISE_PROGRESS"));
+    print_setting("Hint budget", HintBudget::from_env(), &env_source("CURSED_CODDY_HINT_BUDGET"));
+    print_setting("Watch mode", on_off(crate::lessons::watch::watch_from_env()), &env_source("CURSED_CODDY_WATCH"));
+    print_setting("Style notes", on_off(crate::lessons::style_notes_enabled()), &env_source("CURSED_CODDY_STYLE_NOTES"));
+    print_setting("Review after", format!("{} failed attempts", crate::config::review_after_attempts()), &env_source("CURSED_CODDY_REVIEW_AFTER"));
+    let languages: Vec<&str> = Language::enabled().iter().map(|l| l.display_name()).collect();
+    print_setting("Languages", languages.join(", "), &env_source("CURSED_CODDY_LANGUAGES"));
+    let topics = std::env::var("CURSED_CODDY_TOPICS")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "from the curriculum".to_string());
+    print_setting("Random topics", topics, &env_source("CURSED_CODDY_TOPICS"));
+
+    print_settings_section("Display");
+    let colors_source = if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        "env NO_COLOR".to_string()
+    } else if std::env::var("CURSED_CODDY_COLOR").is_ok_and(|v| !v.trim().is_empty()) {
+        "env CURSED_CODDY_COLOR".to_string()
+    } else {
+        "detected from the terminal".to_string()
+    };
+    print_setting("Colors", Colors::support(), &colors_source);
+    let ascii_source = if output::is_ascii() && !output::ascii_from_env() {
+        "flag --ascii".to_string()
     } else {
-        println!("{}", Colors::warning("No lesson in progress. Start a new lesson with 'start'."));
+        alias_source("CURSED_CODDY_ASCII")
+    };
+    print_setting("ASCII only", on_off(output::is_ascii()), &ascii_source);
+
+    print_settings_section("Files");
+    let data_dir = crate::progress::data_dir()?;
+    let data_dir_source = if home_from_flag { "flag --home".to_string() } else { alias_source("CURSED_CODDY_HOME") };
+    print_setting("Data directory", data_dir.display(), &data_dir_source);
+    print_setting("Work directory", FileManager::work_dir().display(), &alias_source("CURSED_CODDY_WORKDIR"));
+    print_setting(
+        "Lesson files",
+        if FileManager::keep_files_from_env() { "kept after the lesson" } else { "removed once the lesson is completed" },
+        &env_source("CURSED_CODDY_KEEP_FILES"),
+    );
+    match FileManager::project_dir_from_env() {
+        Some(dir) => print_setting("Project folder", dir.display(), &env_source("CURSED_CODDY_PROJECT_DIR")),
+        None => print_setting("Project folder", "off (exercise files go in the work directory)", "default"),
+    }
+    for language in Language::ALL {
+        let template = FileManager::template_override_path(&language).filter(|path| path.is_file());
+        match template {
+            Some(path) => print_setting(&format!("{} template", language), path.display(), "file"),
+            None => print_setting(&format!("{} template", language), "built-in", "default"),
+        }
+    }
+    let topic_notes = data_dir.join("topic_notes.txt");
+    if topic_notes.is_file() {
+        print_setting("Topic notes", topic_notes.display(), "file");
+    } else {
+        print_setting("Topic notes", "built-in only", "default");
+    }
+
+    print_settings_section("Exercises");
+    let (edition, profile, cargo_features) = RustRunner::build_settings();
+    print_setting("Rust edition", edition, &env_source("CURSED_CODDY_RUST_EDITION"));
+    let profile = if profile.is_empty() { "cargo defaults".to_string() } else { profile.join(", ") };
+    print_setting("Rust profile", profile, &env_source("CURSED_CODDY_RUST_PROFILE"));
+    let cargo_features = if cargo_features.is_empty() { "none".to_string() } else { cargo_features.join(", ") };
+    print_setting("Cargo features", cargo_features, &env_source("CURSED_CODDY_CARGO_FEATURES"));
+    let diagnostics = if structured_diagnostics() { "one by one, with fixes" } else { "compiler's plain output" };
+    print_setting("Rust errors", diagnostics, &env_source("CURSED_CODDY_RUST_DIAGNOSTICS"));
+    for var in [
+        "CURSED_CODDY_RUN_ENV",
+        "CURSED_CODDY_RUN_ENV_JAVASCRIPT",
+        "CURSED_CODDY_RUN_ENV_CPP",
+        "CURSED_CODDY_RUN_ENV_RUST",
+    ] {
+        if let Some(value) = std::env::var(var).ok().filter(|v| !v.trim().is_empty()) {
+            print_setting("Program env", value.trim(), &format!("env {}", var));
+        }
     }
+    print_setting("Output limit", format_size(max_output_bytes()), &env_source("CURSED_CODDY_MAX_OUTPUT"));
+
+    print_settings_section("Saved");
+    let tracker = Tracker::new()?;
+    let preferences = tracker.preferences()?;
+    let saved = |value: Option<String>| match value {
+        Some(value) => (value, "saved"),
+        None => ("not set".to_string(), "default"),
+    };
+    let (language, source) = saved(preferences.language.map(|l| l.to_string()));
+    print_setting("Default language", language, source);
+    let (difficulty, source) = saved(preferences.difficulty.map(|d| d.to_string()));
+    print_setting("Default difficulty", difficulty, source);
+    let (lesson_type, source) = saved(preferences.lesson_type.map(|t| t.to_string()));
+    print_setting("Default lesson type", lesson_type, source);
+    print_setting("Prefetched lessons", tracker.prefetched_count(), "saved");
+    // A damaged cache is reported by the commands that use it, not here
+    let last_lesson = match tracker.load_lesson_cache().ok().flatten() {
+        Some(lesson) => format!("{} ({}, {})", lesson.topic, lesson.language, lesson.difficulty),
+        None => "none".to_string(),
+    };
+    print_setting("Last lesson", last_lesson, "saved");
 
     Ok(())
 }
 
-fn handle_progress() -> Result<()> {
+fn print_settings_section(title: &str) {
+    println!();
+    println!("{}", Colors::warning(title).bold());
+}
+
+fn print_setting(name: &str, value: impl std::fmt::Display, source: &str) {
+    println!("  {:<20} {:<40} {}", name, value.to_string(), Colors::muted(source));
+}
+
+// A setting read from the environment comes from there when the variable is
+// set, and from the built-in default otherwise
+fn env_source(var: &str) -> String {
+    match std::env::var(var) {
+        Ok(value) if !value.trim().is_empty() => format!("env {}", var),
+        _ => "default".to_string(),
+    }
+}
+
+// Like env_source, for settings that also accept their CODDY_* name: names whichever was set
+fn alias_source(var: &'static str) -> String {
+    env_vars::lookup(var).map_or_else(|| "default".to_string(), |(name, _)| format!("env {}", name))
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+// Picks a topic from CURSED_CODDY_TOPICS (comma-separated) if set, otherwise from the
+// language's curriculum at the chosen difficulty so the topic always fits the language
+fn random_topic(language: Language, difficulty: Difficulty) -> String {
+    let custom: Vec<String> = std::env::var("CURSED_CODDY_TOPICS")
+        .map(|v| {
+            v.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut rng = rand::thread_rng();
+    if !custom.is_empty() {
+        return custom[rng.gen_range(0..custom.len())].clone();
+    }
+
+    let curriculum = Curriculum::get_for_language(language);
+    let topics = curriculum.topics_for_difficulty(difficulty);
+    topics[rng.gen_range(0..topics.len())].to_string()
+}
+
+fn handle_continue() -> Result<()> {
+    let tracker = Tracker::new()?;
+    match tracker.last_activity()? {
+        Some(Activity::Journey(language)) => {
+            println!("{}", Colors::primary(&format!("Continuing your {} journey...", language.display_name())).bold());
+            JourneyManager::new()?.start_or_continue_journey(language, None)
+        }
+        _ => {
+            LessonManager::new()?.resume_lesson()?;
+            Ok(())
+        }
+    }
+}
+
+fn handle_resume() -> Result<()> {
+    let manager = LessonManager::new()?;
+    manager.resume_from_history()
+}
+
+fn handle_regen_exercises() -> Result<()> {
+    let manager = LessonManager::new()?;
+    manager.regenerate_exercises()
+}
+
+fn handle_play(file: &std::path::Path) -> Result<()> {
+    let manager = LessonManager::new()?;
+    manager.play_lesson_file(file)
+}
+
+fn handle_export(output: Option<std::path::PathBuf>) -> Result<()> {
+    let manager = LessonManager::new()?;
+    manager.export_last_lesson(output)
+}
+
+fn handle_progress(json: bool, ratings: bool) -> Result<()> {
     let tracker = Tracker::new()?;
     let progress = tracker.load()?;
 
+    if ratings {
+        return show_ratings(&progress.lesson_ratings, json);
+    }
+
+    if json {
+        let content = serde_json::to_string_pretty(&progress)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize progress: {}", e))?;
+        println!("{}", content);
+        return Ok(());
+    }
+
     println!("{}", Colors::primary("Your Progress").bold());
-    println!("{}", Colors::primary(&"=".repeat(60)));
+    Borders::print_rule();
     println!(
         "Total lessons completed: {}",
         Colors::success(&progress.statistics.total_lessons_completed.to_string())
@@ -174,15 +822,24 @@ fn handle_progress() -> Result<()> {
         println!("\n{}", Colors::warning("Recent Lessons:"));
         for lesson in progress.completed_lessons.iter().rev().take(5) {
             println!(
-                "  {} - {} ({})",
-                Colors::primary(&lesson.topic),
-                lesson.language.display_name(),
-                lesson.difficulty.display_name()
+                "  {} {} {}",
+                Colors::language_badge(lesson.language),
+                Colors::difficulty_badge(lesson.difficulty),
+                Colors::primary(&lesson.topic)
             );
         }
     }
 
-    if progress.journey_progress.is_some() {
+    if !progress.completed_journeys.is_empty() {
+        let completed: Vec<String> = progress
+            .completed_journeys
+            .iter()
+            .map(|j| format!("{} {}", Colors::language_badge(j.language), Colors::success(&j.completed_month())))
+            .collect();
+        println!("\n{} {}", Colors::warning("Completed Journeys:"), completed.join(", "));
+    }
+
+    if !progress.journeys.is_empty() {
         println!("\n{}", Colors::primary("Learning Journey:").bold());
         let journey_manager = JourneyManager::new()?;
         journey_manager.show_journey_status()?;
@@ -191,110 +848,725 @@ fn handle_progress() -> Result<()> {
     Ok(())
 }
 
-fn handle_journey() -> Result<()> {
+// Average and count of the ratings grouped by `key`, lowest average first
+fn rating_averages<K: Ord>(ratings: &[LessonRating], key: impl Fn(&LessonRating) -> K) -> Vec<(K, f64, usize)> {
+    let mut groups: std::collections::BTreeMap<K, (u32, usize)> = std::collections::BTreeMap::new();
+    for rating in ratings {
+        let group = groups.entry(key(rating)).or_insert((0, 0));
+        group.0 += u32::from(rating.rating);
+        group.1 += 1;
+    }
+    let mut averages: Vec<(K, f64, usize)> = groups
+        .into_iter()
+        .map(|(key, (total, count))| (key, f64::from(total) / count as f64, count))
+        .collect();
+    averages.sort_by(|a, b| a.1.total_cmp(&b.1));
+    averages
+}
+
+fn rating_count(count: usize) -> String {
+    format!("{} rating{}", count, if count == 1 { "" } else { "s" })
+}
+
+fn show_ratings(ratings: &[LessonRating], json: bool) -> Result<()> {
+    if json {
+        let content = serde_json::to_string_pretty(ratings)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize ratings: {}", e))?;
+        println!("{}", content);
+        return Ok(());
+    }
+
+    println!("{}", Colors::primary("Lesson Ratings").bold());
+    Borders::print_rule();
+    if ratings.is_empty() {
+        println!("{}", Colors::muted("No lessons rated yet. You're asked for a rating when you finish a lesson."));
+        return Ok(());
+    }
+
+    let total: u32 = ratings.iter().map(|r| u32::from(r.rating)).sum();
+    println!(
+        "Lessons rated: {} (average {})",
+        Colors::success(&ratings.len().to_string()),
+        Colors::success(&format!("{:.1}", f64::from(total) / ratings.len() as f64))
+    );
+
+    let model_name = |rating: &LessonRating| rating.model.clone().unwrap_or_else(|| "built-in".to_string());
+    let by_model = rating_averages(ratings, |r| (r.language.display_name(), model_name(r)));
+    println!("\n{}", Colors::warning("By Model:"));
+    for ((language, model), average, count) in by_model.iter().rev() {
+        println!("  {:<11} {:<24} {:.1}  {}", language, Colors::primary(model), average, Colors::muted(&format!("({})", rating_count(*count))));
+    }
+
+    // Only worth suggesting when there's more than one model to choose from
+    let languages: std::collections::BTreeSet<&str> = by_model.iter().map(|((language, _), _, _)| *language).collect();
+    for language in languages {
+        let rated: Vec<_> = by_model.iter().filter(|((l, _), _, _)| *l == language).collect();
+        if let Some(((_, best), average, _)) = rated.last().filter(|_| rated.len() > 1) {
+            println!("{}", Colors::info(&format!("Your best model for {} lessons is {} ({:.1} average).", language, best, average)));
+        }
+    }
+
+    let weak_topics: Vec<_> = rating_averages(ratings, |r| (r.language.display_name(), r.topic.clone()))
+        .into_iter()
+        .filter(|(_, average, _)| *average < 3.0)
+        .take(5)
+        .collect();
+    if !weak_topics.is_empty() {
+        println!("\n{}", Colors::warning("Lowest-Rated Topics:"));
+        for ((language, topic), average, count) in weak_topics {
+            println!("  {:<11} {:<24} {:.1}  {}", language, Colors::primary(&topic), average, Colors::muted(&format!("({})", rating_count(count))));
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_journey(difficulty_override: Option<Difficulty>) -> Result<()> {
     println!("{}", Colors::primary("Learning Journey Mode").bold());
-    println!("{}", Colors::primary(&"=".repeat(60)));
+    Borders::print_rule();
 
-    // Check if there's an existing journey
+    // Check for existing journeys (one per language)
     let tracker = Tracker::new()?;
-    let existing_journey = tracker.get_journey_progress()?;
+    // Journeys in disabled languages can't be continued, so leave them out
+    let existing_journeys: Vec<_> = tracker
+        .get_journeys()?
+        .into_iter()
+        .filter(|j| j.language.is_enabled())
+        .collect();
+
+    let language_options = Language::enabled();
 
-    let language = if let Some(ref journey) = existing_journey {
-        println!("Found existing journey for: {}", Colors::warning(journey.language.display_name()));
-        
-        // Show journey status
+    let language = if !existing_journeys.is_empty() {
+        // Show status of every saved journey
         let journey_manager = JourneyManager::new()?;
         journey_manager.show_journey_status()?;
-        println!();
-        
-        // Give user options
-        let options = vec!["Continue existing journey", "Reset and start fresh", "Start new journey (different language)"];
+
+        // Give user options: resume any saved journey, start another, or reset one
+        let mut options: Vec<String> = existing_journeys
+            .iter()
+            .map(|j| format!("Continue {} journey", j.language.display_name()))
+            .collect();
+        options.push("Start new journey (another language)".to_string());
+        options.push("Reset a journey".to_string());
+
         let choice = Select::new("What would you like to do?", options)
-            .prompt()
-            .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?;
-        
-        match choice {
-            "Continue existing journey" => {
-                journey.language
-            }
-            "Reset and start fresh" => {
-                let confirm = inquire::Confirm::new("Are you sure you want to reset your journey progress? This cannot be undone.")
-                    .with_default(false)
-                    .prompt()
-                    .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?;
-                
-                if confirm {
-                    tracker.reset_journey()?;
-                    println!("{}", Colors::success("Journey progress reset!"));
-                    println!();
-                    
-                    // Select language for new journey
-                    let language_options = vec![
-                        Language::JavaScript,
-                        Language::Cpp,
-                        Language::Rust,
-                    ];
-                    Select::new("Select a language for your new journey:", language_options)
-                        .prompt()
-                        .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?
-                } else {
-                    println!("Reset cancelled.");
-                    return Ok(());
-                }
-            }
-            "Start new journey (different language)" => {
-                // Select new language (this will automatically reset the old one)
-                let language_options = vec![
-                    Language::JavaScript,
-                    Language::Cpp,
-                    Language::Rust,
-                ];
-                Select::new("Select a language for your journey:", language_options)
+            .raw_prompt()
+            .map_err(|e| exit_code::prompt_error("Selection", e))?;
+
+        if choice.index < existing_journeys.len() {
+            existing_journeys[choice.index].language
+        } else if choice.index == existing_journeys.len() {
+            // Languages with a saved journey are resumed rather than restarted
+            Select::new("Select a language for your journey:", language_options)
+                .prompt()
+                .map_err(|e| exit_code::prompt_error("Selection", e))?
+        } else {
+            let reset_options: Vec<Language> = existing_journeys.iter().map(|j| j.language).collect();
+            let reset_language = if reset_options.len() == 1 {
+                reset_options[0]
+            } else {
+                Select::new("Which journey do you want to reset?", reset_options)
                     .prompt()
-                    .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?
-            }
-            _ => {
+                    .map_err(|e| exit_code::prompt_error("Selection", e))?
+            };
+
+            let confirm = inquire::Confirm::new(&format!(
+                "Are you sure you want to reset your {} journey progress? Only the last reset can be undone (with 'cursed-coddy journey undo').",
+                reset_language.display_name()
+            ))
+                .with_default(false)
+                .prompt()
+                .map_err(|e| exit_code::prompt_error("Selection", e))?;
+
+            if confirm {
+                tracker.reset_journey(reset_language)?;
+                println!("{}", Colors::success("Journey progress reset!"));
+                println!();
+                reset_language
+            } else {
+                println!("Reset cancelled.");
                 return Ok(());
             }
         }
     } else {
         // Select language
-        let language_options = vec![
-            Language::JavaScript,
-            Language::Cpp,
-            Language::Rust,
-        ];
         Select::new("Select a language for your learning journey:", language_options)
             .prompt()
-            .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?
+            .map_err(|e| exit_code::prompt_error("Selection", e))?
     };
 
+    if let Some(difficulty) = difficulty_override {
+        println!("{} {}", Colors::info("Difficulty override for this session:"), Colors::difficulty_badge(difficulty));
+    }
+
     let journey_manager = JourneyManager::new()?;
-    journey_manager.start_or_continue_journey(language)?;
+    journey_manager.start_or_continue_journey(language, difficulty_override)?;
 
     Ok(())
 }
 
+fn handle_journey_stage(language: Option<Language>, stage: Option<usize>, difficulty_override: Option<Difficulty>) -> Result<()> {
+    println!("{}", Colors::primary("Stage Session").bold());
+    Borders::print_rule();
+    println!("{}", Colors::muted("One longer lesson covering every topic of a curriculum stage, with exercises across them."));
+    println!();
+
+    let language = match language {
+        Some(language) => language,
+        None => Select::new("Select a language:", Language::enabled())
+            .prompt()
+            .map_err(|e| exit_code::prompt_error("Selection", e))?,
+    };
+    let curriculum = Curriculum::get_for_language(language);
+    let stage_index = match stage {
+        Some(n) => n
+            .checked_sub(1)
+            .filter(|&idx| idx < curriculum.total_stages())
+            .ok_or_else(|| anyhow::anyhow!(
+                "Stage {} doesn't exist. The {} curriculum has stages 1-{}.",
+                n,
+                language.display_name(),
+                curriculum.total_stages()
+            ))?,
+        None => {
+            let options: Vec<String> = curriculum
+                .stages
+                .iter()
+                .enumerate()
+                .map(|(idx, stage)| format!("{}. {} ({})", idx + 1, stage.name, stage.topics.join(", ")))
+                .collect();
+            Select::new("Select a stage:", options)
+                .raw_prompt()
+                .map_err(|e| exit_code::prompt_error("Selection", e))?
+                .index
+        }
+    };
+    let stage = curriculum.get_stage(stage_index).expect("stage index checked above");
+
+    if let Some(difficulty) = difficulty_override {
+        println!("{} {}", Colors::info("Difficulty override for this session:"), Colors::difficulty_badge(difficulty));
+    }
+    JourneyManager::new()?.start_stage_lesson(language, stage, difficulty_override)
+}
+
+fn handle_journey_back() -> Result<()> {
+    let tracker = Tracker::new()?;
+    let journeys: Vec<_> = tracker
+        .get_journeys()?
+        .into_iter()
+        .filter(|j| !j.history.is_empty())
+        .collect();
+    let journey = match journeys.len() {
+        0 => {
+            println!("{}", Colors::warning("There is no journey step to go back from."));
+            return Ok(());
+        }
+        1 => journeys.into_iter().next().expect("one journey"),
+        _ => {
+            let options: Vec<Language> = journeys.iter().map(|j| j.language).collect();
+            let language = Select::new("Which journey do you want to go back in?", options)
+                .prompt()
+                .map_err(|e| exit_code::prompt_error("Selection", e))?;
+            journeys.into_iter().find(|j| j.language == language).expect("selected journey")
+        }
+    };
+    let snapshot = journey.history.last().expect("journey with history");
+
+    // Say exactly what changes before touching anything
+    let undone = if journey.completed_topics.contains(&snapshot.lesson_title) {
+        "completed"
+    } else {
+        "skipped"
+    };
+    println!("{}", Colors::primary("Go Back in the Journey").bold());
+    Borders::print_rule();
+    println!("Language: {}", Colors::language_badge(journey.language));
+    println!("Going back before: {} ({})", Colors::warning(&snapshot.lesson_title), undone);
+    println!(
+        "Lessons Completed: {} -> {}",
+        Colors::success(&journey.completed_topics.len().to_string()),
+        Colors::success(&snapshot.completed_topics.len().to_string())
+    );
+    println!(
+        "{}",
+        Colors::info(&format!(
+            "'{}' will no longer count as {}, and it will be your next lesson again.",
+            snapshot.lesson_title, undone
+        ))
+    );
+    if journey.history.len() > 1 {
+        println!("{}", Colors::muted(&format!("You can go back {} more step(s) after this.", journey.history.len() - 1)));
+    }
+    println!();
+
+    let confirm = inquire::Confirm::new("Go back?")
+        .with_default(true)
+        .prompt()
+        .map_err(|e| exit_code::prompt_error("Selection", e))?;
+
+    if confirm {
+        tracker.journey_back(journey.language)?;
+        println!("{}", Colors::success("Done! Continue the journey with 'cursed-coddy journey'."));
+    } else {
+        println!("Nothing changed.");
+    }
+
+    Ok(())
+}
+
+fn handle_journey_undo() -> Result<()> {
+    let tracker = Tracker::new()?;
+    let journey = match tracker.get_last_reset_journey()? {
+        Some(journey) => journey,
+        None => {
+            println!("{}", Colors::warning("There is no journey reset to undo."));
+            return Ok(());
+        }
+    };
+
+    // Say exactly what comes back, and what it replaces, before touching anything
+    println!("{}", Colors::primary("Undo Journey Reset").bold());
+    Borders::print_rule();
+    println!("Language: {}", Colors::language_badge(journey.language));
+    println!("Started: {}", Colors::info(&journey.started_date()));
+    println!("Current Lesson: {}", Colors::success(&format!("Lesson {}", journey.current_stage + 1)));
+    println!("Lessons Completed: {}", Colors::success(&journey.completed_topics.len().to_string()));
+    if !journey.skipped_topics.is_empty() {
+        println!("Lessons Skipped: {}", Colors::warning(&journey.skipped_topics.len().to_string()));
+    }
+    if let Some(current) = tracker.get_journey_progress(journey.language)? {
+        println!(
+            "\n{}",
+            Colors::warning(&format!(
+                "This replaces the {} journey you started since the reset ({} lesson(s) completed).",
+                journey.language.display_name(),
+                current.completed_topics.len()
+            ))
+        );
+    }
+    println!();
+
+    let confirm = inquire::Confirm::new(&format!("Restore this {} journey?", journey.language.display_name()))
+        .with_default(true)
+        .prompt()
+        .map_err(|e| exit_code::prompt_error("Selection", e))?;
+
+    if confirm {
+        tracker.undo_journey_reset()?;
+        println!("{}", Colors::success("Journey restored! Continue it with 'cursed-coddy journey'."));
+    } else {
+        println!("Undo cancelled.");
+    }
+
+    Ok(())
+}
+
+// Every model generates the same lesson so the results are comparable
+const BENCH_LANGUAGE: Language = Language::Rust;
+const BENCH_DIFFICULTY: Difficulty = Difficulty::Beginner;
+const BENCH_LESSON_TYPE: LessonType = LessonType::Short;
+const BENCH_TOPIC: &str = "variables";
+
+struct BenchResult {
+    model: String,
+    seconds: Option<f64>,
+    valid_json: bool,
+    problems: Vec<String>,
+    error: Option<String>,
+}
+
+fn handle_bench(models: Vec<String>) -> Result<()> {
+    println!("{}", Colors::primary("Model Benchmark").bold());
+    Borders::print_rule();
+
+    let models = if models.is_empty() {
+        Generator::new().installed_models()?
+    } else {
+        models
+    };
+    if models.is_empty() {
+        println!("{}", Colors::warning("No models found. Pull one with 'ollama pull <model>' or pass --models."));
+        return Ok(());
+    }
+
+    println!(
+        "Generating a {} {} lesson on '{}' with {} model(s)\n",
+        BENCH_DIFFICULTY.display_name(),
+        BENCH_LANGUAGE.display_name(),
+        BENCH_TOPIC,
+        models.len()
+    );
+
+    let mut results = Vec::new();
+    for model in models {
+        println!("{}", Colors::info(&format!("Benchmarking {}...", model)));
+        let generator = Generator::new().with_model(&model);
+        let started = Instant::now();
+        let result = match generator.generate(BENCH_LANGUAGE, BENCH_DIFFICULTY, BENCH_LESSON_TYPE, BENCH_TOPIC) {
+            Ok(content) => BenchResult {
+                model,
+                seconds: Some(started.elapsed().as_secs_f64()),
+                valid_json: !content.is_fallback,
+                problems: content.validate(BENCH_LANGUAGE),
+                error: None,
+            },
+            Err(e) => BenchResult {
+                model,
+                seconds: None,
+                valid_json: false,
+                problems: Vec::new(),
+                error: Some(e.to_string()),
+            },
+        };
+        results.push(result);
+    }
+
+    print_bench_results(&results);
+    Ok(())
+}
+
+fn handle_import(file: &std::path::Path, strategy: MergeStrategy, dry_run: bool, yes: bool) -> Result<()> {
+    println!("{}", Colors::primary("Import Progress").bold());
+    Borders::print_rule();
+
+    let tracker = Tracker::new()?;
+    let merge = tracker.import_from(file, strategy)?;
+    if merge.changes.is_empty() {
+        println!("{}", Colors::success(&format!("Nothing to import: this machine already has everything in {}.", file.display())));
+        return Ok(());
+    }
+
+    println!("Importing with the '{}' strategy changes:", strategy.display_name());
+    for change in &merge.changes {
+        println!("  - {}", change);
+    }
+    println!();
+
+    if dry_run {
+        println!("{}", Colors::muted("Dry run: nothing was saved."));
+        return Ok(());
+    }
+    if !yes
+        && !Confirm::new("Save these changes?")
+            .with_default(true)
+            .prompt()
+            .map_err(|e| exit_code::prompt_error("Confirmation", e))?
+    {
+        println!("{}", Colors::muted("Nothing was changed."));
+        return Ok(());
+    }
+    tracker.save(&merge.progress)?;
+    println!("{}", Colors::success("Progress imported."));
+    Ok(())
+}
+
+// A quick refresher on a topic. Nothing is recorded as progress; the answer is
+// saved so the same lookup is instant next time.
+fn handle_explain(topic: &str, language: Option<Language>, refresh: bool) -> Result<()> {
+    let tracker = Tracker::new()?;
+    let language = match language {
+        Some(language) => language,
+        None => saved_language(&tracker)?,
+    };
+
+    if let Some(reference) = tracker.load_reference(language, topic).filter(|_| !refresh) {
+        print_topic_reference(language, topic, &reference);
+        println!("{}", Colors::muted("Saved from an earlier lookup; pass --refresh to ask the model again."));
+        return Ok(());
+    }
+
+    let reference = Generator::new().generate_reference(language, topic)?;
+    if let Err(e) = tracker.save_reference(language, topic, &reference) {
+        eprintln!("{}", Colors::warning(&format!("Could not save the explanation: {}", e)));
+    }
+    print_topic_reference(language, topic, &reference);
+    println!("{}", Colors::muted("For exercises on it, run 'cursed-coddy start' and enter the topic."));
+    Ok(())
+}
+
+// The saved language if it's still enabled, otherwise the first enabled one
+fn saved_language(tracker: &Tracker) -> Result<Language> {
+    Ok(tracker
+        .preferences()?
+        .language
+        .filter(|l| Language::enabled().contains(l))
+        .or_else(|| Language::enabled().first().copied())
+        .unwrap_or(Language::JavaScript))
+}
+
+fn handle_roadmap(language: Option<Language>, format: RoadmapFormat, output: Option<std::path::PathBuf>) -> Result<()> {
+    let tracker = Tracker::new()?;
+    let language = match language {
+        Some(language) => language,
+        None => saved_language(&tracker)?,
+    };
+    let roadmap = Roadmap::new(Curriculum::get_for_language(language), &tracker.load()?.completed_lessons);
+
+    let Some(path) = output else {
+        match format {
+            RoadmapFormat::Text => print!("{}", roadmap.to_text()),
+            RoadmapFormat::Md => print!("{}", roadmap.to_markdown()),
+        }
+        return Ok(());
+    };
+
+    let document = match format {
+        RoadmapFormat::Md => roadmap.to_markdown(),
+        RoadmapFormat::Text => {
+            // Colour codes would only clutter a file
+            colored::control::set_override(false);
+            roadmap.to_text()
+        }
+    };
+    std::fs::write(&path, document).map_err(|e| crate::cli::fs_error::write_error(e, "Failed to write the roadmap", &path))?;
+    println!("{}", Colors::success(&format!("Roadmap written to {}", path.display())));
+    Ok(())
+}
+
+fn handle_prefetch(language: Language, stage: Option<usize>) -> Result<()> {
+    let curriculum = Curriculum::get_for_language(language);
+    let stages: Vec<_> = match stage {
+        Some(n) => {
+            let stage = n
+                .checked_sub(1)
+                .and_then(|idx| curriculum.get_stage(idx))
+                .ok_or_else(|| anyhow::anyhow!(
+                    "Stage {} doesn't exist. The {} curriculum has stages 1-{}.",
+                    n,
+                    language.display_name(),
+                    curriculum.total_stages()
+                ))?;
+            vec![stage]
+        }
+        None => curriculum.stages.iter().collect(),
+    };
+
+    println!("{}", Colors::primary("Prefetch Lessons").bold());
+    Borders::print_rule();
+
+    let lessons: Vec<_> = stages
+        .iter()
+        .flat_map(|stage| stage.topics.iter().map(move |topic| (*stage, topic)))
+        .collect();
+    println!("Language: {}", Colors::language_badge(language));
+    println!("Lessons to prefetch: {}\n", Colors::success(&lessons.len().to_string()));
+
+    let tracker = Tracker::new()?;
+    let generator = Generator::new();
+    let (mut cached, mut skipped, mut failed) = (0, 0, Vec::new());
+    for (idx, (stage, topic)) in lessons.iter().enumerate() {
+        println!(
+            "{} {} {} {}",
+            Colors::muted(&format!("[{}/{}]", idx + 1, lessons.len())),
+            Colors::difficulty_badge(stage.difficulty),
+            Colors::primary(topic),
+            Colors::muted(&format!("({}, {})", stage.name, stage.lesson_type.display_name()))
+        );
+
+        let existing = tracker.load_prefetched(language, stage.difficulty, stage.lesson_type, topic);
+        if existing.is_some_and(|c| c.exercises.len() == generator.exercise_count(stage.lesson_type)) {
+            println!("  {}", Colors::muted("Already prefetched"));
+            skipped += 1;
+            continue;
+        }
+
+        // Fallback content is what learners get offline anyway, so only real lessons are kept
+        let result = generator
+            .generate(language, stage.difficulty, stage.lesson_type, topic)
+            .and_then(|content| {
+                if content.is_fallback {
+                    return Err(anyhow::anyhow!(content
+                        .fallback_reason
+                        .unwrap_or_else(|| "the model didn't return a usable lesson".to_string())));
+                }
+                tracker.save_prefetched(&CachedLesson {
+                    language,
+                    difficulty: stage.difficulty,
+                    lesson_type: stage.lesson_type,
+                    topic: topic.to_string(),
+                    content,
+                })
+            });
+        match result {
+            Ok(()) => {
+                println!("  {}", Colors::label_pass("CACHED"));
+                cached += 1;
+            }
+            Err(e) => {
+                println!("  {} {}", Colors::label_fail("SKIPPED"), Colors::error(&e.to_string()));
+                failed.push(topic.as_str());
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} cached, {} already prefetched, {} failed",
+        Colors::success(&cached.to_string()),
+        Colors::info(&skipped.to_string()),
+        if failed.is_empty() { Colors::success("0") } else { Colors::error(&failed.len().to_string()) }
+    );
+    if !failed.is_empty() {
+        println!("{}", Colors::warning(&format!("Not cached: {}. Run prefetch again to retry them.", failed.join(", "))));
+    }
+    println!(
+        "{}",
+        Colors::info("Start a lesson with the same topic, difficulty and lesson type to use the cached content.")
+    );
+    Ok(())
+}
+
+fn print_bench_results(results: &[BenchResult]) {
+    let model_width = results.iter()
+        .map(|r| r.model.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(5, 40);
+
+    println!();
+    println!(
+        "  {}",
+        Colors::muted(&format!("{:<model_width$}  {:>8}  {:<10}  {}", "Model", "Time", "JSON", "Issues"))
+    );
+    println!("  {}", Colors::muted(&Borders::separator(model_width + 32)));
+    for result in results {
+        let time = result.seconds
+            .map(|s| format!("{:.1}s", s))
+            .unwrap_or_else(|| "-".to_string());
+        let json = if result.error.is_some() {
+            Colors::error(&format!("{:<10}", "ERROR"))
+        } else if result.valid_json {
+            Colors::success(&format!("{:<10}", "valid"))
+        } else {
+            Colors::warning(&format!("{:<10}", "fallback"))
+        };
+        let issues = match (&result.error, result.problems.len()) {
+            (Some(_), _) => "-".to_string(),
+            (None, 0) => "none".to_string(),
+            (None, n) => n.to_string(),
+        };
+        println!("  {:<model_width$}  {:>8}  {}  {}", result.model, time, json, issues);
+    }
+    println!();
+
+    // Details for anything that didn't come back clean
+    for result in results {
+        if let Some(ref error) = result.error {
+            println!("{} {}", Colors::label_fail("FAIL"), Colors::error(&format!("{}: {}", result.model, error)));
+        }
+        for problem in &result.problems {
+            println!("{} {}", Colors::label_warn("WARN"), Colors::warning(&format!("{}: {}", result.model, problem)));
+        }
+    }
+}
+
+// Rows of the compare-models table: a label and how to measure it from a lesson
+// in the compared language
+type Metric = fn(&GeneratedContent, Language) -> String;
+const COMPARE_METRICS: &[(&str, Metric)] = &[
+    ("Valid JSON", |c, _| if c.is_fallback { "no (fallback)".to_string() } else { "yes".to_string() }),
+    ("Concept length", |c, _| format!("{} chars", c.concept.chars().count())),
+    ("Steps", |c, _| c.step_by_step.len().to_string()),
+    ("Code examples", |c, _| c.code_examples.len().to_string()),
+    ("Syntax guide", |c, _| format!("{} chars", c.syntax_guide.chars().count())),
+    ("Common patterns", |c, _| c.common_patterns.len().to_string()),
+    ("Exercises", |c, _| c.exercises.len().to_string()),
+    ("Test cases", |c, _| c.exercises.iter().map(|e| e.test_cases.len()).sum::<usize>().to_string()),
+    ("Issues", |c, language| c.validate(language).len().to_string()),
+];
+
+fn handle_compare_models(a: String, b: String, topic: Option<String>, language: Option<Language>) -> Result<()> {
+    let topic = topic.unwrap_or_else(|| BENCH_TOPIC.to_string());
+    let language = language.unwrap_or(BENCH_LANGUAGE);
+
+    println!("{}", Colors::primary("Model Comparison").bold());
+    Borders::print_rule();
+    println!(
+        "Generating a {} {} lesson on '{}' with each model\n",
+        BENCH_DIFFICULTY.display_name(),
+        language.display_name(),
+        topic
+    );
+
+    let mut results = Vec::new();
+    for model in [a, b] {
+        println!("{}", Colors::info(&format!("Generating with {}...", model)));
+        let generator = Generator::new().with_model(&model);
+        let started = Instant::now();
+        let content = generator.generate(language, BENCH_DIFFICULTY, BENCH_LESSON_TYPE, &topic);
+        results.push((model, started.elapsed().as_secs_f64(), content));
+    }
+
+    let column_width = results
+        .iter()
+        .map(|(model, _, _)| model.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(16, 40);
+    let label_width = 16;
+
+    println!();
+    println!(
+        "  {}",
+        Colors::muted(&format!("{:<label_width$}  {:<column_width$}  {:<column_width$}", "", results[0].0, results[1].0))
+    );
+    println!("  {}", Colors::muted(&Borders::separator(label_width + column_width * 2 + 4)));
+    let time: Vec<String> = results.iter().map(|(_, seconds, _)| format!("{:.1}s", seconds)).collect();
+    println!("  {:<label_width$}  {:<column_width$}  {:<column_width$}", "Time", time[0], time[1]);
+    for (label, metric) in COMPARE_METRICS {
+        let values: Vec<String> = results
+            .iter()
+            .map(|(_, _, content)| match content {
+                Ok(content) => metric(content, language),
+                Err(_) => "-".to_string(),
+            })
+            .collect();
+        println!("  {:<label_width$}  {:<column_width$}  {:<column_width$}", label, values[0], values[1]);
+    }
+    println!();
+
+    for (model, _, content) in &results {
+        match content {
+            Ok(content) => {
+                for problem in content.validate(language) {
+                    println!("{} {}", Colors::label_warn("WARN"), Colors::warning(&format!("{}: {}", model, problem)));
+                }
+            }
+            Err(e) => {
+                println!("{} {}", Colors::label_fail("FAIL"), Colors::error(&format!("{}: {}", model, e)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_selftest() -> Result<()> {
+    let failures = SelfTest::run();
+    println!();
+    if failures > 0 {
+        return Err(anyhow::anyhow!("Self-test failed for {} language(s)", failures));
+    }
+    println!("{}", Colors::label_pass("SUCCESS").bold());
+    println!("{}", Colors::success("All self-tests passed. Your setup is ready."));
+    Ok(())
+}
+
 fn handle_compile() -> Result<()> {
-    use crate::cli::banner;
     use crate::lessons::LessonManager;
     
     banner::display_banner();
     println!("{}", Colors::primary("Compilation & Build Guides").bold());
-    println!("{}", Colors::primary(&"=".repeat(60)));
+    Borders::print_rule();
     println!();
     println!("Learn how to compile and build programs for each language.");
     println!();
 
     // Select language
-    let language_options = vec![
-        Language::JavaScript,
-        Language::Cpp,
-        Language::Rust,
-    ];
+    let language_options = Language::enabled();
     let language = Select::new("Select a language:", language_options)
         .prompt()
-        .map_err(|e| anyhow::anyhow!("Selection cancelled: {}", e))?;
+        .map_err(|e| exit_code::prompt_error("Selection", e))?;
 
     // Generate topic based on language
     let topic = match language {
@@ -315,17 +1587,135 @@ fn handle_compile() -> Result<()> {
     Ok(())
 }
 
+fn handle_onboard() -> Result<()> {
+    println!("{}", Colors::primary("Welcome to Cursed Coddy!").bold());
+    Borders::print_rule();
+    println!("Let's check your setup and take a quick look around.");
+
+    // Step 1: lessons are generated by a local model, which new users often don't have yet
+    println!();
+    println!("{}", Colors::primary("1. Checking your setup").bold());
+    let generator = Generator::new();
+    if !SelfTest::check_ollama() {
+        println!();
+        println!("{}", Colors::info("Free-mode lessons are written by a local model through Ollama. To set it up:"));
+        println!("  1. Install Ollama from https://ollama.com/download");
+        println!("  2. Start it with 'ollama serve'");
+        println!("  3. Download the model with 'ollama pull {}'", generator.model());
+        println!("{}", Colors::muted("Until then, the journey and 'start --offline' use built-in lessons."));
+    }
+    println!("{}", Colors::muted("Run 'cursed-coddy selftest' any time to check compilers and grading too."));
+
+    // Step 2: the ways to learn
+    println!();
+    println!("{}", Colors::primary("2. Ways to learn").bold());
+    println!("  {}  Pick a language, difficulty and topic, and get a generated lesson", Colors::accent("start  "));
+    println!("  {}  Work through hand-written lessons in order, saved per language", Colors::accent("journey"));
+    println!("  {}  Learn how to compile and run programs in each language", Colors::accent("compile"));
+    println!("{}", Colors::muted("Run 'cursed-coddy help' to see every command."));
+
+    // From here on a bare run shows the help, even if the learner stops now
+    Tracker::new()?.mark_onboarded()?;
+
+    // Step 3: try it out
+    println!();
+    println!("{}", Colors::primary("3. Try it").bold());
+    let options = vec!["Start a sample lesson", "Start the learning journey", "Not now"];
+    let choice = Select::new("What would you like to do?", options)
+        .prompt()
+        .map_err(|e| exit_code::prompt_error("Selection", e))?;
+    match choice {
+        "Start a sample lesson" => {
+            let language = Select::new("Select a language:", Language::enabled())
+                .prompt()
+                .map_err(|e| exit_code::prompt_error("Selection", e))?;
+            // A built-in lesson starts right away, with or without a model
+            let (lesson, _) = HumanLessons::get_next_lesson(None, language)
+                .ok_or_else(|| anyhow::anyhow!("No built-in lessons for {}", language.display_name()))?;
+            LessonManager::new()?.start_lesson_with_content(
+                lesson.language,
+                lesson.difficulty,
+                lesson.lesson_type,
+                "Sample lesson".to_string(),
+                lesson.content,
+            )?;
+            Ok(())
+        }
+        "Start the learning journey" => handle_journey(None),
+        _ => {
+            println!("{}", Colors::info("Whenever you're ready, run 'cursed-coddy start' or 'cursed-coddy journey'."));
+            Ok(())
+        }
+    }
+}
+
 fn handle_help() {
     println!("{}", Colors::primary("Cursed Coddy - CLI Coding Education Platform").bold());
     println!();
     println!("Commands:");
     println!("  start     - Start a new lesson (free mode)");
+    println!("              --language <javascript|cpp|rust> --difficulty <beginner|intermediate|advanced>");
+    println!("              --type <short|medium|long> --topic <TOPIC>  Lesson settings to use instead of asking");
+    println!("              --exercise-only  Skip the lesson content and go straight to practice");
+    println!("              --preview        Show a short summary of the lesson before generating it");
+    println!("              --exercises <N>  Number of exercises to generate (1-10), regardless of lesson type");
+    println!("              --minutes <MIN>  About how long the lesson should take, instead of picking a lesson type");
+    println!("              --verbosity <terse|normal|verbose>  How long the explanations are");
+    println!("              --hint-budget <N|unlimited>  Hide hints and let you reveal N per exercise with 'hint'");
+    println!("              --offline        Use prefetched or built-in lessons instead of generating one");
+    println!("              --use-defaults   Use your saved language, difficulty and lesson type without asking");
+    println!("              --verify-examples  Compile each generated code example and mark the ones that don't compile");
+    println!("              --watch          Run the tests every time you save the exercise file");
+    println!("              --no-pull        Don't offer to download the model when it isn't installed");
+    println!("              --project-dir <PATH>  Keep the lesson's notes, tests and your solutions in PATH/<language>/<topic>");
     println!("  journey   - Start or continue learning journey (campaign mode)");
+    println!("              --difficulty <beginner|intermediate|advanced>  Override lesson difficulty for this session");
+    println!("  journey undo - Restore the journey removed by the last reset");
+    println!("  journey back - Go back to before the last lesson you completed or skipped");
+    println!("  journey stage - Learn a whole curriculum stage in one combined lesson");
+    println!("              --language <javascript|cpp|rust> --stage <N>  Stage to learn (asks if not given)");
+    println!("              --difficulty <beginner|intermediate|advanced>  Generate the lesson at this difficulty instead of the stage's");
     println!("  compile   - Learn how to compile/build programs for each language");
-    println!("  continue  - Continue from where you left off");
+    println!("  continue  - Continue your lesson or journey, whichever you were doing last");
+    println!("  resume    - Pick a recent lesson to continue, review or redo");
+    println!("  regen-exercises - Regenerate the exercises of the last lesson");
+    println!("  play <file> - Play a lesson pack or JSON lesson file without generating anything");
+    println!("  export    - Export the last lesson as a shareable .coddy.json pack");
+    println!("              --output <path>  Where to write it (defaults to <topic>.coddy.json)");
     println!("  progress  - Show your learning progress");
+    println!("              --json  Print the saved progress as JSON");
+    println!("              --ratings  Show your lesson ratings by model and topic");
+    println!("  import <file> - Merge progress exported with 'progress --json' on another machine");
+    println!("              --strategy <union|theirs|ours>  Which side wins for journeys, the lesson in progress and preferences (default union)");
+    println!("              --dry-run  Show what would change without saving");
+    println!("              --yes      Save without asking");
+    println!("  bench     - Compare local models on generation speed and output quality");
+    println!("              --models <a,b,...>  Models to compare (defaults to all installed)");
+    println!("  compare-models - Generate the same lesson with two models and compare the results");
+    println!("              --a <model> --b <model>  Models to compare");
+    println!("              --topic <topic> --language <javascript|cpp|rust>  Lesson to generate (optional)");
+    println!("  selftest  - Check that lesson files, compilers and grading work on this machine");
+    println!("  explain <topic> - Explain a topic in a few sentences and one example, without a lesson");
+    println!("              --language <javascript|cpp|rust>  Language to explain it in (defaults to your saved language)");
+    println!("              --refresh  Ask the model again instead of showing the saved explanation");
+    println!("  roadmap   - Show a language's whole curriculum, with the topics you've done ticked off");
+    println!("              --language <javascript|cpp|rust>  Curriculum to show (defaults to your saved language)");
+    println!("              --format <text|md>  'md' writes a Markdown document with a checkbox per topic");
+    println!("              --output <file>  Write the roadmap to a file instead of printing it");
+    println!("  prefetch  - Generate and cache a curriculum's lessons for offline study");
+    println!("              --language <javascript|cpp|rust>  Curriculum to prefetch");
+    println!("              --stage <N>  Only prefetch this stage (defaults to every stage)");
+    println!("  onboard   - Check your setup and take the first-run tour again");
+    println!("  prefs     - Set the language, difficulty and lesson type 'start' picks by default");
+    println!("              --clear  Forget the saved defaults");
+    println!("  settings  - Show the configuration in effect and where each value comes from");
     println!("  help      - Show this help message");
     println!();
+    println!("Options:");
+    println!("  --quiet   Hide the banner, decorative rules and spinners (works with any command)");
+    println!("  --ascii   Draw borders, spinners and symbols with plain ASCII (works with any command)");
+    println!("  --home <DIR>  Keep progress and caches in DIR instead of ~/.cursed-coddy (works with any command)");
+    println!();
     println!("{}", Colors::warning("Learning Journey:"));
     println!("  A structured curriculum that guides you from basics to advanced topics.");
     println!("  Progresses automatically through stages, scaling difficulty over time.");
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Exit codes scripts can rely on; keep the table in the README in sync
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const CONNECTION: i32 = 2;
+pub const TOOLCHAIN_MISSING: i32 = 3;
+pub const CANCELLED: i32 = 4;
+pub const EXERCISES_NOT_PASSED: i32 = 5;
+
+// Set when a lesson finishes with skipped exercises, which still counts as completed
+static EXERCISES_SKIPPED: AtomicBool = AtomicBool::new(false);
+
+/// An error that ends the program with a specific exit code
+#[derive(Debug)]
+pub struct ExitError {
+    code: i32,
+    message: String,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+fn exit_error(code: i32, message: impl fmt::Display) -> anyhow::Error {
+    ExitError { code, message: message.to_string() }.into()
+}
+
+/// Ollama couldn't be reached or didn't answer
+pub fn connection_error(message: impl fmt::Display) -> anyhow::Error {
+    exit_error(CONNECTION, message)
+}
+
+/// A compiler or runtime needed for an exercise isn't installed or doesn't work
+pub fn toolchain_error(message: impl fmt::Display) -> anyhow::Error {
+    exit_error(TOOLCHAIN_MISSING, message)
+}
+
+/// The learner cancelled a prompt or paused the lesson
+pub fn cancelled(message: impl fmt::Display) -> anyhow::Error {
+    exit_error(CANCELLED, message)
+}
+
+/// A failed prompt: Esc or Ctrl+C counts as cancelling, anything else (such as
+/// stdin not being a terminal) is a plain failure
+pub fn prompt_error(what: &str, e: inquire::InquireError) -> anyhow::Error {
+    match e {
+        inquire::InquireError::OperationCanceled | inquire::InquireError::OperationInterrupted => {
+            exit_error(CANCELLED, format!("{} cancelled: {}", what, e))
+        }
+        inquire::InquireError::NotTTY => {
+            exit_error(FAILURE, format!("{} needs an interactive terminal, but stdin is not one", what))
+        }
+        _ => exit_error(FAILURE, format!("{} failed: {}", what, e)),
+    }
+}
+
+pub fn is_toolchain_missing(e: &anyhow::Error) -> bool {
+    e.chain()
+        .any(|cause| cause.downcast_ref::<ExitError>().is_some_and(|exit| exit.code == TOOLCHAIN_MISSING))
+}
+
+/// Whether the learner cancelled, as opposed to something failing
+pub fn is_cancelled(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| cause.downcast_ref::<ExitError>().is_some_and(|exit| exit.code == CANCELLED))
+}
+
+pub fn set_exercises_skipped() {
+    EXERCISES_SKIPPED.store(true, Ordering::Relaxed);
+}
+
+/// Exit code for the result of a whole run
+pub fn for_result(result: &anyhow::Result<()>) -> i32 {
+    match result {
+        Ok(()) if EXERCISES_SKIPPED.load(Ordering::Relaxed) => EXERCISES_NOT_PASSED,
+        Ok(()) => SUCCESS,
+        Err(e) => e
+            .chain()
+            .find_map(|cause| {
+                if let Some(exit) = cause.downcast_ref::<ExitError>() {
+                    Some(exit.code)
+                } else if cause.is::<reqwest::Error>() {
+                    Some(CONNECTION)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(FAILURE),
+    }
+}
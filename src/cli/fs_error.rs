@@ -0,0 +1,34 @@
+use crate::execution::FileManager;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// Turns a failed file or directory write into an error that says what to do
+/// when the cause is something the learner can fix (a full disk or a folder
+/// they can't write to). `what` describes the write, e.g. "Failed to write
+/// exercise file"; the io::Error stays in the chain as the cause.
+pub fn write_error(e: io::Error, what: &str, path: &Path) -> anyhow::Error {
+    // Exercise files can be moved with CURSED_CODDY_WORKDIR. Progress can be moved too
+    // (--home, CURSED_CODDY_HOME), but the data directory is checked up front with
+    // that advice, so only work directory paths get a hint here.
+    let workdir_hint = if path.starts_with(FileManager::work_dir()) {
+        ", or set CURSED_CODDY_WORKDIR to a folder on another disk"
+    } else {
+        ""
+    };
+
+    let advice = match e.kind() {
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded | ErrorKind::WriteZero => format!(
+            "no space left on device while writing {}. Free up some space{}.",
+            path.display(),
+            workdir_hint
+        ),
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => format!(
+            "not allowed to write {}. Check the permissions of {}{}.",
+            path.display(),
+            path.parent().unwrap_or(path).display(),
+            workdir_hint
+        ),
+        _ => return anyhow::Error::new(e).context(what.to_string()),
+    };
+    anyhow::Error::new(e).context(format!("{}: {}", what, advice))
+}
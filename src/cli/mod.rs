@@ -1,3 +1,7 @@
 pub mod banner;
 pub mod commands;
 pub mod colors;
+pub mod exit_code;
+pub mod fs_error;
+pub mod output;
+pub mod timing;
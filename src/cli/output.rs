@@ -0,0 +1,71 @@
+use crate::config::env_vars;
+use std::sync::atomic::{AtomicBool, Ordering};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// Set once from --quiet (or a machine-readable mode) before any output is printed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// Set once from --ascii or CURSED_CODDY_ASCII before any output is printed
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Suppress the banner, decorative rules, screen clearing and spinners
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Draw borders, spinners and symbols with plain ASCII, for terminals and fonts
+/// without box-drawing glyphs and for screen readers
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// CURSED_CODDY_ASCII=true (or CODDY_ASCII=true) turns ASCII mode on without the flag
+pub fn ascii_from_env() -> bool {
+    env_vars::value("CURSED_CODDY_ASCII")
+        .is_some_and(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+}
+
+/// The Unicode symbol, or its ASCII stand-in in ASCII mode
+pub fn glyph(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if is_ascii() {
+        ascii
+    } else {
+        unicode
+    }
+}
+
+/// Columns the text takes up in a terminal: wide CJK characters and most emoji
+/// take two, combining marks none
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Columns a single character takes up
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// Reads single keys instead of lines while it's held; raw mode is undone however
+/// the caller returns
+pub struct RawMode;
+
+impl RawMode {
+    pub fn enable() -> std::io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Milliseconds spent in each phase during the current lesson, for its summary
+static GENERATION_MS: AtomicU64 = AtomicU64::new(0);
+static COMPILATION_MS: AtomicU64 = AtomicU64::new(0);
+static TESTS_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Where a session's time goes: waiting for the model, building exercises, or running them
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Generation,
+    Compilation,
+    Tests,
+}
+
+impl Phase {
+    const ALL: [Phase; 3] = [Phase::Generation, Phase::Compilation, Phase::Tests];
+
+    fn total(self) -> &'static AtomicU64 {
+        match self {
+            Phase::Generation => &GENERATION_MS,
+            Phase::Compilation => &COMPILATION_MS,
+            Phase::Tests => &TESTS_MS,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Phase::Generation => "Generation",
+            Phase::Compilation => "Compilation",
+            Phase::Tests => "Tests",
+        }
+    }
+}
+
+pub fn record(phase: Phase, elapsed: Duration) {
+    let millis = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+    phase.total().fetch_add(millis, Ordering::Relaxed);
+}
+
+/// Starts the breakdown over, so a lesson's summary doesn't count an earlier lesson
+/// of the same run, such as the previous one in a journey
+pub fn reset() {
+    for phase in Phase::ALL {
+        phase.total().store(0, Ordering::Relaxed);
+    }
+}
+
+/// Runs `f` and adds the time it took to the phase, whether or not it succeeded
+pub fn measure<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record(phase, started.elapsed());
+    result
+}
+
+/// "Generation: 48s, Compilation: 3.2s, Tests: 1.1s", or None if nothing was timed yet
+pub fn breakdown() -> Option<String> {
+    let totals = Phase::ALL.map(|phase| Duration::from_millis(phase.total().load(Ordering::Relaxed)));
+    if totals.iter().all(Duration::is_zero) {
+        return None;
+    }
+    let parts: Vec<String> = Phase::ALL
+        .iter()
+        .zip(totals)
+        .map(|(phase, total)| format!("{}: {}", phase.label(), format_duration(total)))
+        .collect();
+    Some(parts.join(", "))
+}
+
+// Tenths of a second for short phases, where they make the difference
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else if secs >= 10 {
+        format!("{}s", secs)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::{Difficulty, Language, LessonType};
+use crate::config::{Difficulty, Language, LessonType, MAX_EXERCISES};
 use serde::{Deserialize, Serialize};
 
 #[allow(dead_code)]
@@ -18,6 +18,18 @@ pub struct Stage {
     pub lesson_type: LessonType,
 }
 
+impl Stage {
+    /// Topic of a lesson that covers the whole stage at once
+    pub fn combined_topic(&self) -> String {
+        format!("{} ({})", self.name, self.topics.join(", "))
+    }
+
+    /// Exercises in a combined stage lesson: one per topic, and at least as many as a Long lesson
+    pub fn combined_exercise_count(&self) -> usize {
+        self.topics.len().clamp(LessonType::Long.exercise_count(), MAX_EXERCISES)
+    }
+}
+
 #[allow(dead_code)]
 impl Curriculum {
     pub fn get_for_language(language: Language) -> Self {
@@ -275,4 +287,21 @@ impl Curriculum {
     pub fn total_stages(&self) -> usize {
         self.stages.len()
     }
+
+    /// Topics from the stages at the given difficulty, or every topic if none match
+    pub fn topics_for_difficulty(&self, difficulty: Difficulty) -> Vec<&str> {
+        let matching: Vec<&str> = self
+            .stages
+            .iter()
+            .filter(|stage| stage.difficulty == difficulty)
+            .flat_map(|stage| stage.topics.iter().map(String::as_str))
+            .collect();
+        if !matching.is_empty() {
+            return matching;
+        }
+        self.stages
+            .iter()
+            .flat_map(|stage| stage.topics.iter().map(String::as_str))
+            .collect()
+    }
 }
@@ -0,0 +1,13 @@
+/// Reads a CURSED_CODDY_* setting, falling back to its shorter CODDY_* name (the
+/// variable without "CURSED_"). Returns the variable that was set and its value;
+/// blank values count as unset. The full name wins when both are set.
+pub fn lookup(var: &'static str) -> Option<(&'static str, String)> {
+    std::iter::once(var)
+        .chain(var.strip_prefix("CURSED_"))
+        .find_map(|name| std::env::var(name).ok().filter(|v| !v.trim().is_empty()).map(|v| (name, v)))
+}
+
+/// The value of a CURSED_CODDY_* setting or its CODDY_* alias, if either is set
+pub fn value(var: &'static str) -> Option<String> {
+    lookup(var).map(|(_, value)| value)
+}
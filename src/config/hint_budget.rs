@@ -0,0 +1,72 @@
+/// How many of an exercise's hints the learner may reveal. With a limit, hints
+/// start hidden and are revealed one at a time with 'hint'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintBudget {
+    /// Every hint is shown with the exercise
+    Unlimited,
+    Limited(usize),
+}
+
+impl HintBudget {
+    /// CURSED_CODDY_HINT_BUDGET (a number, or "unlimited"); unlimited when unset or invalid
+    pub fn from_env() -> Self {
+        std::env::var("CURSED_CODDY_HINT_BUDGET")
+            .ok()
+            .and_then(|value| Self::parse(&value).ok())
+            .unwrap_or(HintBudget::Unlimited)
+    }
+
+    /// Parses "unlimited" or a number of hints per exercise, as given to --hint-budget
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim().to_lowercase();
+        if value == "unlimited" {
+            return Ok(HintBudget::Unlimited);
+        }
+        value
+            .parse()
+            .map(HintBudget::Limited)
+            .map_err(|_| format!("expected a number of hints or 'unlimited', got '{}'", value))
+    }
+
+    /// How many hints of an exercise can be revealed
+    pub fn allowance(&self, hint_count: usize) -> usize {
+        match self {
+            HintBudget::Unlimited => hint_count,
+            HintBudget::Limited(limit) => hint_count.min(*limit),
+        }
+    }
+}
+
+impl std::fmt::Display for HintBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HintBudget::Unlimited => write!(f, "unlimited"),
+            HintBudget::Limited(limit) => write!(f, "{} per exercise", limit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_takes_a_number_or_unlimited() {
+        assert_eq!(HintBudget::parse("unlimited"), Ok(HintBudget::Unlimited));
+        assert_eq!(HintBudget::parse(" Unlimited "), Ok(HintBudget::Unlimited));
+        assert_eq!(HintBudget::parse("2"), Ok(HintBudget::Limited(2)));
+        assert_eq!(HintBudget::parse("0"), Ok(HintBudget::Limited(0)));
+
+        let error = HintBudget::parse("lots").unwrap_err();
+        assert!(error.contains("'lots'"), "{}", error);
+        assert!(HintBudget::parse("-1").is_err());
+        assert!(HintBudget::parse("").is_err());
+    }
+
+    #[test]
+    fn allowance_never_exceeds_the_hints_there_are() {
+        assert_eq!(HintBudget::Unlimited.allowance(3), 3);
+        assert_eq!(HintBudget::Limited(1).allowance(3), 1);
+        assert_eq!(HintBudget::Limited(5).allowance(3), 3);
+    }
+}
@@ -1,13 +1,42 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 pub enum Language {
+    #[value(name = "javascript", alias = "js")]
     JavaScript,
     Cpp,
     Rust,
 }
 
 impl Language {
+    pub const ALL: [Language; 3] = [Language::JavaScript, Language::Cpp, Language::Rust];
+
+    /// Languages learners can pick, configurable via CURSED_CODDY_LANGUAGES as a
+    /// comma-separated list (e.g. "javascript,rust"). Unknown names are ignored,
+    /// and an unset or empty list enables every language.
+    pub fn enabled() -> Vec<Language> {
+        let configured: Vec<Language> = std::env::var("CURSED_CODDY_LANGUAGES")
+            .map(|v| v.split(',').filter_map(Language::from_name).collect())
+            .unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .filter(|l| configured.is_empty() || configured.contains(l))
+            .collect()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        Self::enabled().contains(self)
+    }
+
+    fn from_name(name: &str) -> Option<Language> {
+        match name.trim().to_lowercase().as_str() {
+            "javascript" | "js" => Some(Language::JavaScript),
+            "cpp" | "c++" => Some(Language::Cpp),
+            "rust" | "rs" => Some(Language::Rust),
+            _ => None,
+        }
+    }
+
     pub fn file_extension(&self) -> &'static str {
         match self {
             Language::JavaScript => "js",
@@ -16,6 +45,15 @@ impl Language {
         }
     }
 
+    /// Lowercase name used in file and folder names: javascript, cpp or rust
+    pub fn key(&self) -> &'static str {
+        match self {
+            Language::JavaScript => "javascript",
+            Language::Cpp => "cpp",
+            Language::Rust => "rust",
+        }
+    }
+
     pub fn display_name(&self) -> &'static str {
         match self {
             Language::JavaScript => "JavaScript",
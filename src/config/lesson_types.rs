@@ -1,6 +1,36 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Upper bound for a per-lesson exercise count override
+pub const MAX_EXERCISES: usize = 10;
+
+/// Whether lessons include a multiple-choice quiz after the exercises; on by
+/// default, CURSED_CODDY_QUIZ=false leaves it out for coding practice only
+pub fn quiz_enabled() -> bool {
+    std::env::var("CURSED_CODDY_QUIZ")
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+        .unwrap_or(true)
+}
+
+/// Whether each exercise's header shows how far through the lesson it is, with a
+/// bar of one cell per exercise; on by default, CURSED_CODDY_EXERCISE_PROGRESS=false
+/// goes back to a plain heading between rules
+pub fn exercise_progress_enabled() -> bool {
+    std::env::var("CURSED_CODDY_EXERCISE_PROGRESS")
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+        .unwrap_or(true)
+}
+
+/// Failed attempts after which an exercise can be left unsolved and marked for
+/// review; CURSED_CODDY_REVIEW_AFTER, 5 by default
+pub fn review_after_attempts() -> usize {
+    std::env::var("CURSED_CODDY_REVIEW_AFTER")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(5)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 pub enum LessonType {
     Short,
     Medium,
@@ -31,6 +61,28 @@ impl LessonType {
             LessonType::Long => 5,
         }
     }
+
+    /// Roughly how long a lesson of this type takes, for planning a study session
+    pub fn target_minutes(&self) -> usize {
+        match self {
+            LessonType::Short => 5,
+            LessonType::Medium => 15,
+            LessonType::Long => 30,
+        }
+    }
+
+    /// The lesson type and exercise count that fill about `minutes`: the type
+    /// whose own length is closest sets the concept depth, and its exercises
+    /// are scaled to the time at the same pace
+    pub fn for_minutes(minutes: usize) -> (LessonType, usize) {
+        let minutes = minutes.max(1);
+        let lesson_type = [LessonType::Short, LessonType::Medium, LessonType::Long]
+            .into_iter()
+            .min_by_key(|t| t.target_minutes().abs_diff(minutes))
+            .unwrap_or(LessonType::Short);
+        let exercises = (minutes * lesson_type.exercise_count() + lesson_type.target_minutes() / 2) / lesson_type.target_minutes();
+        (lesson_type, exercises.clamp(1, MAX_EXERCISES))
+    }
 }
 
 impl std::fmt::Display for LessonType {
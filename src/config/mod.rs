@@ -1,9 +1,14 @@
 pub mod curriculum;
 pub mod difficulties;
+pub mod env_vars;
+pub mod hint_budget;
 pub mod languages;
 pub mod lesson_types;
 pub mod ruleset;
+pub mod verbosity;
 
 pub use difficulties::*;
+pub use hint_budget::*;
 pub use languages::*;
 pub use lesson_types::*;
+pub use verbosity::*;
@@ -1,16 +1,220 @@
-use crate::config::{Difficulty, Language, LessonType};
+use crate::config::curriculum::Stage;
+use crate::config::{Difficulty, Language, LessonType, Verbosity};
+
+// Topics about defining types (structs/enums/traits/OOP) tend to produce exercises
+// that can't be graded by stdout, so their prompts ask for a printing main explicitly
+const STRUCTURAL_TOPIC_WORDS: &[&str] = &[
+    "struct", "enum", "trait", "impl", "method", "class", "object", "oop",
+    "inheritance", "polymorphism", "interface", "constructor", "encapsulation",
+];
+
+const STRUCTURAL_TOPIC_RULES: &str = r#"STRUCTURAL TOPIC RULES (this topic defines types, which stdout grading can't see directly):
+- Every exercise MUST include a `main` (or top-level script code for JavaScript) that creates values of the types it defines, calls their methods/functions, and PRINTS the results
+- Say in the description exactly what `main` must print, e.g. "Create a Rectangle 3x4 and print its area: Area: 12"
+- Grade observable behavior (return values, computed results, state after method calls), not the mere existence of a type or method
+- Do NOT ask the learner to just print a struct's fields back or rely on debug formatting such as {:?} or console.dir
+- Make "example_output" and every test case "output" the exact text that `main` prints
+
+"#;
+
+// Extra instructions for topics models often get wrong, added when the topic
+// mentions one of the keywords (and, if set, the lesson is in that language)
+struct TopicNote {
+    language: Option<Language>,
+    keywords: &'static [&'static str],
+    note: &'static str,
+}
+
+const TOPIC_NOTES: &[TopicNote] = &[
+    TopicNote {
+        language: Some(Language::Rust),
+        keywords: &["ownership", "move"],
+        note: "Emphasize move vs. copy semantics: which types are Copy, what happens to a String after `let b = a;`, and show the exact E0382 \"borrow of moved value\" error",
+    },
+    TopicNote {
+        language: Some(Language::Rust),
+        keywords: &["borrowing", "borrow", "reference"],
+        note: "State the borrowing rules exactly (any number of `&T` OR one `&mut T`, never both at once) and show the E0502 error from mixing them; every exercise must compile under these rules",
+    },
+    TopicNote {
+        language: Some(Language::Rust),
+        keywords: &["lifetime"],
+        note: "Explain that lifetime annotations describe how long references are valid and never change it; show the E0106 missing lifetime specifier error and use the `longest(x, y)` function as the first example",
+    },
+    TopicNote {
+        language: Some(Language::Cpp),
+        keywords: &["pointer"],
+        note: "Distinguish pointers from references, always initialize pointers (use nullptr), and don't use raw new/delete in exercises unless the topic is manual memory management",
+    },
+    TopicNote {
+        language: Some(Language::JavaScript),
+        keywords: &["closure"],
+        note: "Show the classic loop-with-var pitfall next to the let version, and make exercises print what the closure returns rather than the function itself",
+    },
+];
+
+// Added to the lesson's JSON format when the post-lesson quiz is on
+const QUIZ_FORMAT: &str = r#",
+  "quiz": [
+    {"question": "A conceptual question (why or when, not syntax trivia)", "options": ["Option A", "Option B", "Option C", "Option D"], "correct": 0, "explanation": "Why the correct option is right and the others are wrong"}
+  ]
+       Include 2-3 multiple-choice "quiz" questions that check understanding of the concept, such as why it was designed this way. "correct" is the 0-based index of the right option; vary its position between questions."#;
+
+// Added to the exercise format for beginner lessons, so the learner doesn't start from a blank file
+const STARTER_CODE_RULE: &str = r#"
+For this beginner lesson, also give each exercise a "starter_code" string: the file the learner starts from, such as `main` with any imports it needs and a `// TODO:` comment where the learner writes the part the exercise is about (e.g. "// TODO: print your name here"). It must compile and run, but must NOT contain the solution."#;
+
+const FUNCTION_EXERCISE_RULE: &str = r#"
+An exercise that practises one calculation or algorithm (e.g. "return the nth Fibonacci number") can instead have the learner write a single function: add "function": {"signature": "fn fib(n: u32) -> u32", "cases": [{"call": "fib(10)", "expected": "55"}]} with 2-3 cases, where "expected" is what {:?} prints for the right result (e.g. "55", "\"hi\"" or "[1, 2]"). The program around the function is generated, so leave out "test_cases" and don't ask for main, input or printing in the description."#;
+
+const CLEAN_CODE_RULE: &str = r#"
+For an exercise about idiomatic or tidy code (e.g. removing an unused variable, a needless `mut` or an unused import), add "require_clean": true: passing it then also takes code that compiles without warnings. Leave it out for other exercises."#;
 
 pub struct Ruleset;
 
 impl Ruleset {
+    /// Whether the topic is about structs, enums, traits, classes or other type definitions
+    pub fn is_structural_topic(topic: &str) -> bool {
+        Self::topic_mentions(topic, STRUCTURAL_TOPIC_WORDS)
+    }
+
+    // Whether any word of the topic is one of the keywords; a keyword of several
+    // words matches when the topic contains it
+    fn topic_mentions(topic: &str, keywords: &[&str]) -> bool {
+        let topic = topic.to_lowercase();
+        if keywords.iter().any(|k| k.contains(' ') && topic.contains(k)) {
+            return true;
+        }
+        topic
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| {
+                // Accept plurals such as "structs" and "classes"
+                [Some(word), word.strip_suffix('s'), word.strip_suffix("es")]
+                    .into_iter()
+                    .flatten()
+                    .any(|w| keywords.contains(&w))
+            })
+    }
+
+    /// Extra guidance for the topic: the built-in notes for hard topics plus any
+    /// from ~/.cursed-coddy/topic_notes.txt, one `keyword: instructions` per line.
+    /// A keyword in that file replaces the built-in note that has it.
+    fn topic_notes(language: Language, topic: &str) -> String {
+        let user_notes = Self::user_topic_notes();
+        let mut notes: Vec<String> = TOPIC_NOTES
+            .iter()
+            .filter(|n| n.language.is_none_or(|l| l == language))
+            .filter(|n| !n.keywords.iter().any(|k| user_notes.iter().any(|(u, _)| u == k)))
+            .filter(|n| Self::topic_mentions(topic, n.keywords))
+            .map(|n| n.note.to_string())
+            .collect();
+        notes.extend(
+            user_notes
+                .into_iter()
+                .filter(|(keyword, _)| Self::topic_mentions(topic, &[keyword.as_str()]))
+                .map(|(_, note)| note),
+        );
+
+        if notes.is_empty() {
+            return String::new();
+        }
+        let mut section = "TOPIC-SPECIFIC GUIDANCE:\n".to_string();
+        for note in notes {
+            section.push_str(&format!("- {}\n", note));
+        }
+        section.push('\n');
+        section
+    }
+
+    fn user_topic_notes() -> Vec<(String, String)> {
+        let content = match crate::progress::data_dir()
+            .ok()
+            .and_then(|dir| std::fs::read_to_string(dir.join("topic_notes.txt")).ok())
+        {
+            Some(content) => content,
+            None => return Vec::new(),
+        };
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once(':'))
+            .map(|(keyword, note)| (keyword.trim().to_lowercase(), note.trim().to_string()))
+            .filter(|(keyword, note)| !keyword.is_empty() && !note.is_empty())
+            .collect()
+    }
+
+    fn starter_code_rule(difficulty: Difficulty) -> &'static str {
+        if difficulty == Difficulty::Beginner {
+            STARTER_CODE_RULE
+        } else {
+            ""
+        }
+    }
+
+    // Warnings are the compiler's, so JavaScript has none to check, and beginners
+    // have enough to learn without them
+    fn clean_code_rule(language: Language, difficulty: Difficulty) -> &'static str {
+        if language != Language::JavaScript && difficulty != Difficulty::Beginner {
+            CLEAN_CODE_RULE
+        } else {
+            ""
+        }
+    }
+
+    // Only Rust builds the program around a function exercise
+    fn function_exercise_rule(language: Language) -> &'static str {
+        if language == Language::Rust {
+            FUNCTION_EXERCISE_RULE
+        } else {
+            ""
+        }
+    }
+
+    fn structural_topic_rules(topic: &str) -> &'static str {
+        if Self::is_structural_topic(topic) {
+            STRUCTURAL_TOPIC_RULES
+        } else {
+            ""
+        }
+    }
+
     pub fn generate_prompt(
         language: Language,
         difficulty: Difficulty,
         lesson_type: LessonType,
         topic: &str,
+        exercise_count: usize,
+        with_quiz: bool,
+        verbosity: Verbosity,
     ) -> String {
         let concept_count = lesson_type.concept_count();
-        let exercise_count = lesson_type.exercise_count();
+        let concept_sentences = verbosity.concept_sentences();
+        let (min_examples, max_examples) = verbosity.code_examples();
+        let (example_count, example_minimum) = if min_examples == max_examples {
+            (format!("exactly {} code example(s)**", min_examples), format!("exactly {} example(s).", min_examples))
+        } else {
+            let fewer = min_examples - 1;
+            (
+                format!("at least {} code examples** ({}-{} total)", min_examples, min_examples, max_examples),
+                format!(
+                    "at least {} examples. Do not provide just {} example{}.",
+                    min_examples,
+                    fewer,
+                    if fewer == 1 { "" } else { "s" }
+                ),
+            )
+        };
+        // The JSON sample shows a second example only when one may be given
+        let second_example = if max_examples > 1 {
+            r#",
+    {
+      "code": "another example code here",
+      "explanation": "Detailed explanation of this second example, showing a different approach or variation."
+    }"#
+        } else {
+            ""
+        };
 
         format!(
             r#"You are a coding education assistant similar to Codecademy and Coddy. Generate an educational lesson following these rules:
@@ -21,17 +225,17 @@ LESSON TYPE: {}
 TOPIC: {}
 
 TEACHING STYLE REQUIREMENTS:
-1. **Concept Introduction**: Provide a clear, beginner-friendly explanation of what the concept is (5-7 sentences). Include:
+1. **Concept Introduction**: Provide a clear, beginner-friendly explanation of what the concept is ({} sentences). Include:
    - What the concept is and why it exists
    - How it differs from other languages (if applicable)
    - Why this design choice was made (the reasoning behind it)
    - Common misconceptions or differences from languages like JavaScript, Python, C++, etc.
    - Real-world context: when and why you'd use this feature
-2. **Step-by-Step Explanation**: Break down how the concept works in simple steps (4-6 steps). Each step should explain:
+2. **Step-by-Step Explanation**: Break down how the concept works in simple steps ({} steps). Each step should explain:
    - What happens at that step
    - Why it works that way
    - How it differs from similar concepts in other languages (if applicable)
-3. **Code Examples**: **CRITICAL: You MUST provide at least 2 code examples** (2-3 total). Each example should have detailed line-by-line explanations. Include:
+3. **Code Examples**: **CRITICAL: You MUST provide {}. Each example should have detailed line-by-line explanations. Include:
    - What each line does
    - Why it's written that way
    - What would happen if you tried to do it differently
@@ -41,7 +245,7 @@ TEACHING STYLE REQUIREMENTS:
    - What each part means
    - Common variations
    - What happens if you omit parts (e.g., what if you forget `mut`?)
-5. **Common Patterns**: Show 2-3 common use cases or patterns for this concept. Explain:
+5. **Common Patterns**: Show {} or patterns for this concept. Explain:
    - When to use each pattern
    - Why that pattern is preferred
    - What problems it solves
@@ -62,6 +266,8 @@ TEACHING STYLE REQUIREMENTS:
      * Test cases MUST validate what the exercise asks the student to do
      * If the exercise asks to print something, test cases should check that exact output
      * If the exercise asks to calculate something, test cases should verify the calculation
+     * Give each test case a short "label" describing what it checks (e.g., "handles zero", "handles negative input")
+     * If the program should read COMMAND-LINE ARGUMENTS instead of stdin, put them in an "args" array on each test case (e.g., "args": ["5", "10"]) and say so in the description
      * **IMPORTANT**: If test cases have different outputs but no input, this is an error. Either:
        - Use the same expected output for all test cases (for exercises without input), OR
        - Provide input values for each test case (for exercises with input)
@@ -90,9 +296,9 @@ EDUCATIONAL FOCUS:
   * For Rust: Cover both rustc direct compilation and Cargo project management
   * For JavaScript: Explain Node.js execution, no compilation needed but show how to run scripts
 
-OUTPUT FORMAT (JSON):
+{}{}OUTPUT FORMAT (JSON):
 {{
-  "concept": "Clear, detailed explanation (5-7 sentences) of what the concept is, why it exists, how it differs from other languages, and the reasoning behind the design choice. Include comparisons to JavaScript, Python, C++, etc. when relevant. For Rust immutability, explain that `let` without `mut` creates an immutable variable that CANNOT be changed after assignment (unlike JavaScript/Python where variables are mutable by default), and explain WHY Rust made this design choice (memory safety, preventing bugs, etc.).",
+  "concept": "Clear, detailed explanation ({} sentences) of what the concept is, why it exists, how it differs from other languages, and the reasoning behind the design choice. Include comparisons to JavaScript, Python, C++, etc. when relevant. For Rust immutability, explain that `let` without `mut` creates an immutable variable that CANNOT be changed after assignment (unlike JavaScript/Python where variables are mutable by default), and explain WHY Rust made this design choice (memory safety, preventing bugs, etc.).",
   "step_by_step": [
     "Step 1: Detailed explanation including what happens and why",
     "Step 2: Detailed explanation including what happens and why",
@@ -102,13 +308,9 @@ OUTPUT FORMAT (JSON):
     {{
       "code": "example code here",
       "explanation": "Detailed line-by-line explanation of what this code does, why it's written that way, what would happen if done differently, and comparisons to other languages when relevant. For Rust, show what compiler error you get if you try to change an immutable variable."
-    }},
-    {{
-      "code": "another example code here",
-      "explanation": "Detailed explanation of this second example, showing a different approach or variation."
-    }}
+    }}{}
   ],
-   **CRITICAL: The "code_examples" array MUST contain at least 2 examples. Do not provide just 1 example.**
+   **CRITICAL: The "code_examples" array MUST contain {}**
   "syntax_guide": "Detailed explanation of the syntax with examples. Include what each part means, what happens if you omit parts, and comparisons to other languages. For Rust immutability, explain that `let` without `mut` creates an immutable variable that CANNOT be changed (unlike JavaScript/Python where variables are mutable by default), and show what error you get if you try.",
   "common_patterns": [
     "Pattern 1: Detailed description explaining when to use it, why it's preferred, and what problems it solves",
@@ -122,13 +324,16 @@ OUTPUT FORMAT (JSON):
       "example_input": "example input (use empty string \"\" if no input needed, but ALWAYS include this field)",
       "example_output": "expected output (show what the code should produce/print, ALWAYS include this field)",
       "test_cases": [
-        {{"input": "...", "output": "..."}},
-        {{"input": "...", "output": "..."}},
-        {{"input": "...", "output": "..."}}
+        {{"input": "...", "output": "...", "label": "what this case checks"}},
+        {{"input": "...", "output": "...", "label": "what this case checks"}},
+        {{"input": "...", "output": "...", "label": "what this case checks"}}
       ]
        **CRITICAL: The "test_cases" array MUST contain at least 2-3 test cases. Test cases MUST validate the exercise requirements and align with the description and hints.**
+       If the output varies between runs (random numbers, timestamps, memory addresses), also add "output_pattern": a regex the whole output must match, such as "Random number: \\d+". Keep "output" as one example of a matching output.
+       If the program must end with a non-zero exit code for a case (e.g. "exit with code 1 on invalid input"), add "expected_exit_code" to that test case, such as "expected_exit_code": 1.
+       If the exercise writes its result to a file (e.g. "write the results to output.txt"), add "expected_file" to each test case, such as "expected_file": {{"name": "output.txt", "contents": "..."}}, and say in the description that the file must be opened by that relative name.{}{}{}
     }}
-  ]
+  ]{}
 }}
 
        **CRITICAL: The "exercises" array is REQUIRED and must contain at least {} exercise(s). Do not omit this field.**
@@ -145,12 +350,367 @@ OUTPUT FORMAT (JSON):
             difficulty.display_name(),
             lesson_type.display_name(),
             topic,
+            concept_sentences,
+            verbosity.step_count(),
+            example_count,
+            verbosity.common_patterns(),
             exercise_count,
             exercise_count, // Duplicate for emphasis
             lesson_type.display_name(),
             concept_count,
             difficulty.display_name(),
+            Self::structural_topic_rules(topic),
+            Self::topic_notes(language, topic),
+            concept_sentences,
+            second_example,
+            example_minimum,
+            Self::starter_code_rule(difficulty),
+            Self::function_exercise_rule(language),
+            Self::clean_code_rule(language, difficulty),
+            if with_quiz { QUIZ_FORMAT } else { "" },
             exercise_count // Final emphasis
         )
     }
+
+    /// One lesson covering every topic of a curriculum stage: the usual lesson prompt
+    /// for the combined topic, plus instructions to tie the topics together and
+    /// spread the exercises across them
+    pub fn generate_stage_prompt(
+        language: Language,
+        stage: &Stage,
+        difficulty: Difficulty,
+        exercise_count: usize,
+        with_quiz: bool,
+        verbosity: Verbosity,
+    ) -> String {
+        let prompt = Self::generate_prompt(
+            language,
+            difficulty,
+            LessonType::Long,
+            &stage.combined_topic(),
+            exercise_count,
+            with_quiz,
+            verbosity,
+        );
+        let topics: String = stage.topics.iter().map(|t| format!("- {}\n", t)).collect();
+        let section = format!(
+            r#"COMBINED STAGE LESSON:
+This single lesson replaces the separate lessons of the "{}" stage ({}), so it must cover ALL of these topics:
+{}- Write ONE integrated concept that explains each topic and how they relate and build on each other, not a list of separate mini-lessons
+- Make the steps, code examples and patterns use several of the topics together where it makes sense
+- Spread the exercises across the topics so each one is practised (as far as the exercise count allows), and make the last exercise combine several of them
+- Name the topic(s) each exercise practises in its title, e.g. "Loops and Arrays: Sum a List"
+
+"#,
+            stage.name, stage.description, topics
+        );
+
+        // Placed with the other topic guidance, before the output format
+        match prompt.find("OUTPUT FORMAT (JSON):") {
+            Some(index) => format!("{}{}{}", &prompt[..index], section, &prompt[index..]),
+            None => format!("{}\n\n{}", prompt, section),
+        }
+    }
+
+    pub fn generate_exercises_prompt(
+        language: Language,
+        difficulty: Difficulty,
+        topic: &str,
+        concept: &str,
+        exercise_count: usize,
+    ) -> String {
+        format!(
+            r#"You are a coding education assistant similar to Codecademy and Coddy. A lesson has already been written; generate ONLY new practice exercises for it.
+
+LANGUAGE: {}
+DIFFICULTY: {}
+TOPIC: {}
+
+LESSON CONCEPT (already shown to the learner):
+{}
+
+EXERCISE REQUIREMENTS:
+- Create exactly {} exercise(s) that practice the concept above
+- Clear step-by-step instructions that explain WHAT to do and HOW to do it
+- **CRITICAL**: Exercises must produce valid, compilable code that runs successfully
+- **CRITICAL**: If the exercise requires reading input, EXPLICITLY state "Your program should read input from stdin" in the description
+- For beginner exercises, prefer hardcoded values over reading input
+- Detailed, specific hints
+- "example_input": use empty string "" if no input is needed
+- "example_output": ALWAYS show exactly what the program should print
+- **CRITICAL: Each exercise MUST have 2-3 test cases** that match the description exactly
+  * Give each test case a short "label" describing what it checks
+  * If the program reads command-line arguments instead of stdin, put them in an "args" array on each test case
+  * If test cases have no input, they must all expect the same output
+
+{}OUTPUT FORMAT (JSON):
+{{
+  "exercises": [
+    {{
+      "title": "Exercise title",
+      "description": "Detailed step-by-step instructions",
+      "hints": ["Hint 1", "Hint 2"],
+      "example_input": "",
+      "example_output": "expected output",
+      "test_cases": [
+        {{"input": "...", "output": "...", "label": "what this case checks"}},
+        {{"input": "...", "output": "...", "label": "what this case checks"}}
+      ]
+    }}
+  ]
+}}
+
+If a test's output varies between runs (random numbers, timestamps, memory addresses), add "output_pattern" to it: a regex the whole output must match, such as "Random number: \\d+".
+If the program must end with a non-zero exit code for a test (e.g. "exit with code 1 on invalid input"), add "expected_exit_code" to it, such as "expected_exit_code": 1.
+If the exercise writes its result to a file (e.g. "write the results to output.txt"), add "expected_file" to each test, such as "expected_file": {{"name": "output.txt", "contents": "..."}}, and say in the description that the file must be opened by that relative name.{}{}{}
+
+Output ONLY valid JSON - no markdown code fences, no explanatory text before or after. Generate the exercises now:"#,
+            language.display_name(),
+            difficulty.display_name(),
+            topic,
+            concept,
+            exercise_count,
+            Self::structural_topic_rules(topic),
+            Self::starter_code_rule(difficulty),
+            Self::function_exercise_rule(language),
+            Self::clean_code_rule(language, difficulty)
+        )
+    }
+
+    pub fn generate_line_explanation_prompt(
+        language: Language,
+        code: &str,
+        line_number: usize,
+    ) -> String {
+        let line = code.lines().nth(line_number.saturating_sub(1)).unwrap_or("");
+
+        format!(
+            r#"You are a patient coding tutor helping a beginner learn {}.
+
+Here is a code example:
+{}
+
+Explain ONLY line {} of this example:
+{}
+
+Rules:
+- Answer in 2-4 plain sentences
+- Say what the line does and why it is written that way
+- Refer to the surrounding code only when it helps explain this line
+- No markdown, no code fences, no JSON
+
+Explanation:"#,
+            language.display_name(),
+            code,
+            line_number,
+            line.trim()
+        )
+    }
+
+    pub fn generate_preview_prompt(
+        language: Language,
+        difficulty: Difficulty,
+        lesson_type: LessonType,
+        topic: &str,
+        exercise_count: usize,
+    ) -> String {
+        format!(
+            r#"You are a coding education assistant similar to Codecademy and Coddy. A learner is deciding whether to take a lesson. Describe what the lesson would cover.
+
+LANGUAGE: {}
+DIFFICULTY: {}
+LESSON TYPE: {} ({} concept(s), {} exercise(s))
+TOPIC: {}
+
+Rules:
+- Write ONE paragraph of 3-5 plain sentences
+- Say which concepts the lesson introduces and what the exercises will ask the learner to build
+- Mention any prior knowledge the learner should already have
+- No markdown, no code, no JSON
+
+Preview:"#,
+            language.display_name(),
+            difficulty.display_name(),
+            lesson_type.display_name(),
+            lesson_type.concept_count(),
+            exercise_count,
+            topic
+        )
+    }
+
+    /// Asks for a refresher on a topic outside of a lesson: the concept and one short example
+    pub fn generate_topic_reference_prompt(language: Language, topic: &str) -> String {
+        format!(
+            r#"You are a coding education assistant. A learner wants a quick refresher on a topic, not a lesson.
+
+LANGUAGE: {}
+TOPIC: {}
+
+Rules:
+- "concept": 2-4 plain sentences saying what it is and when to use it
+- "example": one short, complete {} program (under 20 lines) that shows it, and one sentence explaining the example
+- No exercises, no quiz
+
+Respond with ONLY this JSON, no prose and no code fences:
+{{"concept": "What it is", "example": {{"code": "The program", "explanation": "What the example shows"}}}}"#,
+            language.display_name(),
+            topic,
+            language.display_name()
+        )
+    }
+
+    /// Asks for only the step-by-step explanation, for a lesson that came back without one
+    pub fn generate_step_by_step_prompt(language: Language, difficulty: Difficulty, topic: &str) -> String {
+        Self::section_prompt(
+            language,
+            difficulty,
+            topic,
+            "Give 3-5 short steps that walk the learner through using this concept, in the order they would apply them.",
+            r#"{"step_by_step": ["First step", "Second step", "Third step"]}"#,
+        )
+    }
+
+    /// Asks for only the syntax guide, for a lesson whose guide was missing or a stub
+    pub fn generate_syntax_guide_prompt(language: Language, difficulty: Difficulty, topic: &str) -> String {
+        Self::section_prompt(
+            language,
+            difficulty,
+            topic,
+            "Write a syntax guide of 2-4 sentences that shows the exact syntax for this concept, with the keywords and symbols in inline code.",
+            r#"{"syntax_guide": "The syntax guide"}"#,
+        )
+    }
+
+    /// Asks for only the common patterns, for a lesson that came back without any
+    pub fn generate_common_patterns_prompt(language: Language, difficulty: Difficulty, topic: &str) -> String {
+        Self::section_prompt(
+            language,
+            difficulty,
+            topic,
+            "Give 3 common patterns for using this concept in real code, each one sentence naming the pattern and when to use it.",
+            r#"{"common_patterns": ["First pattern", "Second pattern", "Third pattern"]}"#,
+        )
+    }
+
+    // A follow-up prompt for one section of a lesson, answered with a one-key JSON object
+    fn section_prompt(language: Language, difficulty: Difficulty, topic: &str, task: &str, shape: &str) -> String {
+        format!(
+            r#"You are a coding education assistant writing one section of a lesson.
+
+LANGUAGE: {}
+DIFFICULTY: {}
+TOPIC: {}
+
+{}
+
+Respond with ONLY this JSON, no prose and no code fences:
+{}"#,
+            language.display_name(),
+            difficulty.display_name(),
+            topic,
+            task,
+            shape
+        )
+    }
+
+    pub fn generate_json_repair_prompt(original_prompt: &str) -> String {
+        format!(
+            r#"{}
+
+Your previous response was not valid JSON. Respond with ONLY the JSON object, no prose."#,
+            original_prompt
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_prompt_fills_placeholders_in_order() {
+        // Long lessons have different concept (3) and exercise (5) counts, so a
+        // swapped argument shows up as a mismatch below
+        let prompt = Ruleset::generate_prompt(
+            Language::Rust,
+            Difficulty::Intermediate,
+            LessonType::Long,
+            "pattern matching",
+            LessonType::Long.exercise_count(),
+            true,
+            Verbosity::Normal,
+        );
+
+        assert!(prompt.contains("LANGUAGE: Rust\n"));
+        assert!(prompt.contains("DIFFICULTY: Intermediate\n"));
+        assert!(prompt.contains("LESSON TYPE: Long\n"));
+        assert!(prompt.contains("TOPIC: pattern matching\n"));
+
+        assert!(prompt.contains("6. **Guided Exercise**: Create 5 exercise(s) with:"));
+        assert!(prompt.contains("You MUST include at least 5 exercise(s) in the \"exercises\" array"));
+        assert!(prompt.contains("For Long lessons, focus on 3 core concept(s)"));
+        assert!(prompt.contains("Match the complexity to Intermediate difficulty level"));
+        assert!(prompt.contains("must contain at least 5 exercise(s). Do not omit this field."));
+
+        assert_eq!(prompt.matches("5 exercise(s)").count(), 3);
+        // Normal asks for what lessons always did
+        assert_eq!(prompt.matches("(5-7 sentences)").count(), 2);
+        assert!(prompt.contains("simple steps (4-6 steps)"));
+        assert!(prompt.contains("**CRITICAL: You MUST provide at least 2 code examples** (2-3 total). Each"));
+        assert!(prompt.contains("Show 2-3 common use cases or patterns for this concept"));
+        assert!(prompt.contains("array MUST contain at least 2 examples. Do not provide just 1 example.**"));
+        assert!(!prompt.contains("starter_code"));
+        assert!(!prompt.contains("{}"));
+    }
+
+    #[test]
+    fn generate_prompt_adds_notes_for_hard_topics_in_their_language() {
+        let prompt = |language, topic| {
+            Ruleset::generate_prompt(language, Difficulty::Beginner, LessonType::Short, topic, 1, false, Verbosity::Normal)
+        };
+
+        let rust = prompt(Language::Rust, "Ownership and moves");
+        assert!(rust.contains("TOPIC-SPECIFIC GUIDANCE:"));
+        assert!(rust.contains("E0382"));
+        assert!(!prompt(Language::JavaScript, "ownership").contains("E0382"));
+        assert!(!prompt(Language::Rust, "loops").contains("E0382"));
+    }
+
+    #[test]
+    fn only_rust_prompts_offer_function_exercises() {
+        let prompt = |language| {
+            Ruleset::generate_prompt(language, Difficulty::Beginner, LessonType::Short, "recursion", 1, false, Verbosity::Normal)
+        };
+        assert!(prompt(Language::Rust).contains(r#""function": {"signature""#));
+        assert!(!prompt(Language::JavaScript).contains(r#""function""#));
+
+        let exercises = Ruleset::generate_exercises_prompt(Language::Rust, Difficulty::Beginner, "recursion", "", 2);
+        assert!(exercises.contains(r#""function": {"signature""#));
+    }
+
+    #[test]
+    fn clean_code_is_asked_for_past_beginner_in_compiled_languages() {
+        let prompt = |language, difficulty| {
+            Ruleset::generate_prompt(language, difficulty, LessonType::Short, "variables", 1, false, Verbosity::Normal)
+        };
+        assert!(prompt(Language::Rust, Difficulty::Intermediate).contains(r#""require_clean": true"#));
+        assert!(prompt(Language::Cpp, Difficulty::Advanced).contains(r#""require_clean": true"#));
+        assert!(!prompt(Language::Rust, Difficulty::Beginner).contains("require_clean"));
+        assert!(!prompt(Language::JavaScript, Difficulty::Intermediate).contains("require_clean"));
+    }
+
+    #[test]
+    fn generate_stage_prompt_covers_every_topic_of_the_stage() {
+        let curriculum = crate::config::curriculum::Curriculum::get_for_language(Language::JavaScript);
+        let stage = curriculum.get_stage(1).unwrap();
+        let prompt = Ruleset::generate_stage_prompt(Language::JavaScript, stage, stage.difficulty, 5, false, Verbosity::Normal);
+
+        let section = prompt.find("COMBINED STAGE LESSON:").expect("combined lesson section");
+        assert!(section < prompt.find("OUTPUT FORMAT (JSON):").unwrap());
+        for topic in &stage.topics {
+            assert!(prompt.contains(&format!("- {}\n", topic)), "missing topic {}", topic);
+        }
+        assert!(prompt.contains(&format!("TOPIC: {}", stage.combined_topic())));
+        assert!(prompt.contains("at least 5 exercise(s)"));
+    }
 }
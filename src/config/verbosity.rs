@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// How much explanation a lesson gives: the length of the concept and how many
+/// steps, examples and patterns are asked of the model and shown. Normal asks for
+/// what lessons always did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Verbosity {
+    Terse,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    /// CURSED_CODDY_VERBOSITY (terse, normal or verbose); normal when unset or unknown
+    pub fn from_env() -> Self {
+        match std::env::var("CURSED_CODDY_VERBOSITY")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "terse" => Verbosity::Terse,
+            "verbose" => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Verbosity::Terse => "Terse",
+            Verbosity::Normal => "Normal",
+            Verbosity::Verbose => "Verbose",
+        }
+    }
+
+    /// Sentence range requested for the concept introduction
+    pub fn concept_sentences(&self) -> &'static str {
+        match self {
+            Verbosity::Terse => "2-3",
+            Verbosity::Normal => "5-7",
+            Verbosity::Verbose => "7-9",
+        }
+    }
+
+    /// Step range requested for the step-by-step explanation
+    pub fn step_count(&self) -> &'static str {
+        match self {
+            Verbosity::Terse => "2-3",
+            Verbosity::Normal => "4-6",
+            Verbosity::Verbose => "6-8",
+        }
+    }
+
+    /// Fewest and most code examples requested
+    pub fn code_examples(&self) -> (usize, usize) {
+        match self {
+            Verbosity::Terse => (1, 1),
+            Verbosity::Normal => (2, 3),
+            Verbosity::Verbose => (3, 4),
+        }
+    }
+
+    /// How many common use cases the common patterns section asks for
+    pub fn common_patterns(&self) -> &'static str {
+        match self {
+            Verbosity::Terse => "1 common use case",
+            Verbosity::Normal => "2-3 common use cases",
+            Verbosity::Verbose => "3-4 common use cases",
+        }
+    }
+}
+
+impl std::fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name())
+    }
+}
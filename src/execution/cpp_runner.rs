@@ -1,67 +1,85 @@
+use crate::execution::{resolve_tool, Artifact, Runner};
 use anyhow::{Context, Result};
-use std::io::Write;
 use std::path::Path;
 use std::process::{Command, Stdio};
 
+const COMPILERS: &[&str] = &["g++", "clang++"];
+
 pub struct CppRunner;
 
-impl CppRunner {
-    pub fn execute(file_path: &Path, input: Option<&str>) -> Result<String> {
-        // Check if file exists
-        if !file_path.exists() {
-            return Err(anyhow::anyhow!("Exercise file not found: {}", file_path.display()));
-        }
-        
-        let exe_path = file_path.with_extension("");
+impl Runner for CppRunner {
+    fn compile(&self, file_path: &Path) -> Result<Artifact> {
+        // exercise_1.cpp builds exercise_1 (exercise_1.exe on Windows, which won't run it otherwise)
+        let exe_path = file_path.with_extension(std::env::consts::EXE_EXTENSION);
 
-        // Compile
-        let compile_output = Command::new("g++")
+        let compiler = resolve_tool("C++", COMPILERS)?;
+        let compile_output = Command::new(&compiler)
             .arg("-o")
             .arg(&exe_path)
             .arg(file_path)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .output()
-            .context("Failed to execute g++ command")?;
+            .with_context(|| format!("Failed to execute {} command", compiler))?;
 
         if !compile_output.status.success() {
             let stderr = String::from_utf8_lossy(&compile_output.stderr);
             return Err(anyhow::anyhow!("Compilation error: {}", stderr));
         }
-
-        // Run
-        let mut child = Command::new(&exe_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to execute compiled program")?;
-
-        if let Some(input_str) = input {
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin
-                    .write_all(input_str.as_bytes())
-                    .context("Failed to write to stdin")?;
-                // Close stdin to signal EOF
-                drop(stdin);
-            }
-        } else {
-            // Close stdin if no input
-            drop(child.stdin.take());
+        if !exe_path.is_file() {
+            return Err(anyhow::anyhow!(
+                "{} reported success but no program was built at {}",
+                compiler,
+                exe_path.display()
+            ));
         }
 
-        let run_output = child.wait_with_output()
-            .context("Failed to wait for process")?;
+        let mut artifact = Artifact::new(&exe_path);
+        artifact.cleanup_path = Some(exe_path);
+        Ok(artifact)
+    }
+
+    fn check_toolchain(&self) -> Result<()> {
+        resolve_tool("C++", COMPILERS).map(|_| ())
+    }
 
-        // Cleanup
-        let _ = std::fs::remove_file(&exe_path);
+    fn check(&self, file_path: &Path) -> Result<()> {
+        // Type-checks without building a program, so code without main passes too
+        let compiler = resolve_tool("C++", COMPILERS)?;
+        let output = Command::new(&compiler)
+            .arg("-fsyntax-only")
+            .arg(file_path)
+            .output()
+            .with_context(|| format!("Failed to execute {} command", compiler))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("Compilation error: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+        Ok(())
+    }
 
-        if !run_output.status.success() {
-            let stderr = String::from_utf8_lossy(&run_output.stderr);
-            return Err(anyhow::anyhow!("Runtime error: {}", stderr));
+    fn warnings(&self, file_path: &Path) -> Result<Vec<String>> {
+        let compiler = resolve_tool("C++", COMPILERS)?;
+        let output = Command::new(&compiler)
+            .args(["-fsyntax-only", "-Wall", "-Wextra"])
+            .arg(file_path)
+            .output()
+            .with_context(|| format!("Failed to execute {} command", compiler))?;
+        if !output.status.success() {
+            return Ok(Vec::new());
         }
+        // "exercise_1.cpp:5:9: warning: unused variable 'x' [-Wunused-variable]"
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(stderr
+            .lines()
+            .filter_map(|line| {
+                let (location, message) = line.split_once(": warning: ")?;
+                let line_number = location.rsplit(':').nth(1)?;
+                Some(format!("line {}: {}", line_number, message))
+            })
+            .collect())
+    }
 
-        let stdout = String::from_utf8_lossy(&run_output.stdout);
-        Ok(stdout.to_string())
+    fn error_label(&self) -> &'static str {
+        "Runtime error"
     }
 }
@@ -1,34 +1,205 @@
+use crate::cli::colors::Colors;
 use crate::config::Language;
-use crate::execution::{CppRunner, JsRunner, RustRunner};
-use anyhow::Result;
+use crate::cli::fs_error;
+use crate::execution::{CppRunner, FileManager, JsRunner, RunOptions, Runner, RustRunner};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-pub struct Executor;
+pub struct Executor {
+    language: Language,
+    runner: Box<dyn Runner>,
+    env: Vec<(String, String)>,
+}
 
 #[derive(Debug)]
 pub struct ExecutionResult {
     pub output: String,
+    /// None if the program was killed by a signal
+    pub exit_code: Option<i32>,
+    /// Contents of the file the run was asked to collect, if the program wrote it
+    pub file: Option<String>,
 }
 
 impl Executor {
+    pub fn new(language: Language) -> Self {
+        let runner: Box<dyn Runner> = match language {
+            Language::JavaScript => Box::new(JsRunner),
+            Language::Cpp => Box::new(CppRunner),
+            Language::Rust => Box::new(RustRunner),
+        };
+        Self { language, runner, env: run_env(language) }
+    }
+
+    /// Runs the file in a fresh, empty directory. Exiting unsuccessfully is an error,
+    /// unless it exits with `expected_exit_code`. If `collect_file` is given, that
+    /// file is read from the directory after the run.
     pub fn execute(
-        language: Language,
+        &self,
         file_path: &Path,
         input: Option<&str>,
+        args: &[String],
+        expected_exit_code: Option<i32>,
+        collect_file: Option<&str>,
     ) -> Result<ExecutionResult> {
-        let output_result = match language {
-            Language::JavaScript => JsRunner::execute(file_path, input),
-            Language::Cpp => CppRunner::execute(file_path, input),
-            Language::Rust => RustRunner::execute(file_path, input),
-        };
-        
-        match output_result {
-            Ok(output) => Ok(ExecutionResult { output }),
-            Err(e) => Err(e),
+        if !self.language.is_enabled() {
+            return Err(anyhow::anyhow!(
+                "{} is disabled in this configuration (see CURSED_CODDY_LANGUAGES)",
+                self.language.display_name()
+            ));
+        }
+        // The program runs elsewhere, so a relative exercise path (from a relative
+        // CURSED_CODDY_WORKDIR) has to be made absolute first
+        let file_path = std::path::absolute(file_path).context("Failed to resolve the exercise file path")?;
+        // A folder of its own, so two runs at once (two terminals, or prefetch) don't share files
+        let run_dir = FileManager::scratch_dir("run");
+        std::fs::create_dir_all(&run_dir)
+            .map_err(|e| fs_error::write_error(e, "Failed to create the run directory", &run_dir))?;
+        let run_dir = std::path::absolute(&run_dir).context("Failed to resolve the run directory")?;
+
+        let run = self.runner.execute(
+            &file_path,
+            &RunOptions { input, args, expected_exit_code, env: &self.env, run_dir: &run_dir },
+        );
+        let file = collect_file.and_then(|name| std::fs::read_to_string(run_dir.join(name)).ok());
+        let _ = std::fs::remove_dir_all(&run_dir);
+
+        let run = run?;
+        Ok(ExecutionResult { output: run.stdout, exit_code: run.exit_code, file })
+    }
+
+    /// Checks that the language's compiler or interpreter is installed and works
+    pub fn check_toolchain(&self) -> Result<()> {
+        self.runner.check_toolchain()
+    }
+
+    /// The compiler's warnings for the exercise file, one line each
+    pub fn warnings(&self, file_path: &Path) -> Result<Vec<String>> {
+        self.runner.warnings(file_path)
+    }
+
+    /// Compiles a lesson's code example on its own without running it. The error
+    /// carries the compiler's output when it doesn't compile.
+    pub fn check_example(&self, code: &str) -> Result<()> {
+        let dir = FileManager::scratch_dir("examples");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| fs_error::write_error(e, "Failed to create the examples directory", &dir))?;
+        let file_path = dir.join(format!("example.{}", self.language.file_extension()));
+        std::fs::write(&file_path, code)
+            .map_err(|e| fs_error::write_error(e, "Failed to write the example file", &file_path))?;
+
+        let result = self.runner.check(&file_path);
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    pub fn compare_output(actual: &str, expected: &str, mode: ComparisonMode) -> bool {
+        match mode {
+            ComparisonMode::Trim => actual.trim() == expected.trim(),
+            ComparisonMode::PreserveLeading => preserve_leading(actual) == preserve_leading(expected),
+        }
+    }
+}
+
+/// How a program's output is compared with the expected output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComparisonMode {
+    /// Whitespace around the whole output is ignored
+    #[default]
+    #[serde(alias = "trim")]
+    Trim,
+    /// Indentation counts, for output such as ASCII art, trees and tables; only
+    /// whitespace at the end of each line and trailing blank lines are ignored
+    #[serde(alias = "preserve_leading", alias = "preserveLeading")]
+    PreserveLeading,
+}
+
+impl ComparisonMode {
+    /// PreserveLeading when a line of the expected output is indented, since the
+    /// indentation is then part of what the exercise asks for
+    pub fn detect(expected: &str) -> Self {
+        let indented = expected
+            .lines()
+            .any(|line| line.starts_with([' ', '\t']) && !line.trim().is_empty());
+        if indented { ComparisonMode::PreserveLeading } else { ComparisonMode::Trim }
+    }
+}
+
+fn preserve_leading(output: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Environment variables set on the learner's program: CURSED_CODDY_RUN_ENV for
+/// every language, then CURSED_CODDY_RUN_ENV_<JAVASCRIPT|CPP|RUST>, each a
+/// comma-separated list of KEY=VALUE pairs. Later entries win. Rust programs get
+/// RUST_BACKTRACE=1 unless it's already set, so panics show where they happened.
+fn run_env(language: Language) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if language == Language::Rust && std::env::var_os("RUST_BACKTRACE").is_none() {
+        env.push(("RUST_BACKTRACE".to_string(), "1".to_string()));
+    }
+
+    let language_var = match language {
+        Language::JavaScript => "CURSED_CODDY_RUN_ENV_JAVASCRIPT",
+        Language::Cpp => "CURSED_CODDY_RUN_ENV_CPP",
+        Language::Rust => "CURSED_CODDY_RUN_ENV_RUST",
+    };
+    for var in ["CURSED_CODDY_RUN_ENV", language_var] {
+        let configured = std::env::var(var).unwrap_or_default();
+        for entry in configured.split(',') {
+            match entry.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    env.push((key.trim().to_string(), value.trim().to_string()));
+                }
+                _ if entry.trim().is_empty() => {}
+                _ => eprintln!("{}", Colors::warning(&format!("Ignoring '{}' in {}: expected KEY=VALUE", entry.trim(), var))),
+            }
         }
     }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indented_expected_output_keeps_its_indentation() {
+        assert_eq!(ComparisonMode::detect("Hello\nWorld"), ComparisonMode::Trim);
+        assert_eq!(ComparisonMode::detect("\n  \nHello\n"), ComparisonMode::Trim);
+        assert_eq!(ComparisonMode::detect("root\n  child"), ComparisonMode::PreserveLeading);
+        assert_eq!(ComparisonMode::detect("*\n\t**"), ComparisonMode::PreserveLeading);
+    }
+
+    #[test]
+    fn compare_output_ignores_only_the_whitespace_its_mode_allows() {
+        assert!(Executor::compare_output("  42\n\n", "42", ComparisonMode::Trim));
+        assert!(!Executor::compare_output("4 2", "42", ComparisonMode::Trim));
+
+        let tree = "root\n  child\n    leaf";
+        assert!(Executor::compare_output("root  \n  child\n    leaf\n\n", tree, ComparisonMode::PreserveLeading));
+        assert!(!Executor::compare_output("root\nchild\nleaf", tree, ComparisonMode::PreserveLeading));
+        assert!(!Executor::compare_output("root\n  child\n    leaf\nextra", tree, ComparisonMode::PreserveLeading));
+    }
+
+    #[test]
+    fn preserve_leading_drops_trailing_spaces_and_blank_lines_only() {
+        assert_eq!(preserve_leading("  a  \n b\n\n  \n"), vec!["  a", " b"]);
+        assert_eq!(preserve_leading("\n a"), vec!["", " a"]);
+        assert!(preserve_leading("\n\n").is_empty());
+    }
 
-    pub fn compare_output(actual: &str, expected: &str) -> bool {
-        actual.trim() == expected.trim()
+    #[test]
+    fn every_scratch_dir_is_its_own() {
+        let first = FileManager::scratch_dir("run");
+        let second = FileManager::scratch_dir("run");
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), second.parent());
+        assert!(first.parent().unwrap().ends_with("run"));
+        assert!(first.file_name().unwrap().to_string_lossy().starts_with(&format!("{}-", std::process::id())));
     }
 }
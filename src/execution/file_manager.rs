@@ -1,33 +1,194 @@
-use anyhow::{Context, Result};
-use std::path::PathBuf;
+use crate::cli::fs_error;
+use crate::config::env_vars;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Characters of the exercise title kept in its file name in project mode
+const PROJECT_FILE_TITLE_LENGTH: usize = 40;
 
 pub struct FileManager;
 
 impl FileManager {
+    /// Where exercise files and build projects go: CURSED_CODDY_WORKDIR (or CODDY_WORKDIR)
+    /// if set, otherwise a cursed-coddy folder in the system temp directory
+    pub fn work_dir() -> PathBuf {
+        env_vars::value("CURSED_CODDY_WORKDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join("cursed-coddy"))
+    }
+
+    /// A lesson's own folder in the work directory: <workdir>/lessons/<language>/<topic>-<session>.
+    /// The session id is kept with the lesson's progress, so a resumed lesson finds its
+    /// files again, while other lessons, even on the same topic, don't touch them.
+    pub fn session_dir(language: &crate::config::Language, topic: &str, session_id: Option<&str>) -> PathBuf {
+        let slug = crate::progress::topic_slug(topic);
+        let name = match session_id {
+            Some(id) => format!("{}-{}", slug, id),
+            None => slug,
+        };
+        Self::work_dir().join("lessons").join(language.key()).join(name)
+    }
+
+    /// A folder under <workdir>/<kind> that no other run uses, in this process or
+    /// another, for a program to run or an example to be compiled in
+    pub fn scratch_dir(kind: &str) -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        Self::work_dir().join(kind).join(format!("{}-{}", std::process::id(), n))
+    }
+
+    /// CURSED_CODDY_KEEP_FILES: leave a lesson's folder in the work directory once
+    /// the lesson is completed, instead of removing it
+    pub fn keep_files_from_env() -> bool {
+        std::env::var("CURSED_CODDY_KEEP_FILES")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false)
+    }
+
+    /// Removes a completed lesson's folder, along with the programs and Cargo projects
+    /// built in it. Nothing is lost that the lesson needs again, so failures are ignored.
+    pub fn remove_session_dir(session_dir: &Path) {
+        if session_dir.starts_with(Self::work_dir()) {
+            let _ = std::fs::remove_dir_all(session_dir);
+        }
+    }
+
+    /// Writes a fresh exercise file in `dir`. It starts with the exercise's starter
+    /// code when there is some, otherwise with the language template.
     pub fn create_exercise_file(
+        dir: &Path,
         language: &crate::config::Language,
         exercise_number: usize,
+        starter_code: Option<&str>,
     ) -> Result<PathBuf> {
-        let temp_dir = std::env::temp_dir().join("cursed-coddy");
-        std::fs::create_dir_all(&temp_dir)
-            .context("Failed to create temp directory")?;
+        std::fs::create_dir_all(dir)
+            .map_err(|e| fs_error::write_error(e, "Failed to create temp directory", dir))?;
 
         let filename = format!("exercise_{}.{}", exercise_number, language.file_extension());
-        let file_path = temp_dir.join(&filename);
-
-        // Create file with basic template
-        let template = Self::get_template(language);
-        std::fs::write(&file_path, template)
-            .context("Failed to write exercise file")?;
+        let file_path = dir.join(&filename);
+        Self::write_new_exercise_file(&file_path, language, starter_code)?;
 
         Ok(file_path)
     }
 
-    fn get_template(language: &crate::config::Language) -> &'static str {
+    /// What a new exercise file starts with: the starter code, or the language template
+    pub fn initial_contents(language: &crate::config::Language, starter_code: Option<&str>) -> String {
+        match starter_code {
+            Some(code) => code.to_string(),
+            None => Self::get_template(language),
+        }
+    }
+
+    fn write_new_exercise_file(file_path: &Path, language: &crate::config::Language, starter_code: Option<&str>) -> Result<()> {
+        std::fs::write(file_path, Self::initial_contents(language, starter_code))
+            .map_err(|e| fs_error::write_error(e, "Failed to write exercise file", file_path))
+    }
+
+    /// Like create_exercise_file, but keeps the file's contents if it already exists
+    pub fn open_exercise_file(
+        dir: &Path,
+        language: &crate::config::Language,
+        exercise_number: usize,
+        starter_code: Option<&str>,
+    ) -> Result<PathBuf> {
+        let file_path = dir.join(format!("exercise_{}.{}", exercise_number, language.file_extension()));
+        if file_path.exists() {
+            return Ok(file_path);
+        }
+        Self::create_exercise_file(dir, language, exercise_number, starter_code)
+    }
+
+    /// CURSED_CODDY_PROJECT_DIR: a folder to keep every lesson's files in, instead
+    /// of exercise files in the work directory that go once the lesson is done
+    pub fn project_dir_from_env() -> Option<PathBuf> {
+        std::env::var_os("CURSED_CODDY_PROJECT_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// A lesson's folder in project mode: <project>/<language>/<topic>
+    pub fn project_lesson_dir(project_dir: &Path, language: &crate::config::Language, topic: &str) -> PathBuf {
+        project_dir.join(language.key()).join(crate::progress::topic_slug(topic))
+    }
+
+    /// The file an exercise is written in within a project lesson folder, e.g.
+    /// exercise_2_sum_two_numbers.rs. Underscores keep it a valid Rust crate name.
+    pub fn project_exercise_file_name(language: &crate::config::Language, exercise_number: usize, title: &str) -> String {
+        let slug: String = crate::progress::topic_slug(title)
+            .split('-')
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("_")
+            .chars()
+            .take(PROJECT_FILE_TITLE_LENGTH)
+            .collect();
+        let slug = slug.trim_end_matches('_');
+        if slug.is_empty() {
+            format!("exercise_{}.{}", exercise_number, language.file_extension())
+        } else {
+            format!("exercise_{}_{}.{}", exercise_number, slug, language.file_extension())
+        }
+    }
+
+    /// Opens an exercise's file in a project lesson folder. A new file starts like
+    /// one from create_exercise_file; an existing one is the learner's earlier work
+    /// and is kept. Returns the path and whether the file already existed.
+    pub fn open_project_exercise_file(
+        lesson_dir: &Path,
+        language: &crate::config::Language,
+        exercise_number: usize,
+        title: &str,
+        starter_code: Option<&str>,
+    ) -> Result<(PathBuf, bool)> {
+        let file_path = lesson_dir.join(Self::project_exercise_file_name(language, exercise_number, title));
+        if file_path.exists() {
+            return Ok((file_path, true));
+        }
+        std::fs::create_dir_all(lesson_dir)
+            .map_err(|e| fs_error::write_error(e, "Failed to create the lesson folder", lesson_dir))?;
+        Self::write_new_exercise_file(&file_path, language, starter_code)?;
+        Ok((file_path, false))
+    }
+
+    /// The file a function exercise starts with: the function, with todo!() as its body
+    pub fn function_stub(signature: &str) -> String {
+        format!("{} {{\n    todo!()\n}}\n", signature.trim().trim_end_matches('{').trim_end())
+    }
+
+    /// Where the program built around a function exercise's code goes, next to its
+    /// file: exercise_1.rs -> exercise_1_harness.rs (a valid crate name for rustc)
+    pub fn harness_path(exercise_file: &Path) -> PathBuf {
+        let stem = exercise_file.file_stem().unwrap_or_default().to_string_lossy();
+        exercise_file.with_file_name(format!("{}_harness.rs", stem))
+    }
+
+    /// Test cases that replace an exercise's own when grading it, next to its file:
+    /// exercise_1.js -> exercise_1.tests.json
+    pub fn test_override_path(exercise_file: &Path) -> PathBuf {
+        exercise_file.with_extension("tests.json")
+    }
+
+    /// Starter code for a new exercise file: ~/.cursed-coddy/templates/<lang>.txt
+    /// (javascript.txt, cpp.txt or rust.txt) when present, otherwise the built-in one.
+    /// The override is copied as-is, so an empty file means no template at all.
+    fn get_template(language: &crate::config::Language) -> String {
+        if let Some(path) = Self::template_override_path(language) {
+            if let Ok(template) = std::fs::read_to_string(&path) {
+                return template;
+            }
+        }
+
         match language {
             crate::config::Language::JavaScript => "// Write your solution here\n\n",
             crate::config::Language::Cpp => "#include <iostream>\nusing namespace std;\n\nint main() {\n    // Write your solution here\n    return 0;\n}\n",
             crate::config::Language::Rust => "fn main() {\n    // Write your solution here\n}\n",
         }
+        .to_string()
+    }
+
+    /// Where a learner's own template for the language would be
+    pub fn template_override_path(language: &crate::config::Language) -> Option<PathBuf> {
+        Some(crate::progress::data_dir().ok()?.join("templates").join(format!("{}.txt", language.key())))
     }
 }
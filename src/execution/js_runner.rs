@@ -1,47 +1,36 @@
+use crate::execution::{resolve_tool, Artifact, Runner};
 use anyhow::{Context, Result};
-use std::io::Write;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::Command;
 
-pub struct JsRunner;
+// Debian and Ubuntu used to install node as nodejs
+const NODE: &[&str] = &["node", "nodejs"];
 
-impl JsRunner {
-    pub fn execute(file_path: &Path, input: Option<&str>) -> Result<String> {
-        // Check if file exists
-        if !file_path.exists() {
-            return Err(anyhow::anyhow!("Exercise file not found: {}", file_path.display()));
-        }
-        
-        let mut child = Command::new("node")
-            .arg(file_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to execute node command")?;
+pub struct JsRunner;
 
-        if let Some(input_str) = input {
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin
-                    .write_all(input_str.as_bytes())
-                    .context("Failed to write to stdin")?;
-                // Close stdin to signal EOF
-                drop(stdin);
-            }
-        } else {
-            // Close stdin if no input
-            drop(child.stdin.take());
-        }
+impl Runner for JsRunner {
+    fn compile(&self, file_path: &Path) -> Result<Artifact> {
+        // Interpreted - node runs the source file directly
+        let mut artifact = Artifact::new(resolve_tool("JavaScript", NODE)?);
+        artifact.args.push(file_path.display().to_string());
+        Ok(artifact)
+    }
 
-        let output = child.wait_with_output()
-            .context("Failed to wait for process")?;
+    fn check_toolchain(&self) -> Result<()> {
+        resolve_tool("JavaScript", NODE).map(|_| ())
+    }
 
+    fn check(&self, file_path: &Path) -> Result<()> {
+        // Syntax only, since there's nothing to compile
+        let node = resolve_tool("JavaScript", NODE)?;
+        let output = Command::new(&node)
+            .arg("--check")
+            .arg(file_path)
+            .output()
+            .with_context(|| format!("Failed to execute {} command", node))?;
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("Execution error: {}", stderr));
+            return Err(anyhow::anyhow!("Syntax error: {}", String::from_utf8_lossy(&output.stderr)));
         }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(stdout.to_string())
+        Ok(())
     }
 }
@@ -2,10 +2,20 @@ pub mod executor;
 pub mod file_manager;
 pub mod js_runner;
 pub mod cpp_runner;
+pub mod output_limit;
+pub mod runner;
+pub mod source_scan;
+pub mod toolchain;
 pub mod rust_runner;
+pub mod rust_diagnostics;
 
 pub use executor::*;
 pub use file_manager::*;
 pub use js_runner::*;
 pub use cpp_runner::*;
+pub use output_limit::*;
+pub use runner::*;
+pub use source_scan::*;
+pub use toolchain::*;
 pub use rust_runner::*;
+pub use rust_diagnostics::*;
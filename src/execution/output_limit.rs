@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Default cap on captured stdout/stderr per stream (1MB)
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+// Lines of output shown when a run is stopped for exceeding the limit
+const PREVIEW_LINES: usize = 10;
+
+const TRUNCATION_MARKER_PREFIX: &str = "[output truncated: exceeded";
+
+/// Output of a finished process, with each stream capped at the output limit
+pub struct LimitedOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+    pub truncated: bool,
+}
+
+impl LimitedOutput {
+    /// Turns a truncated run into an error showing only the start of the output,
+    /// so the flood isn't echoed back to the terminal by the grader
+    pub fn ensure_within_limit(&self) -> Result<()> {
+        if !self.truncated {
+            return Ok(());
+        }
+        let source = if self.stdout.contains(TRUNCATION_MARKER_PREFIX) { &self.stdout } else { &self.stderr };
+        let preview: Vec<&str> = source.lines().take(PREVIEW_LINES).collect();
+        Err(anyhow::anyhow!(
+            "Output limit exceeded - your program was stopped. Check for an infinite print loop.\nFirst lines of output:\n{}\n[output truncated: exceeded {}]",
+            preview.join("\n"),
+            format_size(max_output_bytes())
+        ))
+    }
+}
+
+/// Maximum bytes captured per stream, configurable via CURSED_CODDY_MAX_OUTPUT
+pub fn max_output_bytes() -> usize {
+    std::env::var("CURSED_CODDY_MAX_OUTPUT")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES)
+}
+
+/// Waits for the child while reading its stdout/stderr incrementally. If either
+/// stream exceeds the output limit the process is killed and the captured output
+/// ends with a truncation marker, so a runaway print loop can't flood the terminal.
+pub fn wait_with_limited_output(mut child: Child) -> Result<LimitedOutput> {
+    let limit = max_output_bytes();
+    let exceeded = Arc::new(AtomicBool::new(false));
+
+    let stdout_reader = child.stdout.take().map(|s| spawn_reader(s, limit, exceeded.clone()));
+    let stderr_reader = child.stderr.take().map(|s| spawn_reader(s, limit, exceeded.clone()));
+
+    let status = loop {
+        if let Some(status) = child.try_wait().context("Failed to wait for process")? {
+            break status;
+        }
+        if exceeded.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            break child.wait().context("Failed to wait for process")?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let collect = |reader: Option<thread::JoinHandle<(Vec<u8>, bool)>>| {
+        let (bytes, truncated) = reader
+            .and_then(|r| r.join().ok())
+            .unwrap_or_default();
+        let mut text = String::from_utf8_lossy(&bytes).to_string();
+        if truncated {
+            text.push_str(&format!("\n{} {}]", TRUNCATION_MARKER_PREFIX, format_size(limit)));
+        }
+        text
+    };
+
+    Ok(LimitedOutput {
+        status,
+        stdout: collect(stdout_reader),
+        stderr: collect(stderr_reader),
+        truncated: exceeded.load(Ordering::Relaxed),
+    })
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    mut stream: R,
+    limit: usize,
+    exceeded: Arc<AtomicBool>,
+) -> thread::JoinHandle<(Vec<u8>, bool)> {
+    thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => return (captured, false),
+                Ok(n) => {
+                    let remaining = limit - captured.len();
+                    if n > remaining {
+                        captured.extend_from_slice(&buf[..remaining]);
+                        exceeded.store(true, Ordering::Relaxed);
+                        return (captured, true);
+                    }
+                    captured.extend_from_slice(&buf[..n]);
+                }
+            }
+        }
+    })
+}
+
+/// A byte count as MB, KB or bytes, whichever is exact
+pub fn format_size(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 && bytes.is_multiple_of(1024 * 1024) {
+        format!("{}MB", bytes / (1024 * 1024))
+    } else if bytes >= 1024 && bytes.is_multiple_of(1024) {
+        format!("{}KB", bytes / 1024)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
@@ -0,0 +1,248 @@
+use crate::cli::colors::Colors;
+use crate::cli::timing::{self, Phase};
+use crate::execution::{wait_with_limited_output, LimitedOutput};
+use anyhow::{Context, Result};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+
+// Set once programs turned out not to run from the work folder. The learner is
+// told then, and later runs in this process go to the fallback folder straight away.
+static NOEXEC_FOUND: OnceLock<()> = OnceLock::new();
+
+/// A program ready to run: the command to launch plus whatever was built for it
+pub struct Artifact {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    /// File or directory removed once the program has run
+    pub cleanup_path: Option<PathBuf>,
+    /// Launched with `cargo run`, which builds the program as part of running it
+    pub cargo_run: bool,
+}
+
+impl Artifact {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            cleanup_path: None,
+            cargo_run: false,
+        }
+    }
+
+    /// Whether the program is the file that was just built, rather than a tool such as node or cargo
+    pub fn is_built_program(&self) -> bool {
+        self.cleanup_path.as_ref() == Some(&self.program)
+    }
+
+    pub fn cleanup(&self) {
+        if let Some(ref path) = self.cleanup_path {
+            let _ = if path.is_dir() {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_file(path)
+            };
+        }
+    }
+}
+
+/// Per-run options: stdin contents and command-line arguments for the program
+pub struct RunOptions<'a> {
+    pub input: Option<&'a str>,
+    pub args: &'a [String],
+    /// Exit code the program is meant to end with; a matching non-zero exit isn't an error
+    pub expected_exit_code: Option<i32>,
+    /// Extra environment variables for the program, on top of the ones it inherits
+    pub env: &'a [(String, String)],
+    /// Working directory for the program, where any files it writes end up
+    pub run_dir: &'a Path,
+}
+
+/// What a finished run printed and how it exited
+pub struct RunOutput {
+    pub stdout: String,
+    /// None if the program was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+/// Compiles and runs learner code for one language
+pub trait Runner {
+    /// Prepares the source file to be run, compiling it if the language needs it
+    fn compile(&self, file_path: &Path) -> Result<Artifact>;
+
+    /// Checks that the compiler or interpreter is installed and works
+    fn check_toolchain(&self) -> Result<()>;
+
+    /// Prefix of the error returned when the program exits unsuccessfully
+    fn error_label(&self) -> &'static str {
+        "Execution error"
+    }
+
+    /// Runs a prepared artifact and returns its stdout and exit code
+    fn run(&self, artifact: &Artifact, opts: &RunOptions) -> Result<RunOutput> {
+        let output = timing::measure(Phase::Tests, || spawn_with_input(artifact, opts))?;
+        check_output(output, self.error_label(), opts.expected_exit_code)
+    }
+
+    /// Checks that the source compiles, without running it
+    fn check(&self, file_path: &Path) -> Result<()> {
+        self.compile(file_path).map(|artifact| artifact.cleanup())
+    }
+
+    /// The compiler's warnings for the source, one line each, for exercises that
+    /// must compile cleanly. Code that doesn't compile has none; the tests report
+    /// that. Interpreted languages have no compiler to warn.
+    fn warnings(&self, _file_path: &Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Compiles, runs and cleans up in one go
+    fn execute(&self, file_path: &Path, opts: &RunOptions) -> Result<RunOutput> {
+        if !file_path.exists() {
+            return Err(anyhow::anyhow!("Exercise file not found: {}", file_path.display()));
+        }
+
+        let artifact = timing::measure(Phase::Compilation, || self.compile(file_path))?;
+        let result = self.run(&artifact, opts);
+        artifact.cleanup();
+        result
+    }
+}
+
+/// Spawns the artifact, pipes the input to its stdin and captures its output.
+/// A freshly built program that the system refuses to start is on a noexec
+/// mount, so a copy of it is run from the fallback folder instead.
+pub fn spawn_with_input(artifact: &Artifact, opts: &RunOptions) -> Result<LimitedOutput> {
+    if noexec_found() && artifact.is_built_program() {
+        return spawn_copy_from_exec_dir(artifact, opts);
+    }
+    let child = match spawn(&artifact.program, artifact, opts) {
+        Err(e) if e.kind() == ErrorKind::PermissionDenied && artifact.is_built_program() => {
+            return spawn_copy_from_exec_dir(artifact, opts);
+        }
+        result => result.with_context(|| format!("Failed to execute {}", artifact.program.display()))?,
+    };
+    feed_and_wait(child, opts)
+}
+
+/// Where compiled programs run when the work folder doesn't allow it; inside the
+/// data directory, which is in the learner's home unless --home says otherwise
+pub fn exec_fallback_dir() -> Result<PathBuf> {
+    Ok(crate::progress::data_dir()?.join("bin"))
+}
+
+/// Whether programs already turned out not to run from the work folder in this process
+pub fn noexec_found() -> bool {
+    NOEXEC_FOUND.get().is_some()
+}
+
+/// Records that programs can't run from `dir`, and tells the learner the first time
+/// that they run from the fallback folder instead
+pub fn warn_noexec(dir: &Path, fallback: &Path) {
+    if NOEXEC_FOUND.set(()).is_err() {
+        return;
+    }
+    println!(
+        "{} {}",
+        Colors::label_warn("WARN"),
+        Colors::warning(&format!(
+            "Programs can't be run from {} (it's probably on a noexec mount), so compiled exercises run from {} instead. Set CODDY_WORKDIR (or CURSED_CODDY_WORKDIR) to a folder where programs can run to avoid this.",
+            dir.display(),
+            fallback.display()
+        ))
+    );
+}
+
+/// The error for a program that couldn't run from its own folder or the fallback folder
+pub fn noexec_error(program: &Path, cause: anyhow::Error) -> anyhow::Error {
+    let dir = program.parent().unwrap_or(program);
+    cause.context(format!(
+        "Can't run the compiled program: {} doesn't allow running programs (it's probably on a noexec mount), and running it from the fallback folder failed too. Set CODDY_WORKDIR (or CURSED_CODDY_WORKDIR) to a folder where programs can run.",
+        dir.display()
+    ))
+}
+
+fn spawn(program: &Path, artifact: &Artifact, opts: &RunOptions) -> std::io::Result<Child> {
+    Command::new(program)
+        .args(&artifact.args)
+        .args(opts.args)
+        .envs(opts.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .current_dir(opts.run_dir)
+        .spawn()
+}
+
+// Copies the built program to the fallback folder, runs it from there and removes the copy
+fn spawn_copy_from_exec_dir(artifact: &Artifact, opts: &RunOptions) -> Result<LimitedOutput> {
+    let run_copy = || -> Result<LimitedOutput> {
+        let exec_dir = exec_fallback_dir()?;
+        std::fs::create_dir_all(&exec_dir)
+            .with_context(|| format!("Failed to create {}", exec_dir.display()))?;
+        let file_name = artifact.program.file_name().unwrap_or_default().to_string_lossy();
+        let copy = RemovedOnDrop(exec_dir.join(format!("{}-{}", std::process::id(), file_name)));
+        // fs::copy keeps the permissions, including the executable bit
+        std::fs::copy(&artifact.program, &copy.0)
+            .with_context(|| format!("Failed to copy the program to {}", copy.0.display()))?;
+        warn_noexec(artifact.program.parent().unwrap_or(&artifact.program), &exec_dir);
+
+        spawn(&copy.0, artifact, opts)
+            .with_context(|| format!("Failed to execute {}", copy.0.display()))
+            .and_then(|child| feed_and_wait(child, opts))
+    };
+    run_copy().map_err(|e| noexec_error(&artifact.program, e))
+}
+
+// A file removed however the code holding it ends, so a copy that failed half-way
+// or a run that errored doesn't leave a program behind in the fallback folder
+struct RemovedOnDrop(PathBuf);
+
+impl Drop for RemovedOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn feed_and_wait(mut child: Child, opts: &RunOptions) -> Result<LimitedOutput> {
+    if let Some(input_str) = opts.input {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(input_str.as_bytes())
+                .context("Failed to write to stdin")?;
+            // Close stdin to signal EOF
+            drop(stdin);
+        }
+    } else {
+        // Close stdin if no input
+        drop(child.stdin.take());
+    }
+
+    wait_with_limited_output(child)
+}
+
+/// Turns a finished run into its stdout and exit code, or an error carrying its
+/// stderr if it failed with anything but the expected exit code
+pub fn check_output(output: LimitedOutput, error_label: &str, expected_exit_code: Option<i32>) -> Result<RunOutput> {
+    output.ensure_within_limit()?;
+
+    let exit_code = output.status.code();
+    if !output.status.success() {
+        match expected_exit_code {
+            Some(expected) if exit_code == Some(expected) => {}
+            Some(expected) if expected != 0 => {
+                return Err(anyhow::anyhow!(
+                    "{} (exited with {}, expected exit code {}): {}",
+                    error_label,
+                    exit_code.map_or("a signal".to_string(), |code| format!("code {}", code)),
+                    expected,
+                    output.stderr
+                ));
+            }
+            _ => return Err(anyhow::anyhow!("{}: {}", error_label, output.stderr)),
+        }
+    }
+
+    Ok(RunOutput { stdout: output.stdout, exit_code })
+}
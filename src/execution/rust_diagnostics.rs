@@ -0,0 +1,233 @@
+use serde::Deserialize;
+use std::fmt;
+
+/// One error or warning from rustc, read from its JSON output (--error-format=json)
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// "error", "warning", ...
+    pub level: String,
+    /// Error code such as E0382, when the compiler gives one
+    pub code: Option<String>,
+    pub message: String,
+    /// Where the problem is: line and column of the primary span
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// The source line the primary span is on
+    pub source_line: Option<String>,
+    /// What the compiler says about the span, e.g. "value borrowed here after move"
+    pub label: Option<String>,
+    /// How many characters the primary span covers on its line
+    pub span_width: usize,
+    /// The compiler's suggested fixes, e.g. "consider cloning the value: `s.clone()` (line 4)"
+    pub suggestions: Vec<String>,
+    /// Notes and help without a replacement
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// "error[E0382]: borrow of moved value", as the compiler heads it
+    pub fn headline(&self) -> String {
+        match self.code {
+            Some(ref code) => format!("{}[{}]: {}", self.level, code, self.message),
+            None => format!("{}: {}", self.level, self.message),
+        }
+    }
+}
+
+/// A Rust program that didn't compile, with rustc's diagnostics. Shown as text it
+/// reads like the compiler's own output.
+#[derive(Debug)]
+pub struct CompileError {
+    pub diagnostics: Vec<Diagnostic>,
+    rendered: String,
+}
+
+impl CompileError {
+    /// The error codes of the errors, in order, e.g. ["E0382"]
+    pub fn error_codes(&self) -> Vec<&str> {
+        self.diagnostics.iter().filter(|d| d.is_error()).filter_map(|d| d.code.as_deref()).collect()
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Compilation error: {}", self.rendered)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// The compile error behind `e`, if it is one with rustc's diagnostics
+pub fn compile_error(e: &anyhow::Error) -> Option<&CompileError> {
+    e.chain().find_map(|cause| cause.downcast_ref::<CompileError>())
+}
+
+/// Reads rustc's JSON diagnostics from its stderr: the diagnostics, and the text
+/// rustc would have printed. Lines that aren't diagnostics are kept in the text.
+pub fn parse_rustc_output(stderr: &str) -> (Vec<Diagnostic>, String) {
+    let mut diagnostics = Vec::new();
+    let mut rendered = String::new();
+    for line in stderr.lines() {
+        match serde_json::from_str::<RawDiagnostic>(line) {
+            Ok(raw) => {
+                rendered.push_str(raw.rendered.as_deref().unwrap_or(&raw.message));
+                // "aborting due to 2 previous errors" and the like only count what's above
+                if !raw.spans.is_empty() || raw.code.is_some() {
+                    diagnostics.push(raw.into_diagnostic());
+                }
+            }
+            Err(_) => {
+                rendered.push_str(line);
+                rendered.push('\n');
+            }
+        }
+    }
+    (diagnostics, rendered)
+}
+
+/// Builds the error for a failed compile from rustc's JSON output
+pub fn compile_error_from(stderr: &str) -> CompileError {
+    let (diagnostics, rendered) = parse_rustc_output(stderr);
+    CompileError { diagnostics, rendered }
+}
+
+// The parts of rustc's diagnostic JSON that are shown
+#[derive(Deserialize)]
+struct RawDiagnostic {
+    message: String,
+    #[serde(default)]
+    code: Option<RawCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RawSpan>,
+    #[serde(default)]
+    children: Vec<RawDiagnostic>,
+    #[serde(default)]
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RawSpan {
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    text: Vec<RawSpanText>,
+    #[serde(default)]
+    suggested_replacement: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawSpanText {
+    text: String,
+    highlight_start: usize,
+    highlight_end: usize,
+}
+
+impl RawDiagnostic {
+    fn into_diagnostic(self) -> Diagnostic {
+        let primary = self.spans.iter().find(|s| s.is_primary).or(self.spans.first());
+        let mut suggestions = Vec::new();
+        let mut notes = Vec::new();
+        for child in &self.children {
+            let replacements: Vec<String> = child
+                .spans
+                .iter()
+                .filter_map(|s| s.suggested_replacement.as_ref().map(|r| format!("`{}` (line {})", r.trim(), s.line_start)))
+                .collect();
+            if replacements.is_empty() {
+                notes.push(format!("{}: {}", child.level, child.message));
+            } else {
+                suggestions.push(format!("{}: {}", child.message, replacements.join(", ")));
+            }
+        }
+        Diagnostic {
+            level: self.level,
+            code: self.code.map(|c| c.code),
+            message: self.message,
+            line: primary.map(|s| s.line_start),
+            column: primary.map(|s| s.column_start),
+            source_line: primary.and_then(|s| s.text.first()).map(|t| t.text.clone()),
+            label: primary.and_then(|s| s.label.clone()),
+            // The highlighted part of the first line, for spans that cover several lines
+            span_width: primary
+                .and_then(|s| s.text.first())
+                .map(|t| t.highlight_end.saturating_sub(t.highlight_start).max(1))
+                .unwrap_or(1),
+            suggestions,
+            notes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed from rustc --error-format=json: fields that aren't read are left out
+    const MOVED: &str = r#"{"$message_type":"diagnostic","message":"borrow of moved value: `s`","code":{"code":"E0382","explanation":"A variable was used after its contents have been moved elsewhere."},"level":"error","spans":[{"file_name":"m.rs","line_start":3,"line_end":3,"column_start":13,"column_end":14,"is_primary":false,"text":[{"text":"    let t = s;","highlight_start":13,"highlight_end":14}],"label":"value moved here","suggested_replacement":null},{"file_name":"m.rs","line_start":5,"line_end":5,"column_start":20,"column_end":21,"is_primary":true,"text":[{"text":"    println!(\"{}\", s);","highlight_start":20,"highlight_end":21}],"label":"value borrowed here after move","suggested_replacement":null}],"children":[{"message":"consider cloning the value if the performance cost is acceptable","code":null,"level":"help","spans":[{"file_name":"m.rs","line_start":3,"line_end":3,"column_start":14,"column_end":14,"is_primary":true,"text":[{"text":"    let t = s;","highlight_start":14,"highlight_end":14}],"label":null,"suggested_replacement":".clone()"}],"children":[],"rendered":null}],"rendered":"error[E0382]: borrow of moved value: `s`\n"}"#;
+    const UNUSED: &str = r#"{"$message_type":"diagnostic","message":"unused variable: `t`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"m.rs","line_start":3,"line_end":3,"column_start":9,"column_end":10,"is_primary":true,"text":[{"text":"    let t = s;","highlight_start":9,"highlight_end":10}],"label":null,"suggested_replacement":null}],"children":[{"message":"`#[warn(unused_variables)]` on by default","code":null,"level":"note","spans":[],"children":[],"rendered":null}],"rendered":"warning: unused variable: `t`\n"}"#;
+    const ABORTING: &str = r#"{"$message_type":"diagnostic","message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"children":[],"rendered":"error: aborting due to 1 previous error\n\n"}"#;
+
+    #[test]
+    fn an_error_is_read_with_its_code_span_and_suggestion() {
+        let (diagnostics, rendered) = parse_rustc_output(MOVED);
+
+        assert_eq!(diagnostics.len(), 1);
+        let moved = &diagnostics[0];
+        assert!(moved.is_error());
+        assert_eq!(moved.headline(), "error[E0382]: borrow of moved value: `s`");
+        // The primary span, not the first one
+        assert_eq!((moved.line, moved.column), (Some(5), Some(20)));
+        assert_eq!(moved.source_line.as_deref(), Some("    println!(\"{}\", s);"));
+        assert_eq!(moved.label.as_deref(), Some("value borrowed here after move"));
+        assert_eq!(moved.span_width, 1);
+        assert_eq!(
+            moved.suggestions,
+            vec!["consider cloning the value if the performance cost is acceptable: `.clone()` (line 3)"]
+        );
+        assert!(moved.notes.is_empty());
+        assert_eq!(rendered, "error[E0382]: borrow of moved value: `s`\n");
+    }
+
+    #[test]
+    fn a_warning_keeps_its_notes_and_the_summary_is_not_a_diagnostic() {
+        let stderr = [UNUSED, ABORTING].join("\n");
+        let (diagnostics, rendered) = parse_rustc_output(&stderr);
+
+        assert_eq!(diagnostics.len(), 1);
+        let unused = &diagnostics[0];
+        assert!(!unused.is_error());
+        assert_eq!(unused.headline(), "warning[unused_variables]: unused variable: `t`");
+        assert_eq!(unused.notes, vec!["note: `#[warn(unused_variables)]` on by default"]);
+        assert!(unused.suggestions.is_empty());
+        assert!(rendered.ends_with("error: aborting due to 1 previous error\n\n"));
+    }
+
+    #[test]
+    fn lines_that_are_not_json_are_kept_as_text() {
+        let stderr = format!("warning: unused manifest key: package.foo\n{}\nerror: could not compile `exercise`", MOVED);
+        let (diagnostics, rendered) = parse_rustc_output(&stderr);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            rendered,
+            "warning: unused manifest key: package.foo\nerror[E0382]: borrow of moved value: `s`\nerror: could not compile `exercise`\n"
+        );
+
+        let error = compile_error_from(&stderr);
+        assert_eq!(error.error_codes(), vec!["E0382"]);
+        assert!(error.to_string().starts_with("Compilation error: warning: unused manifest key"));
+    }
+}
@@ -1,22 +1,111 @@
+use crate::cli::timing::{self, Phase};
+use crate::cli::{colors::Colors, fs_error};
+use crate::config::Language;
+use crate::execution::{active_code, check_output, compile_error_from, parse_rustc_output, exec_fallback_dir, resolve_tool, noexec_found, spawn_with_input, warn_noexec, Artifact, RunOptions, RunOutput, Runner};
 use anyhow::{Context, Result};
-use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
-pub struct RustRunner;
+// rustc errors that point at a damaged or stale build cache rather than a mistake in
+// the learner's code: metadata left half-written by a killed build (E0786), crates
+// built by a toolchain since replaced (E0514), and a broken incremental cache
+const CORRUPT_CACHE_PATTERNS: &[&str] = &[
+    "found invalid metadata files for crate",
+    "compiled by an incompatible version of rustc",
+    "encountered incremental compilation error",
+];
 
-impl RustRunner {
-    pub fn execute(file_path: &Path, input: Option<&str>) -> Result<String> {
-        if !file_path.exists() {
-            return Err(anyhow::anyhow!("Exercise file not found: {}", file_path.display()));
+const DEFAULT_EDITION: &str = "2021";
+const KNOWN_EDITIONS: &[&str] = &["2015", "2018", "2021", "2024"];
+
+/// Settings for the Cargo.toml generated around each Rust exercise
+struct CargoOptions {
+    edition: String,
+    dependencies: Vec<String>,
+    // `key = value` lines for [profile.dev], e.g. `opt-level = 1`
+    profile: Vec<String>,
+    // Unstable cargo features (cargo-features = [...]), which need a nightly toolchain
+    cargo_features: Vec<String>,
+}
+
+impl CargoOptions {
+    // Crates and Cargo-only settings need a Cargo project; anything else builds with plain rustc
+    fn needs_cargo(&self) -> bool {
+        !self.dependencies.is_empty() || !self.profile.is_empty() || !self.cargo_features.is_empty()
+    }
+
+    // Edition from CURSED_CODDY_RUST_EDITION (default 2021), profile tweaks from
+    // CURSED_CODDY_RUST_PROFILE ("opt-level=1,overflow-checks=false") and unstable
+    // cargo features from CURSED_CODDY_CARGO_FEATURES
+    fn from_env(dependencies: Vec<String>) -> Self {
+        let edition = match std::env::var("CURSED_CODDY_RUST_EDITION") {
+            Ok(v) if KNOWN_EDITIONS.contains(&v.trim()) => v.trim().to_string(),
+            Ok(v) if !v.trim().is_empty() => {
+                eprintln!("{}", Colors::warning(&format!(
+                    "Unknown Rust edition '{}' in CURSED_CODDY_RUST_EDITION, using {}",
+                    v.trim(),
+                    DEFAULT_EDITION
+                )));
+                DEFAULT_EDITION.to_string()
+            }
+            _ => DEFAULT_EDITION.to_string(),
+        };
+
+        let profile = Self::env_list("CURSED_CODDY_RUST_PROFILE")
+            .into_iter()
+            .filter_map(|entry| {
+                let (key, value) = entry.split_once('=')?;
+                let (key, value) = (key.trim(), value.trim());
+                (!key.is_empty() && !value.is_empty()).then(|| format!("{} = {}", key, value))
+            })
+            .collect();
+
+        CargoOptions {
+            edition,
+            dependencies,
+            profile,
+            cargo_features: Self::env_list("CURSED_CODDY_CARGO_FEATURES"),
         }
-        
+    }
+
+    fn env_list(name: &str) -> Vec<String> {
+        std::env::var(name)
+            .unwrap_or_default()
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+}
+
+/// Whether rustc's errors are read as JSON, so each can be shown with its code, the
+/// line it's on and the compiler's suggested fix; CURSED_CODDY_RUST_DIAGNOSTICS=false
+/// shows rustc's plain output instead. Builds that need Cargo always show plain output.
+pub fn structured_diagnostics() -> bool {
+    std::env::var("CURSED_CODDY_RUST_DIAGNOSTICS")
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+        .unwrap_or(true)
+}
+
+pub struct RustRunner;
+
+impl Runner for RustRunner {
+    fn compile(&self, file_path: &Path) -> Result<Artifact> {
         // Read the code to detect dependencies
         let code = std::fs::read_to_string(file_path)
             .context("Failed to read exercise file")?;
-        
-        let dependencies = Self::detect_dependencies(&code);
-        
+
+        // A Cargo project is much slower to build than a single file, so it's only
+        // used for code that needs one
+        let options = CargoOptions::from_env(Self::detect_dependencies(&code));
+        if !options.needs_cargo() {
+            return Self::compile_with_rustc(file_path, &options.edition);
+        }
+
+        // Resolve cargo before creating the project so a missing toolchain leaves nothing behind
+        let cargo = resolve_tool("Rust", &["cargo"])?;
+
         // Create a temporary Cargo project
         let cargo_project_dir = file_path.parent()
             .unwrap_or_else(|| std::path::Path::new("."))
@@ -31,65 +120,280 @@ impl RustRunner {
         }
         
         std::fs::create_dir_all(&cargo_project_dir)
-            .context("Failed to create Cargo project directory")?;
+            .map_err(|e| fs_error::write_error(e, "Failed to create Cargo project directory", &cargo_project_dir))?;
         
         // Create Cargo.toml
-        let cargo_toml = Self::generate_cargo_toml(&dependencies);
-        std::fs::write(cargo_project_dir.join("Cargo.toml"), cargo_toml)
-            .context("Failed to write Cargo.toml")?;
+        let cargo_toml = Self::generate_cargo_toml(&options);
+        let cargo_toml_path = cargo_project_dir.join("Cargo.toml");
+        std::fs::write(&cargo_toml_path, cargo_toml)
+            .map_err(|e| fs_error::write_error(e, "Failed to write Cargo.toml", &cargo_toml_path))?;
         
         // Create src directory
         let src_dir = cargo_project_dir.join("src");
         std::fs::create_dir_all(&src_dir)
-            .context("Failed to create src directory")?;
+            .map_err(|e| fs_error::write_error(e, "Failed to create src directory", &src_dir))?;
         
         // Copy user code to src/main.rs
-        std::fs::write(src_dir.join("main.rs"), &code)
-            .context("Failed to write main.rs")?;
-        
-        // Run with cargo (show compiler output)
-        let mut child = Command::new("cargo")
-            .arg("run")
-            .current_dir(&cargo_project_dir)
-            .stdin(Stdio::piped())
+        let main_path = src_dir.join("main.rs");
+        std::fs::write(&main_path, &code)
+            .map_err(|e| fs_error::write_error(e, "Failed to write main.rs", &main_path))?;
+
+        // cargo builds and runs in one step, so compilation happens in run(). The
+        // manifest path lets the program itself run in the run directory.
+        let mut artifact = Artifact::new(cargo);
+        artifact.args = vec![
+            "run".to_string(),
+            "--manifest-path".to_string(),
+            cargo_toml_path.display().to_string(),
+            "--".to_string(),
+        ];
+        artifact.cleanup_path = Some(cargo_project_dir);
+        artifact.cargo_run = true;
+        Ok(artifact)
+    }
+
+    // Cargo is only needed for code that uses crates, and is checked when that code is built
+    fn check_toolchain(&self) -> Result<()> {
+        resolve_tool("Rust", &["rustc"]).map(|_| ())
+    }
+
+    fn error_label(&self) -> &'static str {
+        "Compilation or runtime error"
+    }
+
+    fn check(&self, file_path: &Path) -> Result<()> {
+        let Some(output) = Self::check_with_rustc(file_path, Self::error_format_args())? else {
+            return Ok(());
+        };
+        if !output.status.success() {
+            return Err(Self::compile_error(&String::from_utf8_lossy(&output.stderr)));
+        }
+        Ok(())
+    }
+
+    fn warnings(&self, file_path: &Path) -> Result<Vec<String>> {
+        let Some(output) = Self::check_with_rustc(file_path, &["--error-format=json"])? else {
+            return Ok(Vec::new());
+        };
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        let (diagnostics, _) = parse_rustc_output(&String::from_utf8_lossy(&output.stderr));
+        Ok(diagnostics
+            .iter()
+            .filter(|d| d.level == "warning")
+            .map(|d| match d.line {
+                Some(line) => format!("line {}: {}", line, d.headline()),
+                None => d.headline(),
+            })
+            .collect())
+    }
+
+    fn run(&self, artifact: &Artifact, opts: &RunOptions) -> Result<RunOutput> {
+        // A program built with rustc is already compiled and just runs
+        if !artifact.cargo_run {
+            let output = timing::measure(Phase::Tests, || spawn_with_input(artifact, opts))?;
+            return check_output(output, "Runtime error", opts.expected_exit_code);
+        }
+
+        let started = Instant::now();
+        // Once the target folder turned out not to allow running programs, build in the
+        // fallback folder straight away instead of building twice every run
+        let fallback_env = if noexec_found() { Some(Self::fallback_target_env(opts.env)?) } else { None };
+        let opts = &RunOptions { env: fallback_env.as_deref().unwrap_or(opts.env), ..*opts };
+        let mut run_output = spawn_with_input(artifact, opts)?;
+
+        // A killed build can leave a shared CARGO_TARGET_DIR unusable for every later
+        // exercise, so clear it and try once more before blaming the learner's code
+        if !run_output.status.success() && Self::looks_like_corrupt_cache(&run_output.stderr) {
+            println!(
+                "{} {}",
+                Colors::label_warn("RECOVERY"),
+                Colors::warning("The Rust build cache looks corrupted. Cleaning it and retrying the build once...")
+            );
+            Self::clean_build_cache(artifact);
+            run_output = spawn_with_input(artifact, opts)?;
+        }
+        // cargo built the program but the system wouldn't start it, so the target folder
+        // is on a noexec mount; build into the fallback folder instead
+        if fallback_env.is_none() && !run_output.status.success() && Self::looks_like_noexec(&run_output.stderr) {
+            warn_noexec(&Self::target_dir(artifact), &exec_fallback_dir()?);
+            let env = Self::fallback_target_env(opts.env)?;
+            run_output = spawn_with_input(artifact, &RunOptions { env: &env, ..*opts })?;
+        }
+        Self::record_timing(&run_output.stderr, started.elapsed());
+
+        // Always show compiler output (stderr contains compilation messages)
+        if !run_output.stderr.trim().is_empty() {
+            eprintln!("{}", run_output.stderr);
+        }
+
+        check_output(run_output, self.error_label(), opts.expected_exit_code)
+    }
+}
+
+impl RustRunner {
+    /// A complete program from the learner's function: their code as it is, so line
+    /// numbers in errors still match the file, and a main that prints with {:?} what
+    /// the call numbered by its first argument returns
+    pub fn function_harness(code: &str, calls: &[&str]) -> String {
+        let mut program = code.trim_end().to_string();
+        program.push_str("\n\n// Generated for grading: runs the call for the test case given as the argument\n");
+        program.push_str("fn main() {\n    match std::env::args().nth(1).as_deref() {\n");
+        for (idx, call) in calls.iter().enumerate() {
+            program.push_str(&format!("        Some(\"{}\") => println!(\"{{:?}}\", {}),\n", idx, call.trim().trim_end_matches(';')));
+        }
+        program.push_str("        _ => {}\n    }\n}\n");
+        program
+    }
+
+    /// The edition, [profile.dev] settings and unstable cargo features exercise
+    /// projects are built with
+    pub fn build_settings() -> (String, Vec<String>, Vec<String>) {
+        let options = CargoOptions::from_env(Vec::new());
+        (options.edition, options.profile, options.cargo_features)
+    }
+
+    // Builds a dependency-free exercise as a single file. Debug assertions are on
+    // as in Cargo's dev profile, so integer overflow still panics.
+    fn compile_with_rustc(file_path: &Path, edition: &str) -> Result<Artifact> {
+        let rustc = resolve_tool("Rust", &["rustc"])?;
+        let exe_path = file_path.with_extension(std::env::consts::EXE_EXTENSION);
+        let compile_output = Command::new(&rustc)
+            .arg("--edition")
+            .arg(edition)
+            .args(["-C", "debug-assertions=on"])
+            .arg("-o")
+            .arg(&exe_path)
+            .args(Self::error_format_args())
+            .arg(file_path)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to execute cargo run")?;
-
-        if let Some(input_str) = input {
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin
-                    .write_all(input_str.as_bytes())
-                    .context("Failed to write to stdin")?;
-                drop(stdin);
-            }
-        } else {
-            drop(child.stdin.take());
+            .output()
+            .with_context(|| format!("Failed to execute {} command", rustc))?;
+
+        let stderr = String::from_utf8_lossy(&compile_output.stderr);
+        if !compile_output.status.success() {
+            return Err(Self::compile_error(&stderr));
+        }
+        // Warnings, shown as they are for Cargo builds
+        let warnings = if structured_diagnostics() { parse_rustc_output(&stderr).1 } else { stderr.to_string() };
+        if !warnings.trim().is_empty() {
+            eprintln!("{}", warnings);
+        }
+        if !exe_path.is_file() {
+            return Err(anyhow::anyhow!(
+                "{} reported success but no program was built at {}",
+                rustc,
+                exe_path.display()
+            ));
         }
 
-        let run_output = child.wait_with_output()
-            .context("Failed to wait for cargo process")?;
+        let mut artifact = Artifact::new(&exe_path);
+        artifact.cleanup_path = Some(exe_path);
+        Ok(artifact)
+    }
 
-        // Always show compiler output (stderr contains compilation messages)
-        let stderr = String::from_utf8_lossy(&run_output.stderr);
-        if !stderr.trim().is_empty() {
-            eprintln!("{}", stderr);
+    // Type-checks the file without building it; None for code with crates, which
+    // would have to be downloaded and built, too slow for a check. Only metadata
+    // is emitted, which is enough to catch type and borrow errors. Code without a
+    // main is checked as a library.
+    fn check_with_rustc(file_path: &Path, format_args: &[&str]) -> Result<Option<std::process::Output>> {
+        let code = std::fs::read_to_string(file_path).context("Failed to read exercise file")?;
+        if !Self::detect_dependencies(&code).is_empty() {
+            return Ok(None);
         }
 
-        // Cleanup
-        let _ = std::fs::remove_dir_all(&cargo_project_dir);
+        let rustc = resolve_tool("Rust", &["rustc"])?;
+        let crate_type = if active_code(Language::Rust, &code).contains("fn main") { "bin" } else { "lib" };
+        let metadata_path = file_path.with_extension("rmeta");
+        let output = Command::new(&rustc)
+            .arg("--edition")
+            .arg(CargoOptions::from_env(Vec::new()).edition)
+            .args(["--crate-type", crate_type, "--emit=metadata", "-o"])
+            .arg(&metadata_path)
+            .args(format_args)
+            .arg(file_path)
+            .output()
+            .with_context(|| format!("Failed to execute {} command", rustc))?;
+        let _ = std::fs::remove_file(&metadata_path);
+        Ok(Some(output))
+    }
+
+    // rustc's JSON diagnostics, when they're read, carry error codes and suggested fixes
+    fn error_format_args() -> &'static [&'static str] {
+        if structured_diagnostics() { &["--error-format=json"] } else { &[] }
+    }
 
-        if !run_output.status.success() {
-            return Err(anyhow::anyhow!("Compilation or runtime error: {}", stderr));
+    fn compile_error(stderr: &str) -> anyhow::Error {
+        if structured_diagnostics() {
+            compile_error_from(stderr).into()
+        } else {
+            anyhow::anyhow!("Compilation error: {}", stderr)
         }
+    }
 
-        let stdout = String::from_utf8_lossy(&run_output.stdout);
-        Ok(stdout.to_string())
+    // cargo builds and runs in one go, so its "Finished ... in 0.52s" line is what
+    // tells the build apart from the program. Without it the build failed, and the
+    // whole run was compilation.
+    fn record_timing(stderr: &str, elapsed: Duration) {
+        let build = Self::build_time(stderr).unwrap_or(elapsed).min(elapsed);
+        timing::record(Phase::Compilation, build);
+        timing::record(Phase::Tests, elapsed - build);
     }
-    
+
+    // Parses "in 0.52s" or "in 1m 05s" from cargo's Finished line
+    fn build_time(stderr: &str) -> Option<Duration> {
+        let line = stderr.lines().find(|line| line.trim_start().starts_with("Finished"))?;
+        let time = line.rsplit(" in ").next()?.trim().strip_suffix('s')?;
+        let (minutes, seconds) = match time.split_once('m') {
+            Some((minutes, seconds)) => (minutes.trim().parse::<f64>().ok()?, seconds.trim()),
+            None => (0.0, time),
+        };
+        let seconds = seconds.parse::<f64>().ok()?;
+        Some(Duration::from_secs_f64(minutes * 60.0 + seconds))
+    }
+
+    fn looks_like_corrupt_cache(stderr: &str) -> bool {
+        let stderr = stderr.to_lowercase();
+        CORRUPT_CACHE_PATTERNS.iter().any(|pattern| stderr.contains(pattern))
+    }
+
+    // The program's environment with cargo building into the fallback folder
+    fn fallback_target_env(env: &[(String, String)]) -> Result<Vec<(String, String)>> {
+        let mut env = env.to_vec();
+        env.push(("CARGO_TARGET_DIR".to_string(), exec_fallback_dir()?.join("cargo-target").display().to_string()));
+        Ok(env)
+    }
+
+    fn looks_like_noexec(stderr: &str) -> bool {
+        stderr.contains("could not execute process") && stderr.contains("Permission denied")
+    }
+
+    // Where cargo put the program: CARGO_TARGET_DIR, or the exercise project's own target folder
+    fn target_dir(artifact: &Artifact) -> PathBuf {
+        std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .or_else(|| artifact.cleanup_path.as_ref().map(|dir| dir.join("target")))
+            .unwrap_or_default()
+    }
+
+    // Runs `cargo clean` for the exercise project, which honours CARGO_TARGET_DIR
+    fn clean_build_cache(artifact: &Artifact) {
+        let mut command = Command::new(&artifact.program);
+        command.arg("clean").stdout(Stdio::null()).stderr(Stdio::null());
+        if let Some(ref dir) = artifact.cleanup_path {
+            command.arg("--manifest-path").arg(dir.join("Cargo.toml"));
+        }
+        if let Err(e) = command.status() {
+            eprintln!("{}", Colors::warning(&format!("Failed to clean the Rust build cache: {}", e)));
+        }
+    }
+
     fn detect_dependencies(code: &str) -> Vec<String> {
         let mut deps = Vec::new();
+        // A commented-out `use rand::Rng;` shouldn't pull in a crate
+        let code = active_code(Language::Rust, code);
         
         // Common crate patterns to detect
         let crate_patterns = vec![
@@ -112,20 +416,62 @@ impl RustRunner {
         deps
     }
     
-    fn generate_cargo_toml(dependencies: &[String]) -> String {
-        let deps_section = if dependencies.is_empty() {
-            String::new()
-        } else {
-            format!("\n[dependencies]\n{}\n", dependencies.join("\n"))
-        };
-        
-        format!(
+    fn generate_cargo_toml(options: &CargoOptions) -> String {
+        let mut toml = String::new();
+
+        // cargo-features must come before [package]
+        if !options.cargo_features.is_empty() {
+            let features: Vec<String> = options.cargo_features.iter().map(|f| format!("\"{}\"", f)).collect();
+            toml.push_str(&format!("cargo-features = [{}]\n\n", features.join(", ")));
+        }
+
+        toml.push_str(&format!(
             r#"[package]
 name = "exercise"
 version = "0.1.0"
-edition = "2021"
-{}"#,
-            deps_section
-        )
+edition = "{}"
+"#,
+            options.edition
+        ));
+
+        if !options.dependencies.is_empty() {
+            toml.push_str(&format!("\n[dependencies]\n{}\n", options.dependencies.join("\n")));
+        }
+
+        if !options.profile.is_empty() {
+            toml.push_str(&format!("\n[profile.dev]\n{}\n", options.profile.join("\n")));
+        }
+
+        toml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_time_is_read_from_cargos_finished_line() {
+        let stderr = "   Compiling exercise v0.1.0 (/tmp/exercise)\n    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.52s\n     Running `target/debug/exercise`\n";
+        assert_eq!(RustRunner::build_time(stderr), Some(Duration::from_millis(520)));
+
+        let slow = "    Finished `dev` profile [unoptimized + debuginfo] target(s) in 1m 05s\n";
+        assert_eq!(RustRunner::build_time(slow), Some(Duration::from_secs(65)));
+
+        // A failed build has no Finished line
+        assert_eq!(RustRunner::build_time("error[E0425]: cannot find value `x` in this scope\n"), None);
+    }
+
+    #[test]
+    fn only_a_damaged_build_cache_counts_as_corrupt() {
+        let invalid_metadata = "error[E0786]: found invalid metadata files for crate `core`\n  |\n  = note: failed to mmap file '/tmp/target/debug/deps/libcore.rlib'";
+        let stale = "error[E0514]: found crate `rand` compiled by an incompatible version of rustc";
+        assert!(RustRunner::looks_like_corrupt_cache(invalid_metadata));
+        assert!(RustRunner::looks_like_corrupt_cache(stale));
+
+        // The learner's own mistakes, even ones mentioning files or locks
+        let learner = "error[E0425]: cannot find value `fingerprint` in this scope\n    Blocking waiting for file lock on build directory";
+        assert!(!RustRunner::looks_like_corrupt_cache(learner));
+        assert!(!RustRunner::looks_like_corrupt_cache("error: failed to write to output file: No space left on device"));
     }
 }
@@ -0,0 +1,216 @@
+use crate::config::Language;
+
+/// Blanks out comments and the contents of string and character literals, so
+/// heuristics that scan source text (dependency detection, "has a main?") only
+/// see active code. Not a parser: it knows each language's comment markers and
+/// basic literals, and keeps every newline and quote so line numbers and the
+/// shape of the code stay the same.
+pub fn active_code(language: Language, code: &str) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len());
+    let mut i = 0;
+
+    // A JavaScript file may start with a #! line for running it directly
+    if language == Language::JavaScript && code.starts_with("#!") {
+        while i < chars.len() && chars[i] != '\n' {
+            out.push(' ');
+            i += 1;
+        }
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match (c, next) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    out.push(' ');
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => i = skip_block_comment(&chars, i, language == Language::Rust, &mut out),
+            ('r', Some('"' | '#')) if language == Language::Rust && starts_raw_string(&chars, i) => {
+                i = skip_rust_raw_string(&chars, i, &mut out);
+            }
+            // Only Rust strings and JavaScript template literals may span lines
+            ('"', _) => i = skip_quoted(&chars, i, '"', language == Language::Rust, &mut out),
+            ('`', _) if language == Language::JavaScript => i = skip_quoted(&chars, i, '`', true, &mut out),
+            ('\'', _) if language == Language::JavaScript => i = skip_quoted(&chars, i, '\'', false, &mut out),
+            // In Rust a quote also starts a lifetime ('a), so only a quote that closes
+            // right after one character or escape counts as a literal
+            ('\'', _) => match char_literal_end(&chars, i) {
+                Some(end) => {
+                    out.push('\'');
+                    out.extend(std::iter::repeat_n(' ', end - i - 1));
+                    out.push('\'');
+                    i = end + 1;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+// Blanks a /* */ comment starting at `start` and returns the index after it.
+// Rust block comments nest; C++ and JavaScript ones end at the first */.
+fn skip_block_comment(chars: &[char], start: usize, nested: bool, out: &mut String) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        let pair = (chars[i], chars.get(i + 1).copied());
+        if pair == ('/', Some('*')) && (nested || depth == 0) {
+            depth += 1;
+            out.push_str("  ");
+            i += 2;
+        } else if pair == ('*', Some('/')) {
+            depth -= 1;
+            out.push_str("  ");
+            i += 2;
+            if depth == 0 {
+                break;
+            }
+        } else {
+            out.push(blank(chars[i]));
+            i += 1;
+        }
+    }
+    i
+}
+
+// Blanks the contents of a literal delimited by `quote`, honouring backslash
+// escapes, and returns the index after the closing quote. A single-line literal
+// left unclosed stops at the end of its line instead of blanking the rest of the file.
+fn skip_quoted(chars: &[char], start: usize, quote: char, multiline: bool, out: &mut String) -> usize {
+    out.push(quote);
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                out.push(' ');
+                if let Some(&escaped) = chars.get(i + 1) {
+                    out.push(blank(escaped));
+                }
+                i += 2;
+            }
+            c if c == quote => {
+                out.push(quote);
+                return i + 1;
+            }
+            '\n' if !multiline => return i,
+            c => {
+                out.push(blank(c));
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+// r"..." or r#"..."#, and not an identifier ending in r such as `for`
+fn starts_raw_string(chars: &[char], start: usize) -> bool {
+    if start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        return false;
+    }
+    let hashes = chars[start + 1..].iter().take_while(|&&c| c == '#').count();
+    chars.get(start + 1 + hashes) == Some(&'"')
+}
+
+fn skip_rust_raw_string(chars: &[char], start: usize, out: &mut String) -> usize {
+    let hashes = chars[start + 1..].iter().take_while(|&&c| c == '#').count();
+    let body = start + 2 + hashes;
+    out.push('r');
+    out.extend(std::iter::repeat_n('#', hashes));
+    out.push('"');
+    let mut i = body;
+    while i < chars.len() {
+        if chars[i] == '"' && chars[i + 1..].iter().take(hashes).filter(|&&c| c == '#').count() == hashes {
+            out.push('"');
+            out.extend(std::iter::repeat_n('#', hashes));
+            return i + 1 + hashes;
+        }
+        out.push(blank(chars[i]));
+        i += 1;
+    }
+    i
+}
+
+// Index of the closing quote of a character literal such as 'a' or '\n' or '\u{1F600}'
+fn char_literal_end(chars: &[char], start: usize) -> Option<usize> {
+    match chars.get(start + 1)? {
+        '\\' => (start + 2..chars.len().min(start + 12)).find(|&i| chars[i] == '\''),
+        '\'' | '\n' => None,
+        _ => (chars.get(start + 2) == Some(&'\'')).then_some(start + 2),
+    }
+}
+
+// Keeps line breaks so line numbers still match
+fn blank(c: char) -> char {
+    if c == '\n' { '\n' } else { ' ' }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_are_blanked_but_keep_their_lines() {
+        let code = "fn helper() {} // fn main() {}\n/* fn main() {\n} */\nfn main() {}\n";
+        let active = active_code(Language::Rust, code);
+
+        assert_eq!(active.matches("fn main").count(), 1);
+        assert!(active.starts_with("fn helper() {}"));
+        assert_eq!(active.lines().count(), code.lines().count());
+        assert_eq!(active.chars().count(), code.chars().count());
+    }
+
+    #[test]
+    fn rust_block_comments_nest_and_others_do_not() {
+        let code = "/* outer /* inner */ still comment */ code";
+        assert_eq!(active_code(Language::Rust, code).trim(), "code");
+        // In C++ the first */ ends the comment
+        assert!(active_code(Language::Cpp, code).contains("still comment */ code"));
+    }
+
+    #[test]
+    fn string_contents_are_blanked() {
+        let code = "fn greet() { println!(\"fn main() {{ \\\" }}\"); }\n";
+        let active = active_code(Language::Rust, code);
+        assert!(!active.contains("main"));
+        assert!(active.contains("println!(\""));
+        assert!(active.ends_with("\"); }\n"));
+
+        let js = "const s = 'require(\"rand\")'; const t = `\n${x} // not a comment`;\nrun();";
+        let active = active_code(Language::JavaScript, js);
+        assert!(!active.contains("require"));
+        assert!(!active.contains("not a comment"));
+        assert!(active.ends_with("\nrun();"));
+    }
+
+    #[test]
+    fn raw_strings_end_at_their_own_hashes() {
+        let code = "let s = r#\"say \"fn main\" // here\"#; fn main() {}";
+        let active = active_code(Language::Rust, code);
+        assert_eq!(active.matches("fn main").count(), 1);
+        assert!(active.starts_with("let s = r#\""));
+        assert!(active.ends_with("\"#; fn main() {}"));
+
+        // An identifier ending in r is not a raw string
+        assert_eq!(active_code(Language::Rust, "for\"x\""), "for\" \"");
+    }
+
+    #[test]
+    fn lifetimes_are_not_character_literals() {
+        let code = "fn first<'a>(s: &'a str) -> char { 'x' }";
+        let active = active_code(Language::Rust, code);
+        assert!(active.contains("<'a>(s: &'a str)"));
+        assert!(active.ends_with("{ ' ' }"));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::cli::exit_code;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+// Resolved command per candidate list, so each toolchain is probed only once per run
+static RESOLVED: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+// What running `<tool> --version` showed about a candidate
+enum Probe {
+    Works,
+    Missing,
+    /// It's there but doesn't work: how `--version` failed, and the first line it printed
+    Broken(String, Option<String>),
+}
+
+/// Returns the first candidate command that works, e.g. `g++` falling back to
+/// `clang++`. `toolchain` names the language in error messages. A working
+/// command is cached for the rest of the session.
+pub fn resolve_tool(toolchain: &str, candidates: &[&str]) -> Result<String> {
+    let key = candidates.join("|");
+    let cache = RESOLVED.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some(found) = cache.lock().ok().and_then(|c| c.get(&key).cloned()) {
+        return Ok(found);
+    }
+
+    // A broken candidate is only reported when none of the others works either
+    let mut broken = None;
+    for name in candidates {
+        match probe(name) {
+            Probe::Works => {
+                if let Ok(mut c) = cache.lock() {
+                    c.insert(key, name.to_string());
+                }
+                return Ok(name.to_string());
+            }
+            Probe::Broken(reason, output) if broken.is_none() => broken = Some((name, reason, output)),
+            _ => {}
+        }
+    }
+
+    Err(match broken {
+        Some((name, reason, output)) => exit_code::toolchain_error(format!(
+            "Your {} toolchain appears broken: `{} --version` failed ({}){}. Reinstall it or check which {} is first on your PATH.",
+            toolchain,
+            name,
+            reason,
+            output.map(|line| format!(": {}", line.trim_end_matches('.'))).unwrap_or_default(),
+            name
+        )),
+        None => exit_code::toolchain_error(format!(
+            "No {} toolchain found (tried: {}). Install one of them and make sure it is on your PATH.",
+            toolchain,
+            candidates.join(", ")
+        )),
+    })
+}
+
+fn probe(name: &str) -> Probe {
+    let output = match Command::new(name)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Probe::Missing,
+        Err(e) => return Probe::Broken(e.to_string(), None),
+    };
+    if output.status.success() {
+        return Probe::Works;
+    }
+
+    // The tool's own first line usually says what's wrong, e.g. rustup's "toolchain is not installed"
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_line = stderr.lines().map(str::trim).find(|line| !line.is_empty()).map(str::to_string);
+    Probe::Broken(output.status.to_string(), first_line)
+}
@@ -0,0 +1,57 @@
+use crate::cli::colors::Colors;
+use crate::config::curriculum::Stage;
+use crate::config::{Difficulty, Language, LessonType};
+use crate::ollama::{formatter::GeneratedContent, Generator};
+use anyhow::Result;
+
+/// Where a lesson's content comes from, so the lesson flow doesn't depend on a
+/// running model (the curated lessons work offline, and tests can return fixed content)
+pub trait ContentSource {
+    /// `generator` is the lesson's own, with its exercise count, verbosity and other
+    /// options applied, for sources that generate
+    fn get_content(
+        &self,
+        generator: &Generator,
+        language: Language,
+        difficulty: Difficulty,
+        lesson_type: LessonType,
+        topic: &str,
+    ) -> Result<GeneratedContent>;
+}
+
+/// A lesson generated by the model on the topic
+pub struct GeneratorSource;
+
+impl ContentSource for GeneratorSource {
+    fn get_content(
+        &self,
+        generator: &Generator,
+        language: Language,
+        difficulty: Difficulty,
+        lesson_type: LessonType,
+        topic: &str,
+    ) -> Result<GeneratedContent> {
+        println!("{}", Colors::info("Generating lesson content..."));
+        generator.generate(language, difficulty, lesson_type, topic)
+    }
+}
+
+/// A whole curriculum stage as one generated lesson, for learners who prefer one
+/// longer session per stage over a lesson per topic
+pub struct StageSource {
+    pub stage: Stage,
+}
+
+impl ContentSource for StageSource {
+    fn get_content(
+        &self,
+        generator: &Generator,
+        language: Language,
+        difficulty: Difficulty,
+        _lesson_type: LessonType,
+        _topic: &str,
+    ) -> Result<GeneratedContent> {
+        println!("{}", Colors::info("Generating lesson content..."));
+        generator.generate_stage(language, &self.stage, difficulty)
+    }
+}
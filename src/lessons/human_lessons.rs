@@ -1,5 +1,10 @@
+use crate::cli::colors::Colors;
 use crate::config::{Difficulty, Language, LessonType};
+use crate::lessons::ContentSource;
 use crate::ollama::formatter::{CodeExample, Exercise, GeneratedContent, TestCase};
+use crate::ollama::Generator;
+use crate::progress::topic_slug;
+use anyhow::Result;
 
 #[derive(Clone)]
 pub struct HumanLesson {
@@ -7,10 +12,57 @@ pub struct HumanLesson {
     pub language: Language,
     pub difficulty: Difficulty,
     pub lesson_type: LessonType,
+    /// What the lesson teaches, for finding it by topic offline
+    pub topics: &'static [&'static str],
 }
 
 pub struct HumanLessons;
 
+impl HumanLesson {
+    /// Whether the lesson teaches `topic`: one of its topics, ignoring case and
+    /// punctuation, or a topic mentioning one ("rust printing" covers "printing")
+    fn covers(&self, topic: &str) -> bool {
+        // Compared word by word, so "Hello, World!" is "hello world" and "print" isn't "printing"
+        let words = |text: &str| format!(" {} ", topic_slug(text).split('-').filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" "));
+        let wanted = words(topic);
+        self.topics.iter().any(|own| wanted.contains(&words(own)))
+    }
+}
+
+/// Serves the curated lessons, for studying without a model. Only a lesson on the
+/// requested topic and of the requested type is served, so progress is never recorded
+/// under a topic the learner didn't study.
+pub struct HumanLessonSource;
+
+impl ContentSource for HumanLessonSource {
+    fn get_content(
+        &self,
+        _generator: &Generator,
+        language: Language,
+        difficulty: Difficulty,
+        lesson_type: LessonType,
+        topic: &str,
+    ) -> Result<GeneratedContent> {
+        if let Some(lesson) = HumanLessons::find_lesson(language, difficulty, lesson_type, topic) {
+            println!("{}", Colors::info(&format!("No prefetched lesson on '{}'. Using the built-in lesson.", topic)));
+            return Ok(lesson.content);
+        }
+        let available: Vec<String> = HumanLessons::get_all_lessons()
+            .iter()
+            .filter(|lesson| lesson.language == language)
+            .map(|lesson| format!("{} ({})", lesson.topics.join(", "), lesson.lesson_type.display_name()))
+            .collect();
+        Err(anyhow::anyhow!(
+            "No prefetched or built-in {} {} lesson on '{}' is available offline. Built-in {} lessons: {}. Run 'prefetch' while online to study other topics offline.",
+            lesson_type.display_name(),
+            language.display_name(),
+            topic,
+            language.display_name(),
+            if available.is_empty() { "none".to_string() } else { available.join("; ") }
+        ))
+    }
+}
+
 impl HumanLessons {
     fn get_all_lessons() -> Vec<HumanLesson> {
         vec![
@@ -19,24 +71,28 @@ impl HumanLessons {
                 language: Language::Rust,
                 difficulty: Difficulty::Beginner,
                 lesson_type: LessonType::Short,
+                topics: &["hello world", "printing"],
             },
             HumanLesson {
                 content: Self::rust_leeson_two(),
                 language: Language::Rust,
                 difficulty: Difficulty::Beginner,
                 lesson_type: LessonType::Short,
+                topics: &["printing variables"],
             },
             HumanLesson {
                 content: Self::javascript_hello_world(),
                 language: Language::JavaScript,
                 difficulty: Difficulty::Beginner,
                 lesson_type: LessonType::Short,
+                topics: &["hello world", "printing"],
             },
             HumanLesson {
                 content: Self::cpp_hello_world(),
                 language: Language::Cpp,
                 difficulty: Difficulty::Beginner,
                 lesson_type: LessonType::Short,
+                topics: &["hello world", "printing"],
             },
         ]
     }
@@ -87,6 +143,19 @@ impl HumanLessons {
         all_lessons.iter().filter(|lesson| lesson.language == language).count()
     }
 
+    /// A curated lesson of this type on the topic, preferring one at the given difficulty
+    pub fn find_lesson(language: Language, difficulty: Difficulty, lesson_type: LessonType, topic: &str) -> Option<HumanLesson> {
+        let matching: Vec<HumanLesson> = Self::get_all_lessons()
+            .into_iter()
+            .filter(|lesson| lesson.language == language && lesson.lesson_type == lesson_type && lesson.covers(topic))
+            .collect();
+        matching
+            .iter()
+            .find(|lesson| lesson.difficulty == difficulty)
+            .or_else(|| matching.first())
+            .cloned()
+    }
+
     fn rust_hello_world() -> GeneratedContent {
         GeneratedContent {
             concept: "Welcome to Rust! In this lesson, you'll learn how to write your first Rust program. The 'Hello, World!' program is a traditional first program that prints a message to the console.".to_string(),
@@ -99,10 +168,12 @@ impl HumanLessons {
                 CodeExample {
                     code: "fn main() {\n    println!(\"Hello, World!\");\n}".to_string(),
                     explanation: "This is the simplest Rust program. It defines a main function and prints 'Hello, World!' to the console.".to_string(),
+                    check_error: None,
                 },
                 CodeExample {
                     code: "fn main() {\n    let message = \"Hello, Rust!\";\n    println!(\"{}\", message);\n}".to_string(),
                     explanation: "This example shows how to store a string in a variable and then print it using println! with formatting.".to_string(),
+                    check_error: None,
                 },
             ],
             syntax_guide: "Key Rust syntax:\n- fn main() defines the entry point\n- println!() is a macro for printing\n- Semicolons end statements\n- Curly braces {} define code blocks".to_string(),
@@ -122,12 +193,23 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("Your Name".to_string()),
+                    starter_code: Some("fn main() {\n    // TODO: print your name here\n}\n".to_string()),
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "Your Name".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
                 Exercise {
                     title: "Print Multiple Messages".to_string(),
@@ -138,14 +220,29 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("First message\nSecond message\nThird message".to_string()),
+                    starter_code: None,
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "First message\nSecond message\nThird message".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
             ],
+            quiz: Vec::new(),
+            is_fallback: false,
+            fallback_reason: None,
+            model: None,
         }
     }
 
@@ -161,10 +258,12 @@ impl HumanLessons {
                 CodeExample {
                     code: "fn main() {\n    println!(\"Hello, World!\");\n}".to_string(),
                     explanation: "This is the simplest Rust program. It defines a main function and prints 'Hello, World!' to the console.".to_string(),
+                    check_error: None,
                 },
                 CodeExample {
                     code: "fn main() {\n    let message = \"Hello, Rust!\";\n    println!(\"{}\", message);\n}".to_string(),
                     explanation: "This example shows how to store a string in a variable and then print it using println! with formatting.".to_string(),
+                    check_error: None,
                 },
             ],
             syntax_guide: "Key Rust syntax:\n- fn main() defines the entry point\n- println!() is a macro for printing\n- Semicolons end statements\n- Curly braces {} define code blocks".to_string(),
@@ -184,12 +283,23 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("Your Name".to_string()),
+                    starter_code: Some("fn main() {\n    // TODO: print your name here\n}\n".to_string()),
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "Your Name".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
                 Exercise {
                     title: "Print Multiple Messages".to_string(),
@@ -200,14 +310,29 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("First message\nSecond message\nThird message".to_string()),
+                    starter_code: None,
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "First message\nSecond message\nThird message".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
             ],
+            quiz: Vec::new(),
+            is_fallback: false,
+            fallback_reason: None,
+            model: None,
         }
     }
 
@@ -223,10 +348,12 @@ impl HumanLessons {
                 CodeExample {
                     code: "console.log('Hello, World!');".to_string(),
                     explanation: "This is the simplest JavaScript program. It uses console.log to print 'Hello, World!' to the console.".to_string(),
+                    check_error: None,
                 },
                 CodeExample {
                     code: "const message = 'Hello, JavaScript!';\nconsole.log(message);".to_string(),
                     explanation: "This example shows how to store a string in a constant variable and then print it using console.log.".to_string(),
+                    check_error: None,
                 },
             ],
             syntax_guide: "Key JavaScript syntax:\n- console.log() prints to console\n- const declares a constant variable\n- Semicolons are optional but recommended\n- Strings can use single or double quotes".to_string(),
@@ -245,12 +372,23 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("Your Name".to_string()),
+                    starter_code: Some("// TODO: print your name here with console.log\n".to_string()),
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "Your Name".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
                 Exercise {
                     title: "Print Multiple Messages".to_string(),
@@ -261,14 +399,29 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("First message\nSecond message\nThird message".to_string()),
+                    starter_code: None,
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "First message\nSecond message\nThird message".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
             ],
+            quiz: Vec::new(),
+            is_fallback: false,
+            fallback_reason: None,
+            model: None,
         }
     }
 
@@ -285,10 +438,12 @@ impl HumanLessons {
                 CodeExample {
                     code: "#include <iostream>\n\nint main() {\n    std::cout << \"Hello, World!\" << std::endl;\n    return 0;\n}".to_string(),
                     explanation: "This is the simplest C++ program. It includes iostream, defines main, and uses std::cout to print 'Hello, World!'.".to_string(),
+                    check_error: None,
                 },
                 CodeExample {
                     code: "#include <iostream>\n\nint main() {\n    std::string message = \"Hello, C++!\";\n    std::cout << message << std::endl;\n    return 0;\n}".to_string(),
                     explanation: "This example shows how to store a string in a variable and then print it using std::cout.".to_string(),
+                    check_error: None,
                 },
             ],
             syntax_guide: "Key C++ syntax:\n- #include <iostream> for input/output\n- int main() is the entry point\n- std::cout << for output\n- return 0 indicates success\n- Semicolons end statements".to_string(),
@@ -308,12 +463,23 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("Your Name".to_string()),
+                    starter_code: Some("#include <iostream>\n\nint main() {\n    // TODO: print your name here with std::cout\n    return 0;\n}\n".to_string()),
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "Your Name".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
                 Exercise {
                     title: "Print Multiple Messages".to_string(),
@@ -324,14 +490,45 @@ impl HumanLessons {
                     ],
                     example_input: None,
                     example_output: Some("First message\nSecond message\nThird message".to_string()),
+                    starter_code: None,
                     test_cases: vec![
                         TestCase {
                             input: "".to_string(),
                             output: "First message\nSecond message\nThird message".to_string(),
+                            label: None,
+                            args: vec![],
+                            output_pattern: None,
+                            expected_exit_code: None,
+                            expected_file: None,
+                            input_file: None,
+                            comparison: None,
                         },
                     ],
+                    require_clean: false,
+                    prints_nothing: false,
+                    function: None,
                 },
             ],
+            quiz: Vec::new(),
+            is_fallback: false,
+            fallback_reason: None,
+            model: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_lessons_are_only_found_by_their_topic_and_type() {
+        let find = |lesson_type, topic| HumanLessons::find_lesson(Language::JavaScript, Difficulty::Beginner, lesson_type, topic);
+        assert!(find(LessonType::Short, "Hello, World").is_some());
+        assert!(find(LessonType::Short, "javascript printing").is_some());
+        assert!(find(LessonType::Short, "closures").is_none());
+        assert!(find(LessonType::Long, "printing").is_none());
+        // A word that only starts like a topic isn't it
+        assert!(find(LessonType::Short, "print").is_none());
+    }
+}
@@ -1,35 +1,37 @@
-use crate::cli::colors::Colors;
-use crate::config::Language;
-use crate::lessons::{HumanLessons, LessonManager};
+use crate::cli::colors::{Borders, Colors};
+use crate::config::curriculum::Stage;
+use crate::config::{Difficulty, Language, LessonType};
+use crate::lessons::{HumanLessons, LessonEnd, LessonManager, StageSource};
 use crate::progress::Tracker;
 use anyhow::Result;
 use colored::Colorize;
 use inquire::Confirm;
+// Width of the overall progress bar in the journey status view
+const PROGRESS_BAR_WIDTH: usize = 20;
+
 
 pub struct JourneyManager {
-    lesson_manager: LessonManager,
     tracker: Tracker,
 }
 
 impl JourneyManager {
     pub fn new() -> Result<Self> {
         Ok(Self {
-            lesson_manager: LessonManager::new()?,
             tracker: Tracker::new()?,
         })
     }
 
-    pub fn start_or_continue_journey(&self, language: Language) -> Result<()> {
-        let mut journey_progress = self.tracker.get_journey_progress()?;
+    /// Plays the journey's next lessons. `difficulty_override` replaces each lesson's
+    /// difficulty for this session only; the curriculum and saved journey keep theirs.
+    pub fn start_or_continue_journey(&self, language: Language, difficulty_override: Option<Difficulty>) -> Result<()> {
+        let mut journey_progress = self.tracker.get_journey_progress(language)?;
 
-        // Start new journey if none exists or different language
-        let needs_new_journey = journey_progress.is_none() || 
-            journey_progress.as_ref().map(|j| j.language != language).unwrap_or(true);
-        if needs_new_journey {
+        // Start a new journey if this language doesn't have one yet
+        if journey_progress.is_none() {
             println!();
             println!("{}", Colors::primary("Starting Learning Journey!").bold());
-            println!("{}", Colors::primary(&"=".repeat(60)));
-            println!("Language: {}", Colors::warning(language.display_name()));
+            Borders::print_rule();
+            println!("Language: {}", Colors::language_badge(language));
             println!("{}", Colors::info("Human-made lessons with guided progression\n"));
             
             self.tracker.start_journey(language)?;
@@ -38,14 +40,14 @@ impl JourneyManager {
         // Continue from current position
         loop {
             // Reload progress at start of each iteration
-            journey_progress = self.tracker.get_journey_progress()?;
+            journey_progress = self.tracker.get_journey_progress(language)?;
             let journey = match journey_progress.as_ref() {
                 Some(j) => j,
                 None => break,
             };
 
             // Use current_stage as the last lesson index
-            let last_index = if journey.current_stage == 0 && journey.completed_topics.is_empty() {
+            let last_index = if journey.current_stage == 0 && journey.completed_topics.is_empty() && journey.skipped_topics.is_empty() {
                 None // First lesson
             } else {
                 Some(journey.current_stage)
@@ -61,7 +63,16 @@ impl JourneyManager {
                         println!();
                         println!("{}", Colors::label_pass("SUCCESS").bold());
                         println!("{}", Colors::success("Congratulations! You've completed all human-made lessons for this language!").bold());
-                        self.tracker.reset_journey()?;
+                        self.tracker.complete_journey(language)?;
+                        return Ok(());
+                    } else if total_lessons > 0 {
+                        // Reached the end, but some lessons were skipped rather than learned
+                        // (journeys saved by earlier versions could skip lessons)
+                        let skipped = journey.skipped_topics.len();
+                        println!();
+                        println!("{}", Colors::label_warn("INCOMPLETE").bold());
+                        println!("{}", Colors::warning(&format!("You've reached the end of the journey, but {} lesson(s) were skipped and not counted.", skipped)));
+                        println!("{}", Colors::info("Run 'cursed-coddy journey' and pick 'Reset a journey' to work through them again."));
                         return Ok(());
                     } else {
                         println!();
@@ -75,16 +86,26 @@ impl JourneyManager {
             let lesson_title_short = lesson.content.concept.split('.').next().unwrap_or("Human-made lesson").to_string();
             let lesson_title = format!("Lesson {}: {}", lesson_index + 1, lesson_title_short);
 
-            println!("\n{}", Colors::primary(&"=".repeat(60)));
+            println!();
+            Borders::print_rule();
             println!("{}", Colors::primary(&format!("Lesson {}: {}", lesson_index + 1, lesson_title_short)).bold());
-            println!("{}", Colors::warning(&format!("Difficulty: {}", lesson.difficulty.display_name())));
-            println!("{}", Colors::primary(&"=".repeat(60)));
+            let difficulty = difficulty_override.unwrap_or(lesson.difficulty);
+            println!("Difficulty: {}", Colors::difficulty_badge(difficulty));
+            Borders::print_rule();
 
             // Start the lesson
             let topic = format!("Human-made lesson {}", lesson_index + 1);
-            let lesson_result = self.lesson_manager.start_lesson_with_content(
+            // Exercises already finished in this lesson before the learner left it
+            let exercises_completed = journey
+                .checkpoint
+                .filter(|c| c.lesson_index == lesson_index)
+                .map(|c| c.exercises_completed)
+                .unwrap_or(0);
+            let lesson_result = LessonManager::new()?
+                .with_journey_lesson(lesson_index, exercises_completed)
+                .start_lesson_with_content(
                 lesson.language,
-                lesson.difficulty,
+                difficulty,
                 lesson.lesson_type,
                 topic,
                 lesson.content,
@@ -92,11 +113,13 @@ impl JourneyManager {
             
             // Mark lesson as completed if finished successfully
             match lesson_result {
-                Ok(_) => {
-                    self.tracker.complete_journey_lesson(lesson_index, lesson_title)?;
+                Ok(LessonEnd::Finished) => {
+                    self.tracker.complete_journey_lesson(language, lesson_index, lesson_title)?;
                 }
+                // Already said how to resume; the checkpoint holds the exercise it stopped at
+                Ok(LessonEnd::Paused) => return Ok(()),
                 Err(_e) => {
-                    println!("\n{}", Colors::warning("Lesson not completed. Progress not saved."));
+                    println!("\n{}", Colors::warning("Lesson not completed, so it isn't marked as learned yet."));
                     println!("{}", Colors::info("Use 'cursed-coddy journey' to continue from where you left off."));
                     return Ok(());
                 }
@@ -120,24 +143,68 @@ impl JourneyManager {
         Ok(())
     }
 
+    /// Generates one lesson covering every topic of a curriculum stage instead of a
+    /// lesson per topic, at the stage's difficulty unless overridden. The saved
+    /// journey follows the curated lessons, so this doesn't move it.
+    pub fn start_stage_lesson(&self, language: Language, stage: &Stage, difficulty_override: Option<Difficulty>) -> Result<()> {
+        println!();
+        Borders::print_rule();
+        println!("{}", Colors::primary(&format!("Stage: {}", stage.name)).bold());
+        println!("{}", Colors::muted(&stage.description));
+        println!("Topics: {}", Colors::warning(&stage.topics.join(", ")));
+        Borders::print_rule();
+
+        let difficulty = difficulty_override.unwrap_or(stage.difficulty);
+        let exercise_count = stage.combined_exercise_count();
+        LessonManager::new()?
+            .with_exercise_count(Some(exercise_count))
+            .with_source(Box::new(StageSource { stage: stage.clone() }))
+            .start_lesson(language, difficulty, LessonType::Long, stage.combined_topic())
+    }
+
     pub fn show_journey_status(&self) -> Result<()> {
-        let journey_progress = self.tracker.get_journey_progress()?;
+        let journeys = self.tracker.get_journeys()?;
+
+        if journeys.is_empty() {
+            println!("{}", Colors::warning("No active journey. Start one with 'cursed-coddy journey'."));
+            return Ok(());
+        }
 
-        if let Some(journey) = journey_progress {
-            println!("\n{}", Colors::primary("Learning Journey Status").bold());
-            println!("{}", Colors::primary(&"=".repeat(60)));
-            println!("Language: {}", Colors::warning(journey.language.display_name()));
+        println!("\n{}", Colors::primary("Learning Journey Status").bold());
+        for journey in &journeys {
+            let total_lessons = HumanLessons::get_total_lessons_for_language(journey.language);
+            Borders::print_rule();
+            println!("Language: {}", Colors::language_badge(journey.language));
             println!("Current Lesson: {}", Colors::success(&format!("Lesson {}", journey.current_stage + 1)));
-            println!("Lessons Completed: {}", Colors::success(&journey.completed_topics.len().to_string()));
-            
+            println!("Lessons Completed: {}", Colors::success(&format!("{}/{}", journey.completed_topics.len(), total_lessons)));
+
+            // Overall completion across every lesson in this language
+            let completed = journey.completed_topics.len().min(total_lessons);
+            let percent = (completed * 100).checked_div(total_lessons).unwrap_or(0);
+            println!(
+                "Overall Progress: {} {}",
+                Colors::primary(&Borders::progress_bar(completed, total_lessons, PROGRESS_BAR_WIDTH)),
+                Colors::success(&format!("{}%", percent))
+            );
+            let remaining = total_lessons - completed;
+            if remaining == 0 {
+                println!("{}", Colors::success("All lessons completed!"));
+            } else {
+                println!("{}", Colors::info(&format!(
+                    "{} lesson{} remaining in the {} journey",
+                    remaining,
+                    if remaining == 1 { "" } else { "s" },
+                    journey.language.display_name()
+                )));
+            }
+
             if !journey.completed_topics.is_empty() {
                 println!("\n{}", Colors::success("Completed Lessons:"));
                 for lesson in &journey.completed_topics {
                     println!("  {} {}", Colors::label_pass("OK"), Colors::success(lesson));
                 }
             }
-        } else {
-            println!("{}", Colors::warning("No active journey. Start one with 'cursed-coddy journey'."));
+            println!();
         }
 
         Ok(())
@@ -1,45 +1,81 @@
-use crate::cli::{banner, colors::{Borders, Colors}};
-use crate::config::{Difficulty, Language, LessonType};
-use crate::execution::{Executor, FileManager};
-use crate::ollama::{formatter::GeneratedContent, Generator};
-use crate::progress::Tracker;
-use anyhow::Result;
+use crate::cli::{banner, colors::{Borders, Colors}, exit_code, fs_error, output, timing};
+use crate::config::{quiz_enabled, review_after_attempts, Difficulty, HintBudget, Language, LessonType, Verbosity, MAX_EXERCISES};
+use crate::execution::{compile_error, CompileError, Diagnostic, ExecutionResult, Executor, FileManager, RustRunner};
+use crate::lessons::watch::{self, WatchEvent};
+use crate::lessons::{print_style_notes, project, ContentSource, GeneratorSource};
+use crate::ollama::{formatter::{CodeExample, FunctionSpec, GeneratedContent, LessonPack, LessonPackMeta, QuizQuestion, TestCase, TopicReference}, Generator};
+use crate::progress::{topic_slug, CachedLesson, Tracker};
+use anyhow::{Context, Result};
 use colored::Colorize;
-use inquire::{Confirm, Text};
+use inquire::{Confirm, Select, Text};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Terminal width for text wrapping (default to 78, leaving margin)
 const TERMINAL_WIDTH: usize = 78;
 
-// Helper function to wrap text to terminal width
+// Helper function to wrap text to terminal width. Existing newlines are kept as
+// hard line breaks and each line is wrapped on its own, so bullet lists survive.
 fn wrap_text(text: &str, width: usize, indent: usize) -> String {
-    let indent_str = " ".repeat(indent);
-    let mut result = String::new();
-    let mut current_line = String::new();
-    
-    for word in text.split_whitespace() {
-        let word_chars = word.chars().count();
-        let current_chars = current_line.chars().count();
-        
-        if current_line.is_empty() {
-            current_line = format!("{}{}", indent_str, word);
-        } else if current_chars + word_chars + 1 <= width {
-            current_line.push(' ');
-            current_line.push_str(word);
-        } else {
-            result.push_str(&current_line);
-            result.push('\n');
-            current_line = format!("{}{}", indent_str, word);
+    let mut lines = Vec::new();
+
+    for source_line in text.lines() {
+        if source_line.trim().is_empty() {
+            lines.push(String::new());
+            continue;
         }
+
+        // Keep the line's own indentation, and hang bullet continuations under the text
+        let leading = source_line.chars().take_while(|c| c.is_whitespace()).count();
+        let trimmed = source_line.trim_start();
+        let bullet = ["- ", "* ", "• "].iter().find(|b| trimmed.starts_with(*b)).map(|b| output::display_width(b)).unwrap_or(0);
+        let first_indent = " ".repeat(indent + leading);
+        let rest_indent = " ".repeat(indent + leading + bullet);
+
+        let mut current_line = String::new();
+        for word in trimmed.split_whitespace() {
+            // Measured in columns, so wide CJK characters and emoji don't overflow
+            let word_width = output::display_width(word);
+            let current_width = output::display_width(&current_line);
+
+            if current_line.is_empty() {
+                current_line = format!("{}{}", first_indent, word);
+            } else if current_width + word_width < width {
+                current_line.push(' ');
+                current_line.push_str(word);
+            } else {
+                lines.push(current_line);
+                current_line = format!("{}{}", rest_indent, word);
+            }
+
+            // A word wider than the whole line, such as unspaced CJK text, is broken at the edge
+            while output::display_width(&current_line) > width && width > rest_indent.len() + 2 {
+                let (head, tail) = split_at_width(&current_line, width);
+                lines.push(head);
+                current_line = format!("{}{}", rest_indent, tail);
+            }
+        }
+        lines.push(current_line);
     }
-    
-    if !current_line.is_empty() {
-        result.push_str(&current_line);
-    }
-    
-    result
+
+    lines.join("\n")
+}
+
+// Splits text after as many characters as fit in `columns`
+fn split_at_width(text: &str, columns: usize) -> (String, String) {
+    let mut used = 0;
+    let split = text
+        .char_indices()
+        .find(|&(_, c)| {
+            used += output::char_width(c);
+            used > columns
+        })
+        .map(|(idx, _)| idx)
+        .unwrap_or(text.len());
+    (text[..split].to_string(), text[split..].to_string())
 }
 
 // Helper to print wrapped text with proper formatting
@@ -50,37 +86,509 @@ fn print_wrapped(text: &str, width: usize, indent: usize) {
     }
 }
 
+// Widest code shown inside an example box; longer lines are cut
+const CODE_BOX_MAX_CODE_WIDTH: usize = 60;
+
+// Draws code in a box at most max_width columns wide (borders included), sized to
+// the longest line. Lines that don't fit are cut and end with an ellipsis.
+fn render_code_box(code: &str, max_width: usize) -> Vec<String> {
+    // Tabs would render wider than the one column they count as
+    let code = code.replace('\t', "    ");
+    let longest = code.lines().map(output::display_width).max().unwrap_or(0);
+    // Borders and padding take 4 columns; always leave at least one for code
+    let code_width = longest
+        .min(CODE_BOX_MAX_CODE_WIDTH)
+        .min(max_width.saturating_sub(4))
+        .max(1);
+    let box_width = code_width + 4;
+
+    let mut lines = vec![Colors::text(&Borders::top(box_width)).to_string()];
+    for line in code.lines() {
+        lines.push(Borders::box_line_left(&Borders::truncate(line, code_width), box_width));
+    }
+    lines.push(Colors::text(&Borders::bottom(box_width)).to_string());
+    lines
+}
+
+// A test that failed on some attempt, kept so the learner can look back at it once the
+// exercise is over. The test itself is kept, since a test override added later in the
+// exercise can change what the test at its position checks.
+struct FailedRun {
+    attempt: usize,
+    // The test's position in the tests graded that attempt, from 0
+    test_index: usize,
+    test_case: TestCase,
+    output: String,
+}
+
+// What a test run printed, with the exit code when that's what the test didn't accept
+fn reported_output(test_case: &TestCase, result: &ExecutionResult) -> String {
+    if test_case.exit_code_matches(result.exit_code) {
+        result.output.clone()
+    } else {
+        format!("{} ({})", result.output.trim_end(), exit_status(result.exit_code))
+    }
+}
+
+// How a program ended, for messages. No exit code means it was killed by a signal
+fn exit_status(exit_code: Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!("exit code {}", code),
+        None => "killed by signal".to_string(),
+    }
+}
+
+// Two outputs in columns, line by line. Lines that don't match the expected output
+// at the same position are shown in red.
+fn render_side_by_side(expected: &str, left: (&str, &str), right: (&str, &str), width: usize) -> Vec<String> {
+    let divider = output::glyph(" │ ", " | ");
+    let column = width.saturating_sub(output::display_width(divider)) / 2;
+    let expected: Vec<&str> = expected.trim_end().lines().collect();
+    let left_text = left.1.replace('\t', "    ");
+    let right_text = right.1.replace('\t', "    ");
+    let left_lines: Vec<&str> = left_text.trim_end().lines().collect();
+    let right_lines: Vec<&str> = right_text.trim_end().lines().collect();
+
+    // Only the left column is padded, so rows don't end in spaces
+    let cell = |lines: &[&str], idx: usize, width: usize| -> String {
+        match lines.get(idx) {
+            None if idx == 0 => Colors::muted(&Borders::pad("(no output)", width)).to_string(),
+            None => Borders::pad("", width),
+            Some(line) => {
+                let text = Borders::pad(&Borders::truncate(line, column), width);
+                if expected.get(idx).is_some_and(|e| e.trim_end() == line.trim_end()) {
+                    Colors::text(&text).to_string()
+                } else {
+                    Colors::error(&text).to_string()
+                }
+            }
+        }
+    };
+
+    let mut rows = vec![
+        format!("{}{}{}", Colors::accent(&Borders::pad(left.0, column)), divider, Colors::accent(right.0)),
+        format!("{}{}{}", Borders::separator(column), divider, Borders::separator(column)),
+    ];
+    for idx in 0..left_lines.len().max(right_lines.len()).max(1) {
+        rows.push(format!("{}{}{}", cell(&left_lines, idx, column), divider, cell(&right_lines, idx, 0)));
+    }
+    rows
+}
+
+// True when the outputs have the same words and differ only in spaces, tabs or line
+// breaks, which look the same in a terminal
+fn whitespace_only_difference(actual: &str, expected: &str) -> bool {
+    actual.trim() != expected.trim() && actual.split_whitespace().eq(expected.split_whitespace())
+}
+
+// The marker a space or tab is shown as when whitespace is made visible
+fn whitespace_marker(c: char) -> Option<&'static str> {
+    match c {
+        ' ' => Some(output::glyph("·", ".")),
+        '\t' => Some(output::glyph("→", ">")),
+        _ => None,
+    }
+}
+
+// Each line with spaces shown as · and tabs as →, and whitespace at the end of the
+// line highlighted
+fn show_whitespace(text: &str) -> Vec<String> {
+    text.trim_end_matches(['\n', '\r'])
+        .lines()
+        .map(|line| {
+            let body = line.trim_end_matches([' ', '\t']);
+            let mut shown = String::new();
+            for c in body.chars() {
+                match whitespace_marker(c) {
+                    Some(marker) => shown.push_str(&Colors::muted(marker).to_string()),
+                    None => shown.push(c),
+                }
+            }
+            let trailing: String = line[body.len()..].chars().filter_map(whitespace_marker).collect();
+            format!("{}{}", shown, Colors::error(&trailing).bold())
+        })
+        .collect()
+}
+
+// Expected and actual output with their whitespace made visible, for a failure where
+// nothing else differs
+fn print_whitespace_difference(expected: &str, actual: &str) {
+    println!();
+    println!("{} {}", Colors::label_info("NOTE"), Colors::info("The difference is whitespace-only."));
+    println!("{}", Colors::muted(&format!(
+        "Spaces are shown as {} and tabs as {}; whitespace at the end of a line is highlighted.",
+        whitespace_marker(' ').unwrap_or_default(),
+        whitespace_marker('\t').unwrap_or_default()
+    )));
+    for (title, text) in [("Expected:", expected), ("Got:", actual)] {
+        println!("{}", title);
+        for line in show_whitespace(text) {
+            println!("  {}", line);
+        }
+    }
+}
+
+// rustc's errors one at a time: the error and its code, the line it's on with the
+// problem underlined, then the compiler's suggested fixes
+fn print_compile_error(error: &CompileError) {
+    let errors: Vec<&Diagnostic> = error.diagnostics.iter().filter(|d| d.is_error()).collect();
+    for diagnostic in &errors {
+        println!("{}", Colors::error(&diagnostic.headline()).bold());
+        if let (Some(line), Some(column)) = (diagnostic.line, diagnostic.column) {
+            println!("  {} line {}, column {}", Colors::muted("-->"), line, column);
+            if let Some(ref source) = diagnostic.source_line {
+                let gutter = format!("{} | ", line);
+                println!("  {}{}", Colors::muted(&gutter), source.trim_end());
+                // Tabs are kept so the underline lines up with the code above it
+                let indent: String = source.chars().take(column - 1).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+                let label = diagnostic.label.as_deref().unwrap_or_default();
+                println!("  {}{}{} {}", " ".repeat(gutter.len()), indent, Colors::error(&"^".repeat(diagnostic.span_width)), Colors::error(label));
+            }
+        }
+        for suggestion in &diagnostic.suggestions {
+            println!("  {} {}", Colors::label_tip("FIX"), Colors::success(suggestion));
+        }
+        for note in &diagnostic.notes {
+            println!("  {}", Colors::muted(note));
+        }
+        println!();
+    }
+    let warnings = error.diagnostics.len() - errors.len();
+    if warnings > 0 {
+        println!("{}", Colors::muted(&format!("The compiler also gave {} warning(s), not shown until the errors are fixed.", warnings)));
+    }
+    if let Some(code) = error.error_codes().first() {
+        println!("{}", Colors::muted(&format!("Run 'rustc --explain {}' for a longer explanation of the error.", code)));
+    }
+}
+
+// Why code whose tests pass still fails a clean-code exercise, with the compiler's
+// warnings; None when it compiles without any
+fn warnings_failure(language: Language, run_path: &Path) -> Result<Option<(String, Vec<String>)>> {
+    let warnings = Executor::new(language).warnings(run_path)?;
+    if warnings.is_empty() {
+        return Ok(None);
+    }
+    let message = format!(
+        "Tests passed but your code has {} warning{}. This exercise requires warning-free code.",
+        warnings.len(),
+        if warnings.len() == 1 { "" } else { "s" }
+    );
+    Ok(Some((message, warnings)))
+}
+
+/// Prints an `explain` answer: the concept, then the example in a box like a lesson's
+pub fn print_topic_reference(language: Language, topic: &str, reference: &TopicReference) {
+    print_section_header(&format!("{} in {}", topic, language.display_name()), Colors::success);
+    print_wrapped(&reference.concept, TERMINAL_WIDTH, 0);
+    println!();
+    if reference.example.code.trim().is_empty() {
+        return;
+    }
+    println!("  {}", Colors::warning("Example:").bold());
+    for line in render_code_box(&reference.example.code, TERMINAL_WIDTH - 4) {
+        println!("  {}", line);
+    }
+    if !reference.example.explanation.trim().is_empty() {
+        print!("  {} ", Colors::label_info("TIP"));
+        print_wrapped(&reference.example.explanation, TERMINAL_WIDTH - 4, 4);
+        println!();
+    }
+}
+
 // Helper to print section with proper spacing
 fn print_section_header(title: &str, color_fn: fn(&str) -> colored::ColoredString) {
     println!();
     // Use consistent width for all section headers
     let width = TERMINAL_WIDTH.min(78);
-    let title_chars = title.chars().count();
-    let title_padding = width.saturating_sub(title_chars + 2);
-    let left_pad = title_padding / 2;
-    let right_pad = title_padding - left_pad;
-    let border = format!("╔{}╗", "═".repeat(width.saturating_sub(2)));
-    let title_line = format!("║{}{}{}║", " ".repeat(left_pad), title, " ".repeat(right_pad));
-    println!("{}", color_fn(&border).bold());
-    println!("{}", color_fn(&title_line).bold());
-    println!("{}", color_fn(&format!("╚{}╝", "═".repeat(width.saturating_sub(2)))).bold());
+    println!("{}", color_fn(&Borders::top(width)).bold());
+    println!("{}", color_fn(&Borders::box_line_centered(title, width)).bold());
+    println!("{}", color_fn(&Borders::bottom(width)).bold());
     println!();
 }
 
+// Notes under an example that failed the --verify-examples compile check
+fn print_check_error(example: &CodeExample) {
+    if let Some(error) = &example.check_error {
+        println!("  {} {}", Colors::label_warn("UNVERIFIED"), Colors::muted(&format!("This example didn't compile on its own: {}", error)));
+    }
+}
+
+// Words that mark the extra text in a learner's output as an input prompt
+const PROMPT_WORDS: &[&str] = &["enter", "input", "type", "please"];
+
+const PROMPT_TIP: &str = "Your output has an extra prompt message. Print prompts to stderr or remove them, since tests compare stdout.";
+
+// True when the output is the expected answer with an input prompt (e.g. "Enter a number: ")
+// before or after it, the most common way beginners fail input-reading exercises
+fn has_extra_prompt(actual: &str, expected: &str) -> bool {
+    let (actual, expected) = (actual.trim(), expected.trim());
+    if expected.is_empty() || actual == expected {
+        return false;
+    }
+
+    let extra = if let Some(prefix) = actual.strip_suffix(expected) {
+        prefix
+    } else if let Some(suffix) = actual.strip_prefix(expected) {
+        suffix
+    } else {
+        return false;
+    };
+
+    let extra = extra.trim();
+    let lower = extra.to_lowercase();
+    extra.chars().any(char::is_alphabetic)
+        && (extra.ends_with([':', '?', '>']) || PROMPT_WORDS.iter().any(|w| lower.contains(w)))
+}
+
+// Error text left behind when a program keeps reading after stdin has run out, as each
+// language reports it. Rust: read_exact hitting the end, or parsing the empty line
+// read_line returns there. JavaScript: calling a string method on the line past the
+// last of the input split into lines. C++'s cin just fails quietly, so there's nothing.
+fn read_past_input_patterns(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::Rust => &[
+            "error { kind: unexpectedeof",
+            "parseinterror { kind: empty }",
+            "parsefloaterror { kind: empty }",
+        ],
+        Language::JavaScript => &[
+            "cannot read properties of undefined (reading 'trim')",
+            "cannot read properties of undefined (reading 'split')",
+        ],
+        Language::Cpp => &[],
+    }
+}
+
+const READ_PAST_INPUT_TIP: &str = "Your program tried to read more input than the test provided. Read only as many lines as the exercise describes, or stop reading when no input is left.";
+
+fn read_past_input(language: Language, error: &str) -> bool {
+    let error = error.to_lowercase();
+    read_past_input_patterns(language).iter().any(|p| error.contains(p))
+}
+
+// An exercise that took this many attempts is flagged for review in the lesson summary
+const STRUGGLE_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExerciseStatus {
+    Passed,
+    Skipped,
+    // Attempted several times, then left unsolved and marked for review
+    Unsolved,
+}
+
+// How a single exercise went, collected for the end-of-lesson summary
+#[derive(Debug, Clone)]
+struct ExerciseOutcome {
+    title: String,
+    status: ExerciseStatus,
+    attempts: usize,
+    // Hints revealed with 'hint' under a hint budget
+    hints_used: usize,
+    // Set when the learner chose 'skip-all', ending the lesson after this exercise
+    skip_rest: bool,
+    // Set when the learner typed 'pause', stopping the lesson at this exercise
+    paused: bool,
+}
+
+/// How a lesson run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LessonEnd {
+    /// The learner got through every exercise, passed or not
+    Finished,
+    /// The learner typed 'pause'; 'continue' picks the lesson up at that exercise
+    Paused,
+}
+
+// The lesson an exercise belongs to, for the status line above the exercise prompt
+struct LessonContext<'a> {
+    language: Language,
+    difficulty: Difficulty,
+    topic: &'a str,
+    exercise_count: usize,
+    // Where the exercise files go when not in project mode
+    session_dir: &'a Path,
+}
+
+// Longest topic shown in the status line, so it stays on one line
+const STATUS_TOPIC_WIDTH: usize = 30;
+
+impl LessonContext<'_> {
+    // e.g. "[Rust] · [Beginner] · variables · Exercise 2/3 · Attempt 2"
+    fn status_line(&self, exercise_number: usize, attempt: usize) -> String {
+        let separator = Colors::muted(output::glyph(" · ", " | "));
+        format!(
+            "{}{}{}{}{}{}",
+            Colors::language_badge(self.language),
+            separator,
+            Colors::difficulty_badge(self.difficulty),
+            separator,
+            Colors::warning(&Borders::truncate(self.topic, STATUS_TOPIC_WIDTH)),
+            Colors::muted(&format!(
+                "{}Exercise {}/{}{}Attempt {}",
+                output::glyph(" · ", " | "),
+                exercise_number,
+                self.exercise_count,
+                output::glyph(" · ", " | "),
+                attempt
+            ))
+        )
+    }
+}
+
+// Sections of a lesson that can be displayed (and revisited) before the exercises
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LessonSection {
+    Concept,
+    Steps,
+    Examples,
+    Syntax,
+    Patterns,
+}
+
+impl LessonSection {
+    // Sections that have content for this lesson, in display order
+    fn available(content: &GeneratedContent) -> Vec<Self> {
+        let mut sections = vec![LessonSection::Concept];
+        if !content.step_by_step.is_empty() {
+            sections.push(LessonSection::Steps);
+        }
+        if !content.code_examples.is_empty() {
+            sections.push(LessonSection::Examples);
+        }
+        if !content.syntax_guide.is_empty() {
+            sections.push(LessonSection::Syntax);
+        }
+        if !content.common_patterns.is_empty() {
+            sections.push(LessonSection::Patterns);
+        }
+        sections
+    }
+}
+
+impl std::fmt::Display for LessonSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LessonSection::Concept => "Concept",
+            LessonSection::Steps => "Steps",
+            LessonSection::Examples => "Examples",
+            LessonSection::Syntax => "Syntax",
+            LessonSection::Patterns => "Patterns",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 pub struct LessonManager {
+    // Supplies lesson content; the generator is still used for model-only extras
+    // (previews, regenerated exercises, line explanations)
+    source: Box<dyn ContentSource>,
     generator: Generator,
     tracker: Tracker,
+    exercise_only: bool,
+    verbosity: Verbosity,
+    hint_budget: HintBudget,
+    // Run the tests whenever the exercise file is saved, instead of on Enter
+    watch: bool,
+    // Keep each lesson's notes and exercise files in <project>/<language>/<topic>
+    project_dir: Option<PathBuf>,
+    // Journey lesson index and exercises already done in it, when run from a journey
+    journey_lesson: Option<(usize, usize)>,
 }
 
 impl LessonManager {
     pub fn new() -> Result<Self> {
+        // A manager runs one lesson, generation included, so its time breakdown starts here
+        timing::reset();
         Ok(Self {
+            source: Box::new(GeneratorSource),
             generator: Generator::new(),
             tracker: Tracker::new()?,
+            exercise_only: false,
+            verbosity: Verbosity::from_env(),
+            hint_budget: HintBudget::from_env(),
+            watch: watch::watch_from_env(),
+            project_dir: FileManager::project_dir_from_env(),
+            journey_lesson: None,
         })
     }
 
+    /// Skip the lesson content and go straight to the exercises
+    pub fn with_exercise_only(mut self, exercise_only: bool) -> Self {
+        self.exercise_only = exercise_only;
+        self
+    }
+
+    /// Generate this many exercises instead of the lesson type's default
+    pub fn with_exercise_count(mut self, exercise_count: Option<usize>) -> Self {
+        self.generator = self.generator.with_exercise_count(exercise_count);
+        self
+    }
+
+    /// Generate and show shorter or longer explanations than CURSED_CODDY_VERBOSITY
+    pub fn with_verbosity(mut self, verbosity: Option<Verbosity>) -> Self {
+        if let Some(verbosity) = verbosity {
+            self.verbosity = verbosity;
+            self.generator = self.generator.with_verbosity(verbosity);
+        }
+        self
+    }
+
+    /// Test the exercise file whenever it's saved instead of waiting for Enter
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.watch |= watch;
+        self
+    }
+
+    /// Write each lesson's notes, tests and exercise files into a folder under
+    /// `project_dir` instead of CURSED_CODDY_PROJECT_DIR or the work directory
+    pub fn with_project_dir(mut self, project_dir: Option<PathBuf>) -> Self {
+        if project_dir.is_some() {
+            self.project_dir = project_dir;
+        }
+        self
+    }
+
+    /// Limit how many hints can be revealed per exercise instead of CURSED_CODDY_HINT_BUDGET
+    pub fn with_hint_budget(mut self, hint_budget: Option<HintBudget>) -> Self {
+        if let Some(hint_budget) = hint_budget {
+            self.hint_budget = hint_budget;
+        }
+        self
+    }
+
+    /// Compile each generated code example and mark the ones that don't compile
+    pub fn with_verify_examples(mut self, verify_examples: bool) -> Self {
+        if verify_examples {
+            self.generator = self.generator.with_verify_examples(true);
+        }
+        self
+    }
+
+    /// Runs a journey lesson: progress is checkpointed in the journey after every
+    /// exercise, and a lesson that was partway done resumes at its next exercise
+    pub fn with_journey_lesson(mut self, lesson_index: usize, exercises_completed: usize) -> Self {
+        self.journey_lesson = Some((lesson_index, exercises_completed));
+        self
+    }
+
+    /// Takes lesson content from another source, e.g. the curated lessons offline.
+    /// Options such as the exercise count still apply, whichever order they're set in.
+    pub fn with_source(mut self, source: Box<dyn ContentSource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    fn project_lesson_dir(&self, language: Language, topic: &str) -> Option<PathBuf> {
+        self.project_dir.as_deref().map(|dir| FileManager::project_lesson_dir(dir, &language, topic))
+    }
+
     fn clear_screen() {
+        // Quiet output is usually captured by another tool, where clearing only adds escape codes
+        if output::is_quiet() {
+            return;
+        }
         let _ = if cfg!(target_os = "windows") {
             Command::new("cmd").args(["/C", "cls"]).status()
         } else {
@@ -97,99 +605,218 @@ impl LessonManager {
         lesson_type: LessonType,
         topic: String,
     ) -> Result<()> {
-        println!("\n{}", Colors::primary(&"=".repeat(60)));
+        println!();
+        Borders::print_rule();
         println!("{}", Colors::primary(&format!("Starting {} Lesson", lesson_type.display_name())).bold());
-        println!("{}", Colors::primary(&"=".repeat(60)));
-        println!("Language: {}", Colors::warning(language.display_name()));
-        println!("Difficulty: {}", Colors::warning(difficulty.display_name()));
+        Borders::print_rule();
+        println!("Language: {}", Colors::language_badge(language));
+        println!("Difficulty: {}", Colors::difficulty_badge(difficulty));
         println!("Topic: {}\n", Colors::warning(&topic));
 
-        println!("{}", Colors::info("Generating lesson content..."));
-        let content = self
-            .generator
-            .generate(language, difficulty, lesson_type, &topic)?;
+        let content = self.lesson_content(language, difficulty, lesson_type, &topic)?;
+        self.start_lesson_with_content(language, difficulty, lesson_type, topic, content)?;
+        Ok(())
+    }
 
-        self.start_lesson_with_content(language, difficulty, lesson_type, topic, content)
+    // The prefetched lesson on the topic if there is one, otherwise the source's.
+    // Prefetched content only fits if it has as many exercises as were asked for.
+    fn lesson_content(&self, language: Language, difficulty: Difficulty, lesson_type: LessonType, topic: &str) -> Result<GeneratedContent> {
+        let prefetched = self
+            .tracker
+            .load_prefetched(language, difficulty, lesson_type, topic)
+            .filter(|content| content.exercises.len() == self.generator.exercise_count(lesson_type));
+        match prefetched {
+            Some(content) => {
+                println!("{}", Colors::info("Using prefetched lesson content."));
+                Ok(content)
+            }
+            None => self.source.get_content(&self.generator, language, difficulty, lesson_type, topic),
+        }
     }
 
-    pub fn start_lesson_with_content(
+    /// Shows a short summary of what the lesson would cover and asks whether to
+    /// generate it. Returns false if the learner decides against it.
+    pub fn preview_lesson(
         &self,
         language: Language,
         difficulty: Difficulty,
         lesson_type: LessonType,
-        topic: String,
-        mut content: GeneratedContent,
-    ) -> Result<()> {
-        // Clear screen for clean view
-        Self::clear_screen();
+        topic: &str,
+    ) -> Result<bool> {
+        println!("{}", Colors::info("Generating lesson preview..."));
+        match self.generator.generate_preview(language, difficulty, lesson_type, topic) {
+            Ok(preview) => {
+                print_section_header("LESSON PREVIEW", Colors::info);
+                print_wrapped(&preview, TERMINAL_WIDTH, 2);
+                println!();
+            }
+            Err(e) => {
+                println!("{}", Colors::label_warn("WARN"));
+                println!("{}", Colors::warning(&format!("Could not generate a preview: {}", e)));
+            }
+        }
+
+        Confirm::new("Generate the full lesson?")
+            .with_default(true)
+            .prompt()
+            .map_err(|e| exit_code::prompt_error("Selection", e))
+    }
+
+    /// Replaces the exercises of the most recently started lesson with freshly
+    /// generated ones and runs the lesson again.
+    pub fn regenerate_exercises(&self) -> Result<()> {
+        let cached = match self.tracker.load_lesson_cache()? {
+            Some(cached) => cached,
+            None => {
+                println!("{}", Colors::warning("No cached lesson found. Start a new lesson with 'start'."));
+                return Ok(());
+            }
+        };
 
-        // Display concept introduction
-        print_section_header("CONCEPT INTRODUCTION", |s| Colors::success(s));
-        print_wrapped(&content.concept, TERMINAL_WIDTH, 0);
         println!();
+        Borders::print_rule();
+        println!("{}", Colors::primary("Regenerating Exercises").bold());
+        Borders::print_rule();
+        println!("Language: {}", Colors::language_badge(cached.language));
+        println!("Difficulty: {}", Colors::difficulty_badge(cached.difficulty));
+        println!("Topic: {}\n", Colors::warning(&cached.topic));
 
-        // Display step-by-step explanation
-        if !content.step_by_step.is_empty() {
-            print_section_header("STEP-BY-STEP EXPLANATION", |s| Colors::primary(s));
-            for (idx, step) in content.step_by_step.iter().enumerate() {
-                print!("  {}. ", Colors::primary(&(idx + 1).to_string()).bold());
-                print_wrapped(step, TERMINAL_WIDTH - 4, 4);
-                println!();
+        let mut content = cached.content;
+        content.exercises = self.generator.generate_exercises(
+            cached.language,
+            cached.difficulty,
+            cached.lesson_type,
+            &cached.topic,
+            &content,
+        )?;
+
+        self.start_lesson_with_content(
+            cached.language,
+            cached.difficulty,
+            cached.lesson_type,
+            cached.topic,
+            content,
+        )?;
+        Ok(())
+    }
+
+    /// Plays a lesson pack (see export_last_lesson) or a hand-written lesson in the
+    /// same format as ~/.cursed-coddy/last_lesson.json: language, difficulty,
+    /// lesson_type and topic, plus the lesson itself under "content". Nothing is generated.
+    /// A test's input_file is read relative to the lesson file's directory.
+    pub fn play_lesson_file(&self, path: &std::path::Path) -> Result<()> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read lesson file {}", path.display()))?;
+        let is_pack = serde_json::from_str::<serde_json::Value>(&json)
+            .ok()
+            .is_some_and(|value| value.get("schema_version").is_some());
+        let mut lesson = if is_pack {
+            let pack = LessonPack::from_json(&json)
+                .with_context(|| format!("{} is not a valid lesson pack", path.display()))?;
+            CachedLesson {
+                language: pack.meta.language,
+                difficulty: pack.meta.difficulty,
+                lesson_type: pack.meta.lesson_type,
+                topic: pack.meta.topic,
+                content: pack.content,
             }
-        }
+        } else {
+            serde_json::from_str::<CachedLesson>(&json)
+                .with_context(|| format!("{} is not a valid lesson file", path.display()))?
+        };
+        // Input files are found next to the lesson file, not the current directory
+        let base_dir = path.parent().unwrap_or_else(|| std::path::Path::new("."));
+        lesson.content.load_input_files(base_dir)?;
 
-        // Display code examples
-        if !content.code_examples.is_empty() {
-            print_section_header("CODE EXAMPLES", |s| Colors::warning(s));
-            for (idx, example) in content.code_examples.iter().enumerate() {
-                println!("  {}", Colors::warning(&format!("Example {}:", idx + 1)).bold());
-                // Calculate box width: find longest line, add padding, but cap at terminal width
-                let max_line_len = example.code.lines()
-                    .map(|l| l.chars().count())
-                    .max()
-                    .unwrap_or(0);
-                // Cap at reasonable width (60 chars max for code), leaving margin for box borders and indentation
-                let effective_max = max_line_len.min(60).min(TERMINAL_WIDTH - 8);
-                let box_width = effective_max + 4; // Add padding for box borders (2 chars on each side)
-                println!("  {}", Colors::text(&Borders::top(box_width)));
-                for line in example.code.lines() {
-                    // Truncate line if it's too long
-                    let display_line = if line.chars().count() > effective_max {
-                        &line[..effective_max.min(line.len())]
-                    } else {
-                        line
-                    };
-                    println!("  {}", Borders::box_line_left(display_line, box_width));
-                }
-                println!("  {}", Colors::text(&Borders::bottom(box_width)));
-                print!("  {} ", Colors::label_info("TIP"));
-                print_wrapped(&example.explanation, TERMINAL_WIDTH - 4, 4);
-                println!();
+        println!();
+        Borders::print_rule();
+        println!("{}", Colors::primary(&format!("Playing {}", path.display())).bold());
+        Borders::print_rule();
+        println!("Language: {}", Colors::language_badge(lesson.language));
+        println!("Difficulty: {}", Colors::difficulty_badge(lesson.difficulty));
+        println!("Topic: {}\n", Colors::warning(&lesson.topic));
+
+        let problems = lesson.content.validate(lesson.language);
+        if !problems.is_empty() {
+            println!("{}", Colors::label_warn("WARN"));
+            println!("{}", Colors::warning(&format!("The lesson file has {} issue(s):", problems.len())));
+            for problem in &problems {
+                println!("  - {}", problem);
+            }
+            let play_anyway = Confirm::new("Play it anyway?")
+                .with_default(true)
+                .prompt()
+                .map_err(|e| exit_code::prompt_error("Selection", e))?;
+            if !play_anyway {
+                return Ok(());
             }
         }
 
-        // Display syntax guide
-        if !content.syntax_guide.is_empty() {
-            print_section_header("SYNTAX GUIDE", |s| Colors::accent(s));
-            print_wrapped(&content.syntax_guide, TERMINAL_WIDTH, 0);
-            println!();
-        }
+        self.start_lesson_with_content(lesson.language, lesson.difficulty, lesson.lesson_type, lesson.topic, lesson.content)?;
+        Ok(())
+    }
 
-        // Display common patterns
-        if !content.common_patterns.is_empty() {
-            print_section_header("COMMON PATTERNS", |s| Colors::info(s));
-            for (idx, pattern) in content.common_patterns.iter().enumerate() {
-                print!("  {}. ", Colors::info(&(idx + 1).to_string()).bold());
-                print_wrapped(pattern, TERMINAL_WIDTH - 4, 4);
-                println!();
+    /// Writes the most recently started lesson as a shareable .coddy.json pack,
+    /// by default named after its topic in the current directory
+    pub fn export_last_lesson(&self, output: Option<PathBuf>) -> Result<()> {
+        let cached = match self.tracker.load_lesson_cache()? {
+            Some(cached) => cached,
+            None => {
+                println!("{}", Colors::warning("No lesson to export yet. Start a lesson with 'start' first."));
+                return Ok(());
             }
+        };
+
+        let path = output.unwrap_or_else(|| PathBuf::from(format!("{}.coddy.json", topic_slug(&cached.topic))));
+        let pack = LessonPack::new(
+            LessonPackMeta {
+                language: cached.language,
+                difficulty: cached.difficulty,
+                lesson_type: cached.lesson_type,
+                topic: cached.topic,
+                created_at: format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()),
+            },
+            cached.content,
+        );
+        let json = pack.to_json().context("Failed to serialize lesson pack")?;
+        std::fs::write(&path, json)
+            .map_err(|e| fs_error::write_error(e, &format!("Failed to write lesson pack {}", path.display()), &path))?;
+
+        println!("{}", Colors::success(&format!("Lesson exported to {}", path.display())));
+        println!("{}", Colors::muted(&format!("Anyone can play it with 'cursed-coddy play {}'.", path.display())));
+        Ok(())
+    }
+
+    pub fn start_lesson_with_content(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        lesson_type: LessonType,
+        topic: String,
+        mut content: GeneratedContent,
+    ) -> Result<LessonEnd> {
+        let lesson_started = Instant::now();
+
+        // Lesson files, caches and packs haven't necessarily been through generation's limits
+        content.check_size()?;
+        let cut = content.apply_size_limits(MAX_EXERCISES);
+        if !cut.is_empty() {
+            println!("{}", Colors::label_warn("WARN"));
+            println!("{}", Colors::warning(&format!("This lesson is unusually long, so parts were cut: {}", cut.join(", "))));
+        }
+
+        // The placeholder exercise below only makes sense after reading the lesson; without
+        // it there'd be nothing to practise, so stop before anything is cached or tracked
+        if content.exercises.is_empty() && self.exercise_only {
+            return Err(anyhow::anyhow!(
+                "Couldn't produce any exercises for {}. Try a different topic or lesson type, or run the lesson without --exercise-only.",
+                topic
+            ));
         }
 
         // Check if exercises were generated
-        if content.exercises.is_empty() {
-            println!();
-            println!("{}", Colors::label_warn("WARN"));
-            println!("{}", Colors::warning("No exercises were generated. Creating a simple practice exercise..."));
+        let added_fallback_exercise = content.exercises.is_empty();
+        if added_fallback_exercise {
             // Create a simple fallback exercise
             let fallback_exercise = crate::ollama::formatter::Exercise {
                 title: format!("Practice: {}", topic),
@@ -198,82 +825,564 @@ impl LessonManager {
                     "Review the code examples above".to_string(),
                     "Start with a simple implementation".to_string(),
                 ],
-                example_input: Some("".to_string()),
+                example_input: None,
                 example_output: Some("(Your code should demonstrate the concept)".to_string()),
+                starter_code: None,
                 test_cases: vec![],
+                require_clean: false,
+                prints_nothing: false,
+                function: None,
             };
             content.exercises.push(fallback_exercise);
         }
         
         // Auto-fill missing example_input/example_output from test cases if needed
         for exercise in &mut content.exercises {
+            // Function exercises are graded through a generated main, which only Rust gets.
+            // Their tests show the calls, and an example output would ask for printing.
+            if language == Language::Rust && exercise.function.is_some() {
+                exercise.add_function_tests();
+                continue;
+            }
             // If example_output is missing but we have test cases, use first test case output
-            if exercise.example_output.is_none() || exercise.example_output.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true) {
-                if let Some(first_test) = exercise.test_cases.first() {
-                    exercise.example_output = Some(first_test.output.clone());
-                }
+            if !exercise.has_output() {
+                exercise.example_output = exercise.test_cases.first()
+                    .map(|t| t.output.clone())
+                    .filter(|o| !o.trim().is_empty());
             }
             
             // If example_input is missing but we have test cases, use first test case input
-            if exercise.example_input.is_none() || exercise.example_input.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true) {
-                if let Some(first_test) = exercise.test_cases.first() {
-                    if !first_test.input.trim().is_empty() {
-                        exercise.example_input = Some(first_test.input.clone());
-                    } else {
-                        exercise.example_input = Some("".to_string());
-                    }
-                } else {
-                    exercise.example_input = Some("".to_string());
-                }
+            if !exercise.has_input() {
+                exercise.example_input = exercise.test_cases.first()
+                    .map(|t| t.input.clone())
+                    .filter(|i| !i.trim().is_empty());
             }
         }
 
-        // Start tracking
-        self.tracker.start_lesson(
+        // Cache the lesson right away so quitting mid-lesson can be resumed with
+        // 'continue', and so its exercises can be regenerated later
+        self.tracker.save_lesson_cache(&CachedLesson {
+            language,
+            difficulty,
+            lesson_type,
+            topic: topic.clone(),
+            content: content.clone(),
+        })?;
+
+        // A journey lesson left partway resumes at its next exercise
+        let start_index = self
+            .journey_lesson
+            .map(|(_, done)| done)
+            .filter(|&done| done < content.exercises.len())
+            .unwrap_or(0);
+
+        // Start tracking. A lesson left paused can't be continued once another
+        // takes its place, so its files go.
+        let replaced = self.tracker.start_lesson(
             language,
             difficulty,
             lesson_type,
             topic.clone(),
+            start_index,
             content.exercises.len(),
         )?;
+        if let Some(replaced) = replaced.filter(|_| !FileManager::keep_files_from_env()) {
+            FileManager::remove_session_dir(&FileManager::session_dir(
+                &replaced.language,
+                &replaced.topic,
+                replaced.session_id.as_deref(),
+            ));
+        }
+        // Mark the journey active too, so 'continue' returns to it rather than to a bare lesson
+        if let Some((lesson_index, _)) = self.journey_lesson {
+            self.tracker.checkpoint_journey_lesson(language, lesson_index, start_index)?;
+        }
+
+        let lesson_dir = self.project_lesson_dir(language, &topic);
+        let mut notes_kept = false;
+        if let Some(ref dir) = lesson_dir {
+            let meta = LessonPackMeta {
+                language,
+                difficulty,
+                lesson_type,
+                topic: topic.clone(),
+                created_at: format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()),
+            };
+            notes_kept = project::write_lesson_files(dir, meta, &content)?;
+        }
+
+        // Clear screen for clean view
+        Self::clear_screen();
+        if let Some(ref dir) = lesson_dir {
+            println!("{}", Colors::muted(&format!("Lesson notes and your solutions are saved in {}", dir.display())));
+            if notes_kept {
+                println!("{}", Colors::muted("lesson.md has your own edits, so it was left as it is"));
+            }
+        }
+
+        // Warn when generation failed and the lesson is only a stub
+        if content.is_fallback {
+            println!();
+            println!("{}", Colors::label_warn("DEGRADED CONTENT").bold());
+            match content.fallback_reason {
+                Some(ref reason) => println!("{}", Colors::warning(&format!("This lesson used fallback content because {}.", reason))),
+                None => println!("{}", Colors::warning("Lesson generation failed, so this lesson was assembled from fallback content.")),
+            }
+            println!("{}", Colors::muted("It may be incomplete. Consider regenerating it once Ollama is responding properly."));
+        }
+
+        if start_index > 0 {
+            println!();
+            println!("{}", Colors::label_info("RESUMING"));
+            println!("{}", Colors::muted(&format!(
+                "You finished {} of {} exercises in this lesson. Picking up at exercise {}.",
+                start_index,
+                content.exercises.len(),
+                start_index + 1
+            )));
+        } else if self.exercise_only {
+            println!();
+            println!("{}", Colors::label_info("EXERCISE ONLY"));
+            println!("{}", Colors::muted(&format!("Skipping the lesson content for {}. Jumping straight to practice.", topic)));
+        } else {
+            // Display every section once, top to bottom
+            for section in LessonSection::available(&content) {
+                self.render_section(language, section, &content);
+            }
+
+            // Let the learner revisit any section before moving on to the exercises
+            self.navigate_sections(language, &content);
+        }
+
+        if added_fallback_exercise {
+            println!();
+            println!("{}", Colors::label_warn("WARN"));
+            println!("{}", Colors::warning("No exercises were generated. Created a simple practice exercise instead."));
+        }
+
+        // A journey lesson left partway keeps the file of the exercise it stopped at
+        let resume_at = (start_index > 0).then_some(start_index);
+        self.run_exercises(language, difficulty, &topic, &content, resume_at, lesson_started)
+    }
+
+    /// Picks up the lesson in progress at the exercise where the learner left
+    /// off, using the cached content instead of generating it again.
+    pub fn resume_lesson(&self) -> Result<LessonEnd> {
+        let progress = self.tracker.load()?;
+        let state = match progress.current_lesson {
+            Some(state) => state,
+            None => {
+                println!("{}", Colors::warning("No lesson in progress. Start a new lesson with 'start'."));
+                return Ok(LessonEnd::Finished);
+            }
+        };
+
+        let cached = match self.tracker.load_lesson_cache()? {
+            Some(cached) if cached.matches(&state) => cached,
+            _ => {
+                println!("{}", Colors::warning("The content of your last lesson is no longer available. Start a new lesson with 'start'."));
+                return Ok(LessonEnd::Finished);
+            }
+        };
+
+        let start_index = state.current_exercise.min(cached.content.exercises.len());
+        println!("{}", Colors::primary("Resuming lesson...").bold());
+        println!("Language: {}", Colors::language_badge(cached.language));
+        println!("Difficulty: {}", Colors::difficulty_badge(cached.difficulty));
+        println!("Topic: {}", Colors::warning(&cached.topic));
+        println!(
+            "Progress: {}/{} exercises",
+            start_index, cached.content.exercises.len()
+        );
+
+        self.run_exercises(cached.language, cached.difficulty, &cached.topic, &cached.content, Some(start_index), Instant::now())
+    }
+
+    /// Lists recently started lessons and lets the learner continue, review or redo one,
+    /// using its saved content rather than generating it again.
+    pub fn resume_from_history(&self) -> Result<()> {
+        let history = self.tracker.load_lesson_history()?;
+        if history.is_empty() {
+            println!("{}", Colors::warning("No lessons yet. Start one with 'start'."));
+            return Ok(());
+        }
+
+        // Only the most recent lesson can be the one in progress
+        let current = self.tracker.load()?.current_lesson;
+        let in_progress = match (history.last(), current.as_ref()) {
+            (Some(entry), Some(state)) if !entry.completed && entry.lesson.matches(state) => Some(state),
+            _ => None,
+        };
+
+        let entries: Vec<_> = history.iter().rev().collect();
+        let options: Vec<String> = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                let status = match in_progress {
+                    Some(state) if idx == 0 => format!("in progress, {}/{} exercises", state.current_exercise, state.total_exercises),
+                    _ if entry.completed => "completed".to_string(),
+                    _ => "not finished".to_string(),
+                };
+                format!(
+                    "{}  {} {} - {} ({}, {})",
+                    entry.started_date(),
+                    entry.lesson.language.display_name(),
+                    entry.lesson.difficulty.display_name(),
+                    entry.lesson.topic,
+                    entry.lesson.lesson_type.display_name(),
+                    status
+                )
+            })
+            .collect();
+
+        let choice = Select::new("Pick a lesson:", options)
+            .raw_prompt()
+            .map_err(|e| exit_code::prompt_error("Selection", e))?;
+        let lesson = entries[choice.index].lesson.clone();
+
+        let mut actions = Vec::new();
+        if choice.index == 0 && in_progress.is_some() {
+            actions.push("Continue where you left off");
+        }
+        actions.push("Review the lesson and do its exercises");
+        actions.push("Redo the exercises only");
+        let action = Select::new("What would you like to do?", actions)
+            .prompt()
+            .map_err(|e| exit_code::prompt_error("Selection", e))?;
+
+        match action {
+            "Continue where you left off" => self.resume_lesson(),
+            "Review the lesson and do its exercises" => self.start_lesson_with_content(
+                lesson.language,
+                lesson.difficulty,
+                lesson.lesson_type,
+                lesson.topic,
+                lesson.content,
+            ),
+            _ => Self::new()?.with_exercise_only(true).start_lesson_with_content(
+                lesson.language,
+                lesson.difficulty,
+                lesson.lesson_type,
+                lesson.topic,
+                lesson.content,
+            ),
+        }?;
+        Ok(())
+    }
+
+    // Runs the exercises, recording progress after each one. `resume_at` is the
+    // exercise a paused lesson picks up at, whose file keeps what was written so far;
+    // None starts from the first exercise with fresh files.
+    fn run_exercises(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        topic: &str,
+        content: &GeneratedContent,
+        resume_at: Option<usize>,
+        lesson_started: Instant,
+    ) -> Result<LessonEnd> {
+        let start_index = resume_at.unwrap_or(0);
+        let session_id = self.tracker.load()?.current_lesson.and_then(|state| state.session_id);
+        let session_dir = FileManager::session_dir(&language, topic, session_id.as_deref());
+        // A broken compiler is diagnosed up front rather than as a cryptic error on the first test run
+        if start_index < content.exercises.len() {
+            Executor::new(language).check_toolchain().map_err(Self::pause_for_toolchain)?;
+        }
 
         // Process exercises - don't clear screen before first exercise, show it right after lesson content
-        for (idx, exercise) in content.exercises.iter().enumerate() {
-            let clear_before = idx > 0; // Only clear screen for exercises after the first one
-            self.handle_exercise(language, idx + 1, exercise, &content, clear_before)?;
+        let mut outcomes = Vec::with_capacity(content.exercises.len());
+        for (idx, exercise) in content.exercises.iter().enumerate().skip(start_index) {
+            let clear_before = idx > start_index; // Only clear screen for exercises after the first one
+            // When resuming, keep whatever the learner already wrote for this exercise
+            let keep_file = resume_at == Some(idx);
+            let lesson = LessonContext {
+                language,
+                difficulty,
+                topic,
+                exercise_count: content.exercises.len(),
+                session_dir: &session_dir,
+            };
+            let outcome = self.handle_exercise(&lesson, idx + 1, exercise, content, clear_before, keep_file)?;
+            // Progress still points at this exercise, which is where 'continue' picks up
+            if outcome.paused {
+                return Ok(LessonEnd::Paused);
+            }
+            let skip_rest = outcome.skip_rest;
+            outcomes.push(outcome);
             self.tracker.complete_exercise()?;
+            if let Some((lesson_index, _)) = self.journey_lesson {
+                self.tracker.checkpoint_journey_lesson(language, lesson_index, idx + 1)?;
+            }
+
+            if skip_rest {
+                // Record what was left undone so the summary stays accurate
+                outcomes.extend(content.exercises.iter().skip(idx + 1).map(|e| ExerciseOutcome {
+                    title: e.title.clone(),
+                    status: ExerciseStatus::Skipped,
+                    attempts: 0,
+                    hints_used: 0,
+                    skip_rest: false,
+                    paused: false,
+                }));
+                break;
+            }
         }
 
+        // The quiz checks the concept, so it's left out when only practising code
+        let quiz_score = if quiz_enabled() && !self.exercise_only {
+            Self::run_quiz(&content.quiz)
+        } else {
+            None
+        };
+
         // Complete lesson
         self.tracker.complete_lesson()?;
+        if !FileManager::keep_files_from_env() {
+            FileManager::remove_session_dir(&session_dir);
+        }
+        if outcomes.iter().any(|o| o.status != ExerciseStatus::Passed) {
+            exit_code::set_exercises_skipped();
+        }
+        if start_index > 0 {
+            println!();
+            println!("{}", Colors::muted(&format!("Summary covers exercises {}-{} from this session.", start_index + 1, content.exercises.len())));
+        }
+        Self::print_lesson_summary(&outcomes, quiz_score, lesson_started.elapsed());
         println!();
         println!("{}", Colors::label_pass("SUCCESS").bold());
         println!("{}", Colors::success("Lesson completed!").bold());
+        self.ask_rating(language, topic, content)?;
+        println!("{}", Colors::muted("Liked this lesson? Share it with 'cursed-coddy export'."));
+
+        Ok(LessonEnd::Finished)
+    }
 
+    /// Optionally asks how good the lesson was and records it with the model that
+    /// generated it, for 'progress --ratings'. Esc or "Skip" records nothing.
+    fn ask_rating(&self, language: Language, topic: &str, content: &GeneratedContent) -> Result<()> {
+        const RATINGS: [&str; 6] = ["5 - Great", "4 - Good", "3 - OK", "2 - Weak", "1 - Poor", "Skip"];
+
+        println!();
+        let rating = match Select::new("Rate this lesson:", RATINGS.to_vec()).raw_prompt() {
+            Ok(choice) if choice.index < 5 => 5 - choice.index as u8,
+            _ => return Ok(()),
+        };
+        self.tracker.rate_lesson(content.model.clone(), language, topic.to_string(), rating)?;
+        println!("{}", Colors::muted("Thanks! See how your lessons rate with 'cursed-coddy progress --ratings'."));
         Ok(())
     }
 
+    /// Asks the lesson's multiple-choice questions and returns (correct, answered),
+    /// or None if there was nothing to ask or the learner skipped the quiz right away
+    fn run_quiz(quiz: &[QuizQuestion]) -> Option<(usize, usize)> {
+        let questions: Vec<&QuizQuestion> = quiz.iter().filter(|q| q.is_valid()).collect();
+        if questions.is_empty() {
+            return None;
+        }
+
+        print_section_header("QUIZ", Colors::accent);
+        println!("{}", Colors::muted("Check your understanding of the concept. Press Esc to skip the rest of the quiz."));
+
+        let (mut correct, mut answered) = (0, 0);
+        for (idx, question) in questions.iter().enumerate() {
+            println!();
+            println!("{}", Colors::accent(&format!("Question {}/{}", idx + 1, questions.len())).bold());
+            print_wrapped(&question.question, TERMINAL_WIDTH, 0);
+
+            let options: Vec<String> = question.options.iter().enumerate()
+                .map(|(i, option)| format!("{}) {}", (b'A' + i as u8) as char, option))
+                .collect();
+            let choice = match Select::new("Your answer:", options.clone()).raw_prompt() {
+                Ok(choice) => choice,
+                Err(_) => {
+                    println!("{}", Colors::warning("Quiz skipped."));
+                    break;
+                }
+            };
+
+            answered += 1;
+            if choice.index == question.correct {
+                correct += 1;
+                println!("{}", Colors::label_pass("CORRECT"));
+            } else {
+                println!("{}", Colors::label_fail("INCORRECT"));
+                println!("The answer is: {}", Colors::success(&options[question.correct]));
+            }
+            if !question.explanation.trim().is_empty() {
+                print_wrapped(&question.explanation, TERMINAL_WIDTH, 0);
+            }
+        }
+
+        (answered > 0).then_some((correct, answered))
+    }
+
+    fn render_section(&self, language: Language, section: LessonSection, content: &GeneratedContent) {
+        match section {
+            LessonSection::Concept => {
+                print_section_header("CONCEPT INTRODUCTION", Colors::success);
+                print_wrapped(&content.concept, TERMINAL_WIDTH, 0);
+                println!();
+            }
+            LessonSection::Steps => {
+                print_section_header("STEP-BY-STEP EXPLANATION", Colors::primary);
+                for (idx, step) in content.step_by_step.iter().enumerate() {
+                    print!("  {}. ", Colors::primary(&(idx + 1).to_string()).bold());
+                    print_wrapped(step, TERMINAL_WIDTH - 4, 4);
+                    println!();
+                }
+            }
+            LessonSection::Examples => {
+                print_section_header("CODE EXAMPLES", Colors::warning);
+                for (idx, example) in content.code_examples.iter().enumerate() {
+                    println!("  {}", Colors::warning(&format!("Example {}:", idx + 1)).bold());
+                    for line in render_code_box(&example.code, TERMINAL_WIDTH - 4) {
+                        println!("  {}", line);
+                    }
+                    print_check_error(example);
+                    print!("  {} ", Colors::label_info("TIP"));
+                    print_wrapped(&example.explanation, TERMINAL_WIDTH - 4, 4);
+                    println!();
+                }
+
+                self.explain_example_lines(language, &content.code_examples);
+            }
+            LessonSection::Syntax => {
+                print_section_header("SYNTAX GUIDE", Colors::accent);
+                print_wrapped(&content.syntax_guide, TERMINAL_WIDTH, 0);
+                println!();
+            }
+            LessonSection::Patterns => {
+                print_section_header("COMMON PATTERNS", Colors::info);
+                for (idx, pattern) in content.common_patterns.iter().enumerate() {
+                    print!("  {}. ", Colors::info(&(idx + 1).to_string()).bold());
+                    print_wrapped(pattern, TERMINAL_WIDTH - 4, 4);
+                    println!();
+                }
+            }
+        }
+    }
+
+    // Menu loop for jumping back to a lesson section; returns when the learner is ready for exercises
+    fn navigate_sections(&self, language: Language, content: &GeneratedContent) {
+        let sections = LessonSection::available(content);
+        let mut options: Vec<String> = sections.iter().map(|s| s.to_string()).collect();
+        options.push("Start exercises".to_string());
+
+        loop {
+            let choice = Select::new("Review a section or start the exercises:", options.clone())
+                .with_starting_cursor(sections.len())
+                .raw_prompt();
+
+            match choice {
+                Ok(choice) if choice.index < sections.len() => {
+                    Self::clear_screen();
+                    self.render_section(language, sections[choice.index], content);
+                }
+                _ => return,
+            }
+        }
+    }
+
+    // Let the learner ask about individual lines of the code examples
+    fn explain_example_lines(&self, language: Language, examples: &[CodeExample]) {
+        // Nobody to answer the question when the input is piped
+        if !std::io::stdin().is_terminal() {
+            return;
+        }
+        loop {
+            let wants_explanation = Confirm::new("Would you like an explanation of a specific line in an example?")
+                .with_default(false)
+                .prompt()
+                .unwrap_or(false);
+            if !wants_explanation {
+                return;
+            }
+
+            let example = if examples.len() == 1 {
+                &examples[0]
+            } else {
+                let options: Vec<String> = (1..=examples.len()).map(|n| format!("Example {}", n)).collect();
+                match Select::new("Which example?", options).raw_prompt() {
+                    Ok(choice) => &examples[choice.index],
+                    Err(_) => return,
+                }
+            };
+
+            let lines: Vec<&str> = example.code.lines().collect();
+            println!();
+            for (idx, line) in lines.iter().enumerate() {
+                println!("  {} {}", Colors::muted(&format!("{:>3} |", idx + 1)), line);
+            }
+            println!();
+
+            let line_number = match Text::new(&format!("Line number (1-{}):", lines.len())).prompt() {
+                Ok(input) => match input.trim().parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= lines.len() => n,
+                    _ => {
+                        println!("{}", Colors::warning("Please enter a valid line number."));
+                        continue;
+                    }
+                },
+                Err(_) => return,
+            };
+
+            println!();
+            match self.generator.explain_line(language, &example.code, line_number) {
+                Ok(explanation) => {
+                    print!("  {} ", Colors::label_info(&format!("LINE {}", line_number)));
+                    print_wrapped(&explanation, TERMINAL_WIDTH - 4, 4);
+                }
+                Err(e) => {
+                    // Offline or generation failed - fall back to the example's own explanation
+                    println!(
+                        "{}",
+                        Colors::warning(&format!("Couldn't explain the line: {:#}. Showing the example's explanation instead.", e))
+                    );
+                    print!("  {} ", Colors::label_info("TIP"));
+                    print_wrapped(&example.explanation, TERMINAL_WIDTH - 4, 4);
+                }
+            }
+            println!();
+        }
+    }
+
     fn handle_exercise(
         &self,
-        language: Language,
+        lesson: &LessonContext,
         exercise_number: usize,
         exercise: &crate::ollama::formatter::Exercise,
         content: &crate::ollama::formatter::GeneratedContent,
         clear_screen: bool,
-    ) -> Result<()> {
+        keep_file: bool,
+    ) -> Result<ExerciseOutcome> {
+        let language = lesson.language;
+        let function = exercise.function.as_ref().filter(|_| language == Language::Rust);
         // Clear screen before exercise if requested (not for first exercise)
         if clear_screen {
             Self::clear_screen();
-            println!("\n{}", Colors::primary(&"=".repeat(60)));
+            println!();
+            Borders::print_rule();
         } else {
             println!();
-            println!("{}", Colors::primary(&"=".repeat(60)));
+            Borders::print_rule();
+        }
+        if crate::config::exercise_progress_enabled() {
+            // Where this exercise falls in the lesson, one cell per exercise
+            println!(
+                "{} {}",
+                Colors::primary(&format!("Exercise {} of {}", exercise_number, lesson.exercise_count)).bold(),
+                Colors::primary(&Borders::progress_bar(exercise_number, lesson.exercise_count, lesson.exercise_count))
+            );
+            println!("{}", Colors::text(&exercise.title).bold());
+        } else {
+            println!("{}", Colors::primary(&format!("Exercise {}: {}", exercise_number, exercise.title)).bold());
         }
-        println!("{}", Colors::primary(&format!("Exercise {}: {}", exercise_number, exercise.title)).bold());
-        println!("{}", Colors::primary(&"=".repeat(60)));
+        Borders::print_rule();
         
         // Display quick reference section with key concepts
-        print_section_header("QUICK REFERENCE", |s| Colors::accent(s));
+        print_section_header("QUICK REFERENCE", Colors::accent);
         
         // Show the concept first (especially important if user skipped previous exercises)
         if !content.concept.trim().is_empty() {
@@ -287,27 +1396,12 @@ impl LessonManager {
         // Show relevant code examples FIRST (if available) - they're more useful than syntax text
         if !content.code_examples.is_empty() {
             println!("{}", Colors::warning("Example Code:").bold());
-            for (idx, example) in content.code_examples.iter().take(2).enumerate() {
+            for (idx, example) in content.code_examples.iter().take(self.verbosity.code_examples().1).enumerate() {
                 println!("\n  {}", Colors::primary(&format!("Example {}:", idx + 1)));
-                // Calculate box width: find longest line, add padding, but cap at terminal width
-                let max_line_len = example.code.lines()
-                    .map(|l| l.chars().count())
-                    .max()
-                    .unwrap_or(0);
-                // Cap at reasonable width (60 chars max for code), leaving margin for box borders and indentation
-                let effective_max = max_line_len.min(60).min(TERMINAL_WIDTH - 8);
-                let box_width = effective_max + 4; // Add padding for box borders (2 chars on each side)
-                println!("  {}", Colors::text(&Borders::top(box_width)));
-                for line in example.code.lines() {
-                    // Truncate line if it's too long
-                    let display_line = if line.chars().count() > effective_max {
-                        &line[..effective_max.min(line.len())]
-                    } else {
-                        line
-                    };
-                    println!("  {}", Borders::box_line_left(display_line, box_width));
+                for line in render_code_box(&example.code, TERMINAL_WIDTH - 4) {
+                    println!("  {}", line);
                 }
-                println!("  {}", Colors::text(&Borders::bottom(box_width)));
+                print_check_error(example);
                 // Show brief explanation (first sentence)
                 if let Some(first_sentence) = example.explanation.split('.').next() {
                     println!("  {} {}\n", Colors::label_info("TIP"), first_sentence.trim());
@@ -316,34 +1410,24 @@ impl LessonManager {
         }
         
         println!("{}", Colors::primary(&Borders::separator(TERMINAL_WIDTH)));
-        print_section_header("EXERCISE INSTRUCTIONS", |s| Colors::success(s));
+        print_section_header("EXERCISE INSTRUCTIONS", Colors::success);
         
         print_wrapped(&exercise.description, TERMINAL_WIDTH, 0);
         println!();
 
-        // Check if input is expected (has test cases with input or example_input)
-        let expects_input = !exercise.test_cases.is_empty() && 
-            exercise.test_cases.iter().any(|tc| !tc.input.trim().is_empty()) ||
-            exercise.example_input.as_ref().map(|s| !s.trim().is_empty()).unwrap_or(false);
+        // The test data says whether stdin is read; the description's wording can't be trusted
+        let expects_input = exercise.reads_input();
         
         // Check if output is expected (has test cases with output or example_output)
         let expects_output = !exercise.test_cases.is_empty() && 
             exercise.test_cases.iter().any(|tc| !tc.output.trim().is_empty()) ||
-            exercise.example_output.as_ref().map(|s| !s.trim().is_empty()).unwrap_or(false);
+            exercise.has_output();
         
-        // Detect if test cases have different inputs (indicates stdin reading needed)
-        let has_different_inputs = if !exercise.test_cases.is_empty() {
-            let inputs: std::collections::HashSet<_> = exercise.test_cases.iter()
-                .filter(|tc| !tc.input.trim().is_empty())
-                .map(|tc| tc.input.trim())
-                .collect();
-            inputs.len() > 1
-        } else {
-            false
-        };
+        // Check if the program is given command-line arguments
+        let expects_args = exercise.test_cases.iter().any(|tc| !tc.args.is_empty());
         
         // Detect if test cases have different outputs but no input (likely an error in generation)
-        let has_different_outputs_no_input = if !exercise.test_cases.is_empty() && !expects_input {
+        let has_different_outputs_no_input = if !exercise.test_cases.is_empty() && !expects_input && !expects_args {
             let outputs: std::collections::HashSet<_> = exercise.test_cases.iter()
                 .map(|tc| tc.output.trim())
                 .collect();
@@ -352,16 +1436,13 @@ impl LessonManager {
             false
         };
         
-        // Show warning if input is required but not mentioned in description
-        if expects_input || has_different_inputs {
-            let desc_lower = exercise.description.to_lowercase();
-            if !desc_lower.contains("read") && !desc_lower.contains("input") && !desc_lower.contains("stdin") {
-                println!();
-                println!("{}", Colors::label_warn("CLARIFICATION").bold());
-                println!("{}", Colors::warning("This exercise requires reading input from stdin.").bold());
-                println!("{}", Colors::muted("Your program should read the input provided by each test case, not hardcode values."));
-                println!();
-            }
+        // Say so whenever the tests feed stdin, whatever the description says
+        if expects_input {
+            println!();
+            println!("{}", Colors::label_warn("CLARIFICATION").bold());
+            println!("{}", Colors::warning("This exercise requires reading input from stdin.").bold());
+            println!("{}", Colors::muted("Your program should read the input provided by each test case, not hardcode values."));
+            println!();
         } else if has_different_outputs_no_input {
             // This is likely a generation error - test cases have different outputs but no input
             // This shouldn't happen for simple exercises
@@ -372,76 +1453,120 @@ impl LessonManager {
             println!();
         }
 
+        // Graded on the exit code or a written file, so printing anything fails
+        if exercise.expects_no_output() {
+            println!();
+            println!("{}", Colors::label_info("NO OUTPUT").bold());
+            println!("{}", Colors::info("This exercise checks what your program does, not what it prints. A solution that passes prints nothing."));
+            println!();
+        }
+
+        if exercise.require_clean && language != Language::JavaScript {
+            println!();
+            println!("{}", Colors::label_info("CLEAN CODE").bold());
+            println!("{}", Colors::info("This exercise passes only if your code compiles without warnings, as well as passing the tests."));
+            println!();
+        }
+
+        if let Some(function) = function {
+            println!();
+            println!("{}", Colors::label_info("FUNCTION").bold());
+            println!("{}", Colors::info("Write just this function. The program around it is generated and calls it once per test, so don't add a main:"));
+            println!("   {}", Colors::primary(function.signature.trim()));
+            println!("{}", Colors::muted("Each test compares what the call returns, as {:?} prints it."));
+            println!();
+        }
+
+        // Show how to read command-line arguments if the test cases pass any; a
+        // function exercise's are for the generated main
+        if expects_args && function.is_none() {
+            println!();
+            println!("{}", Colors::label_warn("ARGUMENTS").bold());
+            println!("{}", Colors::warning("This exercise passes command-line arguments to your program.").bold());
+            match language {
+                crate::config::Language::JavaScript => {
+                    println!("   Use {} to read them:", Colors::primary("process.argv"));
+                    println!("   {}", Colors::text("const args = process.argv.slice(2);"));
+                }
+                crate::config::Language::Cpp => {
+                    println!("   Use {} to read them:", Colors::primary("argc and argv"));
+                    println!("   {}", Colors::text("int main(int argc, char* argv[]) {"));
+                    println!("   {}", Colors::text("    std::string first = argv[1];"));
+                }
+                crate::config::Language::Rust => {
+                    println!("   Use {} to read them:", Colors::primary("std::env::args()"));
+                    println!("   {}", Colors::text("let args: Vec<String> = std::env::args().skip(1).collect();"));
+                }
+            }
+            println!();
+        }
+
         // Display example input/output if provided and non-empty
-        if let Some(ref example_input) = exercise.example_input {
-            if !example_input.trim().is_empty() {
-                println!();
-                println!("{}", Colors::label_input("INPUT"));
-                println!("{}", Colors::warning("Example Input:").bold());
-                println!("  {}", Colors::text(example_input));
-                println!();
-                
-                // Show how to read input if input is expected
-                if expects_input {
-                    println!("{}", Colors::label_warn("IMPORTANT").bold());
-                    println!("{}", Colors::error("Your program must read this input!").bold());
-                    match language {
-                        crate::config::Language::JavaScript => {
-                            println!("   Use {} to read input:", Colors::primary("readline() or process.stdin"));
-                            println!("   {}", Colors::text("const readline = require('readline');"));
-                            println!("   {}", Colors::text("const rl = readline.createInterface({ input: process.stdin });"));
-                            println!("   {}", Colors::text("rl.on('line', (line) => { /* use line */ });"));
-                            println!("   {}", Colors::text("Or use: const input = require('fs').readFileSync(0, 'utf-8').trim();"));
-                        }
-                        crate::config::Language::Cpp => {
-                            println!("   Use {} to read input:", Colors::primary("cin or getline()"));
-                            println!("   {}", Colors::text("#include <iostream>"));
-                            println!("   {}", Colors::text("std::string input;"));
-                            println!("   {}", Colors::text("std::getline(std::cin, input);"));
-                            println!("   {}", Colors::text("Or: std::cin >> variable;"));
-                        }
-                        crate::config::Language::Rust => {
-                            println!("   Use {} to read input:", Colors::primary("std::io::stdin()"));
-                            println!("   {}", Colors::text("use std::io;"));
-                            println!("   {}", Colors::text("let mut input = String::new();"));
-                            println!("   {}", Colors::text("io::stdin().read_line(&mut input).expect(\"Failed to read\");"));
-                            println!("   {}", Colors::text("let input = input.trim(); // Remove newline"));
-                        }
+        if let Some(example_input) = exercise.example_input.as_deref().filter(|_| exercise.has_input()) {
+            println!();
+            println!("{}", Colors::label_input("INPUT"));
+            println!("{}", Colors::warning("Example Input:").bold());
+            println!("  {}", Colors::text(example_input));
+            println!();
+            
+            // Show how to read input if input is expected
+            if expects_input {
+                println!("{}", Colors::label_warn("IMPORTANT").bold());
+                println!("{}", Colors::error("Your program must read this input!").bold());
+                match language {
+                    crate::config::Language::JavaScript => {
+                        println!("   Use {} to read input:", Colors::primary("readline() or process.stdin"));
+                        println!("   {}", Colors::text("const readline = require('readline');"));
+                        println!("   {}", Colors::text("const rl = readline.createInterface({ input: process.stdin });"));
+                        println!("   {}", Colors::text("rl.on('line', (line) => { /* use line */ });"));
+                        println!("   {}", Colors::text("Or use: const input = require('fs').readFileSync(0, 'utf-8').trim();"));
+                    }
+                    crate::config::Language::Cpp => {
+                        println!("   Use {} to read input:", Colors::primary("cin or getline()"));
+                        println!("   {}", Colors::text("#include <iostream>"));
+                        println!("   {}", Colors::text("std::string input;"));
+                        println!("   {}", Colors::text("std::getline(std::cin, input);"));
+                        println!("   {}", Colors::text("Or: std::cin >> variable;"));
+                    }
+                    crate::config::Language::Rust => {
+                        println!("   Use {} to read input:", Colors::primary("std::io::stdin()"));
+                        println!("   {}", Colors::text("use std::io;"));
+                        println!("   {}", Colors::text("let mut input = String::new();"));
+                        println!("   {}", Colors::text("io::stdin().read_line(&mut input).expect(\"Failed to read\");"));
+                        println!("   {}", Colors::text("let input = input.trim(); // Remove newline"));
                     }
-                    println!();
                 }
+                println!();
             }
         }
 
-        if let Some(ref example_output) = exercise.example_output {
-            if !example_output.trim().is_empty() {
-                println!();
-                println!("{}", Colors::label_output("OUTPUT"));
-                println!("{}", Colors::warning("Expected Output:").bold());
-                println!("  {}", Colors::text(example_output));
-                println!();
-                // Add a note about printing if output is expected
-                if expects_output {
-                    println!("{}", Colors::label_warn("IMPORTANT").bold());
-                    println!("{}", Colors::error("Your code must produce this output!").bold());
-                    match language {
-                        crate::config::Language::JavaScript => {
-                            println!("   Use {} to print output", Colors::primary("console.log()"));
-                        }
-                        crate::config::Language::Cpp => {
-                            println!("   Use {} to print output", Colors::primary("cout << ... << endl;"));
-                        }
-                        crate::config::Language::Rust => {
-                            println!("   Use {} to print output", Colors::primary("println!()"));
-                        }
+        if let Some(example_output) = exercise.example_output.as_deref().filter(|_| exercise.has_output()) {
+            println!();
+            println!("{}", Colors::label_output("OUTPUT"));
+            println!("{}", Colors::warning("Expected Output:").bold());
+            println!("  {}", Colors::text(example_output));
+            println!();
+            // Add a note about printing if output is expected
+            if expects_output {
+                println!("{}", Colors::label_warn("IMPORTANT").bold());
+                println!("{}", Colors::error("Your code must produce this output!").bold());
+                match language {
+                    crate::config::Language::JavaScript => {
+                        println!("   Use {} to print output", Colors::primary("console.log()"));
+                    }
+                    crate::config::Language::Cpp => {
+                        println!("   Use {} to print output", Colors::primary("cout << ... << endl;"));
+                    }
+                    crate::config::Language::Rust => {
+                        println!("   Use {} to print output", Colors::primary("println!()"));
                     }
-                    println!();
                 }
+                println!();
             }
         }
         
         // If no input/output examples but there are test cases, show the first test case as an example
-        if exercise.example_input.is_none() && exercise.example_output.is_none() && !exercise.test_cases.is_empty() {
+        if !exercise.has_input() && !exercise.has_output() && !exercise.test_cases.is_empty() && function.is_none() {
             let first_test = &exercise.test_cases[0];
             println!("{}", Colors::label_info("EXAMPLE"));
             println!("{}", Colors::primary("Example (from test case):").bold());
@@ -506,16 +1631,9 @@ impl LessonManager {
 
         // Show all test cases that will be used
         if !exercise.test_cases.is_empty() {
-            print_section_header("TEST CASES", |s| Colors::primary(s));
-            
-            // Check if test cases have different inputs (indicates input reading is needed)
-            let has_different_inputs = exercise.test_cases.iter()
-                .filter(|tc| !tc.input.trim().is_empty())
-                .map(|tc| tc.input.trim())
-                .collect::<std::collections::HashSet<_>>()
-                .len() > 1;
+            print_section_header("TEST CASES", Colors::primary);
             
-            if expects_input || has_different_inputs {
+            if expects_input {
                 println!("{}", Colors::text("Your code will be tested with the following inputs:"));
                 println!("{}", Colors::label_warn("IMPORTANT").bold());
                 println!("{}", Colors::error("Your program must READ INPUT from stdin!").bold());
@@ -525,28 +1643,57 @@ impl LessonManager {
             println!();
             
             for (idx, test_case) in exercise.test_cases.iter().enumerate() {
-                if !test_case.input.trim().is_empty() {
+                let label_note = test_case.label_suffix();
+                if let Some(call) = test_case.label.as_deref().filter(|_| function.is_some()) {
+                    print!("  {} ", Colors::label_input(&format!("TEST {}", idx + 1)));
+                    print!("{}", Colors::primary(call));
+                    print!(" {} ", Colors::text(output::glyph("→", "->")));
+                    println!("{}", Colors::success(&test_case.output));
+                } else if !test_case.args.is_empty() {
                     print!("  {} ", Colors::label_input(&format!("TEST {}", idx + 1)));
-                    print!("{}", Colors::warning(&format!("Input = ")));
+                    print!("{}", Colors::warning("Args = "));
+                    print!("{}", Colors::primary(&test_case.args.join(" ")));
+                    if !test_case.input.trim().is_empty() {
+                        print!(" {} ", Colors::warning("Input ="));
+                        print!("{}", Colors::primary(&format!("\"{}\"", test_case.input)));
+                    }
+                    if !test_case.output.trim().is_empty() {
+                        print!(" {} ", Colors::text(output::glyph("→", "->")));
+                        print!("{}", Colors::label_output("OUTPUT"));
+                        print!(" {}", Colors::success(&format!("\"{}\"", test_case.output)));
+                    } else if exercise.expects_no_output() {
+                        print!(" {} ", Colors::text(output::glyph("→", "->")));
+                        print!("{}", Colors::muted("no output"));
+                    }
+                    println!("{}", Colors::muted(&label_note));
+                } else if !test_case.input.trim().is_empty() {
+                    print!("  {} ", Colors::label_input(&format!("TEST {}", idx + 1)));
+                    print!("{}", Colors::warning("Input = "));
                     print!("{}", Colors::primary(&format!("\"{}\"", test_case.input)));
                     if !test_case.output.trim().is_empty() {
-                        print!(" {} ", Colors::text("→"));
+                        print!(" {} ", Colors::text(output::glyph("→", "->")));
                         print!("{}", Colors::label_output("OUTPUT"));
-                        println!(" {}", Colors::success(&format!("\"{}\"", test_case.output)));
-                    } else {
-                        println!();
+                        print!(" {}", Colors::success(&format!("\"{}\"", test_case.output)));
+                    } else if exercise.expects_no_output() {
+                        print!(" {} ", Colors::text(output::glyph("→", "->")));
+                        print!("{}", Colors::muted("no output"));
                     }
-                } else if !test_case.output.trim().is_empty() {
+                    println!("{}", Colors::muted(&label_note));
+                } else {
                     print!("  {} ", Colors::label_output(&format!("TEST {}", idx + 1)));
-                    print!("{}", Colors::success(&format!("Expected Output = \"{}\"", test_case.output)));
-                    println!();
+                    if test_case.has_no_output() {
+                        print!("{}", Colors::success(&format!("Expected Output = {}", test_case.expected_display())));
+                    } else {
+                        print!("{}", Colors::success(&format!("Expected Output = \"{}\"", test_case.expected_display())));
+                    }
+                    println!("{}", Colors::muted(&label_note));
                 }
             }
             println!();
             
             // If test cases have different expected outputs but no input shown,
             // it likely means input is needed but not displayed - add a note
-            if !expects_input && exercise.test_cases.len() > 1 {
+            if !expects_input && function.is_none() && exercise.test_cases.len() > 1 {
                 let outputs: std::collections::HashSet<_> = exercise.test_cases.iter()
                     .map(|tc| tc.output.trim())
                     .collect();
@@ -560,13 +1707,15 @@ impl LessonManager {
             }
         }
 
-        // Display hints if available
-        if !exercise.hints.is_empty() {
-            print_section_header("HINTS", |s| Colors::warning(s));
-            for (idx, hint) in exercise.hints.iter().enumerate() {
+        // Display hints if available; under a hint budget they stay hidden until revealed with 'hint'
+        let shown_hints: &[String] = if self.hint_budget == HintBudget::Unlimited { &exercise.hints } else { &[] };
+        let hint_allowance = self.hint_budget.allowance(exercise.hints.len());
+        if !shown_hints.is_empty() {
+            print_section_header("HINTS", Colors::warning);
+            for (idx, hint) in shown_hints.iter().enumerate() {
                 println!("  {}. {}", idx + 1, hint);
             }
-            let mut hint_num = exercise.hints.len() + 1;
+            let mut hint_num = shown_hints.len() + 1;
             
             // Add hint about input if expected
             if expects_input {
@@ -586,7 +1735,8 @@ impl LessonManager {
                 }
             }
             
-            if expects_output {
+            // A function returns its result, and the generated main prints it
+            if expects_output && function.is_none() {
                 match language {
                     crate::config::Language::JavaScript => {
                         println!("  {}. Remember to use {} to display your result", hint_num, Colors::primary("console.log()"));
@@ -638,22 +1788,132 @@ impl LessonManager {
                 println!();
             }
         }
+        if shown_hints.is_empty() && !exercise.hints.is_empty() {
+            println!(
+                "{}",
+                Colors::muted(&format!(
+                    "This exercise has {} hint(s); you can reveal {}. Try it on your own first.",
+                    exercise.hints.len(),
+                    hint_allowance
+                ))
+            );
+            println!();
+        }
 
-        // Create exercise file
-        let file_path = FileManager::create_exercise_file(&language, exercise_number)?;
+        // Create exercise file; a function exercise's starts as a stub of the function
+        let stub = function.map(|f| FileManager::function_stub(&f.signature));
+        let starter_code = exercise.starter_code.as_deref().or(stub.as_deref());
+        let (file_path, kept_file) = match self.project_lesson_dir(language, lesson.topic) {
+            Some(dir) => FileManager::open_project_exercise_file(&dir, &language, exercise_number, &exercise.title, starter_code)?,
+            None if keep_file => (FileManager::open_exercise_file(lesson.session_dir, &language, exercise_number, starter_code)?, true),
+            None => (FileManager::create_exercise_file(lesson.session_dir, &language, exercise_number, starter_code)?, false),
+        };
+        // Only news in project mode; resuming in the work directory always keeps the file
+        if kept_file && !keep_file {
+            println!("{}", Colors::muted("Your earlier solution for this exercise is still in the file."));
+        }
+        if starter_code.is_some() && !kept_file {
+            println!("{}", Colors::muted("The file has some starter code to get you going. Fill in the TODOs."));
+        }
         
+        let mut outcome = ExerciseOutcome {
+            title: exercise.title.clone(),
+            status: ExerciseStatus::Skipped,
+            attempts: 0,
+            hints_used: 0,
+            skip_rest: false,
+            paused: false,
+        };
+        // Every failed test of every attempt, for the recap once the exercise is over
+        let mut failures = Vec::new();
+
+        // What the file started as, to tell when nothing has been written yet
+        let initial_contents = FileManager::initial_contents(&language, starter_code);
+
         // Retry loop - keep program open until tests pass or user skips
-        let mut retry_count = 0;
         loop {
-            retry_count += 1;
-            
             println!("{}", Colors::info(&format!("Write your solution in: {}", file_path.display())));
-            println!("{}", Colors::muted("Press Enter when you're ready to test your solution, or type 'skip' to skip this exercise (or Ctrl+C to exit)..."));
+            let can_review = outcome.attempts >= review_after_attempts();
+            if self.watch {
+                let mut keys = vec!["Enter: test now", "s: skip", "q: pause"];
+                if shown_hints.is_empty() && hint_allowance > 0 {
+                    keys.insert(1, "h: hint");
+                }
+                if can_review {
+                    keys.insert(keys.len() - 1, "r: move on and mark for review");
+                }
+                println!("{}", Colors::muted(&format!("Watching for changes; the tests run each time you save ({}).", keys.join(", "))));
+            } else {
+                println!("{}", Colors::muted("Press Enter when you're ready to test your solution, or type 'skip' to skip this exercise ('skip-all' to skip the rest of the lesson, 'pause' to take a break and pick up here later)..."));
+                if function.is_none() {
+                    println!("{}", Colors::muted("Type 'input <value>' to run your code with your own stdin first (use \\n for new lines); it isn't graded."));
+                }
+                if shown_hints.is_empty() && hint_allowance > 0 {
+                    println!("{}", Colors::muted(&format!("Type 'hint' to reveal a hint ({} of {} left).", hint_allowance - outcome.hints_used, hint_allowance)));
+                }
+            }
+
+            // What's being worked on stays in sight right at the prompt, however far the rest scrolled
+            println!("{}", lesson.status_line(exercise_number, outcome.attempts + 1));
 
-            let user_input = Text::new("").prompt();
+            // Custom-input runs and hints don't count as an attempt
+            let user_input = loop {
+                if self.watch {
+                    // Mapped to what would be typed at the prompt, so both modes share the handling below
+                    let input = match watch::wait_for_save(&file_path, can_review)? {
+                        WatchEvent::Saved => {
+                            println!("{}", Colors::info("Change detected. Testing..."));
+                            ""
+                        }
+                        WatchEvent::TestNow => "",
+                        WatchEvent::Hint if shown_hints.is_empty() => {
+                            Self::reveal_hint(exercise, hint_allowance, &mut outcome.hints_used);
+                            continue;
+                        }
+                        WatchEvent::Hint => continue,
+                        WatchEvent::Skip => "skip",
+                        WatchEvent::Review => "review",
+                        WatchEvent::Pause => "pause",
+                        WatchEvent::Quit => "quit",
+                    };
+                    break Ok(input.to_string());
+                }
+                let user_input = Text::new("").prompt();
+                if let Some(custom) = user_input.as_deref().ok().and_then(Self::parse_custom_input) {
+                    if function.is_some() {
+                        println!("{}", Colors::muted("Your function is called by the tests, so there's no program to give input to here."));
+                    } else {
+                        Self::run_with_custom_input(language, &file_path, &custom);
+                    }
+                    continue;
+                }
+                if shown_hints.is_empty() && user_input.as_deref().is_ok_and(|input| input.trim().eq_ignore_ascii_case("hint")) {
+                    Self::reveal_hint(exercise, hint_allowance, &mut outcome.hints_used);
+                    continue;
+                }
+                break user_input;
+            };
             
-            // Check if user wants to skip
+            // Check if user wants to skip or pause
             if let Ok(input) = &user_input {
+                if input.trim().to_lowercase() == "pause" {
+                    Self::announce_pause(&file_path);
+                    outcome.paused = true;
+                    return Ok(outcome);
+                }
+                if input.trim().to_lowercase() == "quit" {
+                    return Err(Self::pause_lesson());
+                }
+                if matches!(input.trim().to_lowercase().as_str(), "skip-all" | "finish") {
+                    println!("{}", Colors::warning("Skipping the remaining exercises. Finishing the lesson..."));
+                    outcome.skip_rest = true;
+                    break;
+                }
+                if input.trim().to_lowercase() == "review" && can_review {
+                    println!("{}", Colors::warning("Marked for review. Moving to next..."));
+                    outcome.status = ExerciseStatus::Unsolved;
+                    break;
+                }
                 if input.trim().to_lowercase() == "skip" {
                     println!("{}", Colors::warning("Exercise skipped. Moving to next..."));
                     thread::sleep(Duration::from_millis(1000)); // Brief pause to show message
@@ -663,19 +1923,53 @@ impl LessonManager {
             
             // If user cancelled or wants to continue, proceed with testing
             if user_input.is_err() {
-                return Ok(()); // User cancelled
+                return Err(Self::pause_lesson());
+            }
+
+            // The unchanged template would only fail every test, which says nothing useful
+            if std::fs::read_to_string(&file_path).is_ok_and(|code| code == initial_contents) {
+                println!();
+                println!("{}", Colors::label_info("NOT TESTED"));
+                println!("{}", Colors::info("It looks like you haven't written a solution yet. Edit the file first, then test it."));
+                println!();
+                continue;
             }
 
+            // A fix for wrong generated tests, read on every run so it can be added mid-exercise
+            let override_path = FileManager::test_override_path(&file_path);
+            let graded = match exercise.with_test_override(&override_path) {
+                Ok(Some(fixed)) => {
+                    println!(
+                        "{}",
+                        Colors::muted(&format!("Grading with the {} test(s) in {}.", fixed.test_cases.len(), override_path.display()))
+                    );
+                    Some(fixed)
+                }
+                Ok(None) => None,
+                Err(e) => {
+                    println!("{} {}", Colors::label_warn("WARN"), Colors::warning(&format!("{:#}. Grading with the lesson's tests.", e)));
+                    None
+                }
+            };
+            let exercise = graded.as_ref().unwrap_or(exercise);
+
+            // A function exercise runs as the program built around the learner's function
+            let harness = function.map(|function| Self::write_function_harness(&file_path, function)).transpose()?;
+            let run_path = harness.as_deref().unwrap_or(&file_path);
+
             // Test the solution
+            outcome.attempts += 1;
             let mut all_passed = true;
             let mut errors = Vec::new();
+            let mut prompt_detected = false;
+            let mut read_past_input_detected = false;
             
             // Handle case where there are no test cases
             if exercise.test_cases.is_empty() {
                 println!("{}", Colors::label_warn("WARN"));
                 println!("{}", Colors::warning("No test cases provided for this exercise. Code will be executed but not validated."));
                 // Just try to execute the code to check for syntax errors
-                match Executor::execute(language, &file_path, None) {
+                match Executor::new(language).execute(&file_path, None, &[], None, None) {
                     Ok(_) => {
                         println!("{}", Colors::label_pass("PASS"));
                         println!("{}", Colors::success("Code executed successfully (no test cases to validate)"));
@@ -690,18 +1984,58 @@ impl LessonManager {
                     }
                 }
             } else {
+                let executor = Executor::new(language);
+                // Kept for the recap of what went wrong across attempts
+                let attempt = outcome.attempts;
+                let mut record_failure = |test_index: usize, test_case: &TestCase, output: String| {
+                    failures.push(FailedRun { attempt, test_index, test_case: test_case.clone(), output });
+                };
                 for (test_idx, test_case) in exercise.test_cases.iter().enumerate() {
-                    match Executor::execute(language, &file_path, Some(&test_case.input)) {
+                    // A name that isn't a plain file name is never read, so such a test fails
+                    let expected_file = test_case.expected_file.as_ref().filter(|f| f.has_valid_name());
+                    let collect_file = expected_file.map(|f| f.name.as_str());
+                    match executor.execute(run_path, Some(&test_case.input), &test_case.args, test_case.expected_exit_code, collect_file) {
                         Ok(result) => {
-                            let passed = Executor::compare_output(&result.output, &test_case.output);
+                            let exit_code_matches = test_case.exit_code_matches(result.exit_code);
+                            let file_matches = test_case.file_matches(result.file.as_deref());
+                            let passed = test_case.passes(&result.output) && exit_code_matches && file_matches;
                             
+                            // Append the test's label (if any) so failures explain what was being checked
+                            let label_suffix = test_case.label_suffix();
                             if passed {
-                                println!("{}", Colors::label_pass(&format!("TEST {} PASSED", test_idx + 1)));
+                                println!("{}", Colors::label_pass(&format!("TEST {} PASSED{}", test_idx + 1, label_suffix)));
                             } else {
-                                println!("{}", Colors::label_fail(&format!("TEST {} FAILED", test_idx + 1)));
+                                println!("{}", Colors::label_fail(&format!("TEST {} FAILED{}", test_idx + 1, label_suffix)));
+                                record_failure(test_idx, test_case, reported_output(test_case, &result));
                                 print!("Expected: ");
-                                println!("{}", Colors::warning(&test_case.output));
-                                if result.output.trim().is_empty() {
+                                println!("{}", Colors::warning(&test_case.expected_display()));
+                                if !exit_code_matches {
+                                    print!("Got: ");
+                                    println!("{}", Colors::error(&format!(
+                                        "{} ({})",
+                                        result.output.trim_end(),
+                                        exit_status(result.exit_code)
+                                    )));
+                                    println!();
+                                    println!("{}", Colors::label_info("TIP"));
+                                    println!("{}", Colors::info(&format!(
+                                        "This test expects your program to exit with code {0}. Use process.exit({0}) (JS), return {0} from main (C++), or std::process::exit({0}) (Rust).",
+                                        test_case.expected_exit_code.unwrap_or(0)
+                                    )));
+                                } else if !file_matches && test_case.passes(&result.output) {
+                                    let name = test_case.expected_file.as_ref().map(|f| f.name.as_str()).unwrap_or_default();
+                                    print!("Got: ");
+                                    match result.file {
+                                        Some(ref contents) => println!("{}", Colors::error(&format!("{} containing:\n{}", name, contents))),
+                                        None => println!("{}", Colors::error(&format!("no file named {}", name))),
+                                    }
+                                    println!();
+                                    println!("{}", Colors::label_info("TIP"));
+                                    println!("{}", Colors::info(&format!(
+                                        "This test checks the file your program writes. Open it by its relative name (\"{}\") so it's created in the directory the program runs in.",
+                                        name
+                                    )));
+                                } else if result.output.trim().is_empty() {
                                     print!("Got: ");
                                     println!("{}", Colors::error("(empty) (no output)"));
                                     println!();
@@ -711,68 +2045,113 @@ impl LessonManager {
                                 } else {
                                     print!("Got: ");
                                     println!("{}", Colors::error(&result.output));
+                                    if exercise.expects_no_output() {
+                                        println!();
+                                        println!("{}", Colors::label_info("TIP"));
+                                        println!("{}", Colors::info("This test expects your program to print nothing. Remove any print statements, including ones left over from debugging."));
+                                    } else if whitespace_only_difference(&result.output, &test_case.output) {
+                                        print_whitespace_difference(&test_case.output, &result.output);
+                                    }
+                                    if has_extra_prompt(&result.output, &test_case.output) {
+                                        prompt_detected = true;
+                                        println!();
+                                        println!("{}", Colors::label_info("TIP"));
+                                        println!("{}", Colors::info(PROMPT_TIP));
+                                    }
                                 }
                                 all_passed = false;
                             }
                         }
+                        // Without a compiler nothing can be graded, so stop instead of asking to retry
+                        Err(e) if exit_code::is_toolchain_missing(&e) => return Err(Self::pause_for_toolchain(e)),
                         Err(e) => {
+                            // The build fails the same way for every test, so its errors are shown once
+                            if let Some(build) = compile_error(&e).filter(|c| c.diagnostics.iter().any(Diagnostic::is_error)) {
+                                println!("{}", Colors::label_fail("COMPILE ERROR"));
+                                print_compile_error(build);
+                                let error_msg = e.to_string();
+                                record_failure(test_idx, test_case, error_msg.clone());
+                                errors.push(error_msg);
+                                all_passed = false;
+                                break;
+                            }
                             println!("{}", Colors::label_fail(&format!("TEST {} ERROR", test_idx + 1)));
                             let error_msg = format!("{}", e);
+                            record_failure(test_idx, test_case, error_msg.clone());
                             println!("{}", Colors::error(&error_msg));
-                            errors.push(error_msg);
                             println!();
                             println!("{}", Colors::label_info("TIP"));
-                            println!("{}", Colors::info("Check your code for syntax errors or missing output statements."));
+                            if read_past_input(language, &error_msg) {
+                                read_past_input_detected = true;
+                                println!("{}", Colors::info(READ_PAST_INPUT_TIP));
+                            } else {
+                                println!("{}", Colors::info("Check your code for syntax errors or missing output statements."));
+                            }
+                            errors.push(error_msg);
                             all_passed = false;
                         }
                     }
                 }
             }
 
+            // A clean-code exercise also fails on compiler warnings, which are listed
+            let mut has_warnings = false;
+            if all_passed && exercise.require_clean {
+                match warnings_failure(language, run_path) {
+                    Ok(Some((message, warnings))) => {
+                        println!();
+                        println!("{}", Colors::label_fail("WARNINGS"));
+                        println!("{}", Colors::error(&message));
+                        for warning in &warnings {
+                            println!("  {}", Colors::warning(warning));
+                        }
+                        errors.push(format!("{}\n     {}", message, warnings.join("\n     ")));
+                        has_warnings = true;
+                        all_passed = false;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        println!("{} {}", Colors::label_warn("WARN"), Colors::warning(&format!("Couldn't check your code for warnings: {:#}", e)));
+                    }
+                }
+            }
+
+            if let Some(ref harness) = harness {
+                let _ = std::fs::remove_file(harness);
+            }
+
             if all_passed {
                 println!();
                 println!("{}", Colors::label_pass("SUCCESS").bold());
                 println!("{}", Colors::success("All tests passed!").bold());
+                print_style_notes(&file_path);
+                outcome.status = ExerciseStatus::Passed;
                 break; // Exit retry loop and move to next exercise
             } else {
                 // Clear screen and re-display exercise context for clean view
                 Self::clear_screen();
                 
                 // Re-display exercise header
-                let exercise_header = format!("Exercise {}: {} (Attempt {})", exercise_number, exercise.title, retry_count + 1);
-                let header_width = TERMINAL_WIDTH.min(exercise_header.chars().count() + 4);
+                let exercise_header = format!("Exercise {}: {} (Attempt {})", exercise_number, exercise.title, outcome.attempts + 1);
+                let header_width = TERMINAL_WIDTH.min(output::display_width(&exercise_header) + 4);
+                let exercise_header = Borders::truncate(&exercise_header, header_width - 4);
                 println!();
                 println!("{}", Colors::primary(&Borders::top(header_width)));
                 println!("{}", Colors::primary(&Borders::box_line(&exercise_header, header_width)));
                 println!("{}", Colors::primary(&Borders::bottom(header_width)));
                 
                 // Re-display quick reference
-                print_section_header("QUICK REFERENCE", |s| Colors::accent(s));
+                print_section_header("QUICK REFERENCE", Colors::accent);
                 
                 // Show code examples FIRST
                 if !content.code_examples.is_empty() {
                     println!("{}", Colors::warning("Example Code:").bold());
-                    for (idx, example) in content.code_examples.iter().take(2).enumerate() {
+                    for (idx, example) in content.code_examples.iter().take(self.verbosity.code_examples().1).enumerate() {
                         println!("\n  {}", Colors::primary(&format!("Example {}:", idx + 1)));
-                        // Calculate box width: find longest line, add padding, but cap at terminal width
-                        let max_line_len = example.code.lines()
-                            .map(|l| l.chars().count())
-                            .max()
-                            .unwrap_or(0);
-                        // Cap at reasonable width (60 chars max for code), leaving margin for box borders and indentation
-                        let effective_max = max_line_len.min(60).min(TERMINAL_WIDTH - 8);
-                        let box_width = effective_max + 4; // Add padding for box borders (2 chars on each side)
-                        println!("  {}", Colors::text(&Borders::top(box_width)));
-                        for line in example.code.lines() {
-                            // Truncate line if it's too long
-                            let display_line = if line.chars().count() > effective_max {
-                                &line[..effective_max.min(line.len())]
-                            } else {
-                                line
-                            };
-                            println!("  {}", Borders::box_line_left(display_line, box_width));
+                        for line in render_code_box(&example.code, TERMINAL_WIDTH - 4) {
+                            println!("  {}", line);
                         }
-                        println!("  {}", Colors::text(&Borders::bottom(box_width)));
+                        print_check_error(example);
                         if let Some(first_sentence) = example.explanation.split('.').next() {
                             println!("  {} {}\n", Colors::label_info("TIP"), first_sentence.trim());
                         }
@@ -780,13 +2159,17 @@ impl LessonManager {
                 }
                 
                 println!("{}", Colors::primary(&Borders::separator(TERMINAL_WIDTH)));
-                print_section_header("EXERCISE INSTRUCTIONS", |s| Colors::success(s));
+                print_section_header("EXERCISE INSTRUCTIONS", Colors::success);
                 print_wrapped(&exercise.description, TERMINAL_WIDTH, 0);
                 println!();
                 
                 // Show test results summary
-                print_section_header("TEST RESULTS", |s| Colors::error(s));
-                println!("{}", Colors::error("Some tests failed. Try again!").bold());
+                print_section_header("TEST RESULTS", Colors::error);
+                if has_warnings {
+                    println!("{}", Colors::error("All tests passed, but your code has warnings. Fix them and try again!").bold());
+                } else {
+                    println!("{}", Colors::error("Some tests failed. Try again!").bold());
+                }
                 
                 // Show errors if any
                 if !errors.is_empty() {
@@ -797,15 +2180,31 @@ impl LessonManager {
                     }
                 }
                 
+                // The per-test output was cleared, so repeat any specific diagnosis here
+                if prompt_detected {
+                    println!();
+                    println!("{}", Colors::label_tip("TIP").bold());
+                    print_wrapped(PROMPT_TIP, TERMINAL_WIDTH, 2);
+                    println!();
+                }
+                if read_past_input_detected {
+                    println!();
+                    println!("{}", Colors::label_tip("TIP").bold());
+                    print_wrapped(READ_PAST_INPUT_TIP, TERMINAL_WIDTH, 2);
+                    println!();
+                }
+
                 // Show helpful debugging info
-                print_section_header("DEBUGGING TIPS", |s| Colors::info(s));
+                print_section_header("DEBUGGING TIPS", Colors::info);
                 println!("  1. Make sure your code produces the expected output");
                 println!("  2. Check that you're using the correct syntax for your language");
                 println!("  3. Verify your code runs without errors");
                 
-                if !exercise.hints.is_empty() {
+                // Only the hints the learner has seen; the rest stay hidden under a hint budget
+                let seen_hints = if shown_hints.is_empty() { outcome.hints_used } else { shown_hints.len() };
+                if seen_hints > 0 {
                     println!("\n{}", Colors::warning("Remember the hints:").bold());
-                    for (idx, hint) in exercise.hints.iter().enumerate() {
+                    for (idx, hint) in exercise.hints.iter().take(seen_hints).enumerate() {
                         print!("  {}. ", Colors::warning(&(idx + 1).to_string()).bold());
                         print_wrapped(hint, TERMINAL_WIDTH - 4, 4);
                         println!();
@@ -816,32 +2215,366 @@ impl LessonManager {
                 println!("{}", Colors::info(&format!("Write your solution in: {}", file_path.display())));
                 println!("{}", Colors::muted("Review the syntax guide and examples above, then try again."));
                 
-                // Ask if user wants to retry or skip
-                let retry = Confirm::new("Would you like to try again? (Edit your code and press Enter to test)")
-                    .with_default(true)
+                // Watch mode keeps waiting for the next save instead of asking
+                if self.watch {
+                    println!("{}", Colors::muted("Fix your code and save it to test again."));
+                    println!();
+                    continue;
+                }
+
+                // Ask if user wants to retry or skip; after enough failures they may also move on
+                // without skipping, so the exercise is marked for review instead
+                const RETRY: &str = "Try again (edit your code and press Enter to test)";
+                const SKIP: &str = "Skip this exercise";
+                const MARK_FOR_REVIEW: &str = "Move on and mark it for review";
+                let mut choices = vec![RETRY, SKIP];
+                if outcome.attempts >= review_after_attempts() {
+                    choices.push(MARK_FOR_REVIEW);
+                }
+                let retry = Select::new("What would you like to do?", choices)
+                    .with_help_message("Esc pauses the lesson")
                     .prompt();
                 
                 match retry {
-                    Ok(true) => {
+                    Ok(RETRY) => {
                         println!();
                         println!("{}", Colors::info("Edit your code and press Enter when ready to test again..."));
                         // Loop will continue
                     }
-                    Ok(false) => {
+                    Ok(MARK_FOR_REVIEW) => {
+                        println!();
+                        println!("{}", Colors::warning("Marked for review. Moving to next..."));
+                        outcome.status = ExerciseStatus::Unsolved;
+                        break;
+                    }
+                    Ok(_) => {
                         println!();
                         println!("{}", Colors::warning("Skipping this exercise. Moving to next..."));
                         break; // Skip this exercise and move to next
                     }
                     Err(_) => {
                         // User cancelled (Ctrl+C or similar)
-                        println!();
-                        println!("{}", Colors::warning("Lesson interrupted. Progress not saved."));
-                        return Err(anyhow::anyhow!("Lesson interrupted by user")); // Return error so journey mode knows lesson wasn't completed
+                        return Err(Self::pause_lesson());
                     }
                 }
             }
         }
 
-        Ok(())
+        // Pausing or skipping the rest of the lesson means the learner wants to get away
+        if !outcome.skip_rest {
+            Self::offer_failure_recap(language, &file_path, function, &failures);
+        }
+
+        Ok(outcome)
+    }
+
+    // Writes the program built around the learner's function next to the exercise
+    // file and returns its path; the caller removes it once it has run
+    fn write_function_harness(file_path: &Path, function: &FunctionSpec) -> Result<PathBuf> {
+        let code = std::fs::read_to_string(file_path).context("Failed to read exercise file")?;
+        let calls: Vec<&str> = function.cases.iter().map(|c| c.call.as_str()).collect();
+        let harness = FileManager::harness_path(file_path);
+        std::fs::write(&harness, RustRunner::function_harness(&code, &calls))
+            .map_err(|e| fs_error::write_error(e, "Failed to write the program around your function", &harness))?;
+        Ok(harness)
+    }
+
+    // Offers to replay the tests that failed along the way against the code as it is now,
+    // next to what the learner's code printed the first time each one failed
+    fn offer_failure_recap(
+        language: Language,
+        file_path: &std::path::Path,
+        function: Option<&FunctionSpec>,
+        failures: &[FailedRun],
+    ) {
+        if failures.is_empty() {
+            return;
+        }
+        println!();
+        let show = Confirm::new("Show what failed earlier?")
+            .with_default(false)
+            .with_help_message("Runs the tests that failed before on your code as it is now")
+            .prompt();
+        if !matches!(show, Ok(true)) {
+            return;
+        }
+
+        print_section_header("WHAT FAILED EARLIER", Colors::warning);
+        // A function exercise runs as the program around the function, as when it was graded
+        let harness = match function.map(|function| Self::write_function_harness(file_path, function)).transpose() {
+            Ok(harness) => harness,
+            Err(e) => {
+                println!("{} {}", Colors::label_warn("WARN"), Colors::warning(&format!("{:#}", e)));
+                return;
+            }
+        };
+        let run_path = harness.as_deref().unwrap_or(file_path);
+        let executor = Executor::new(language);
+        // Failures of the same test are shown together, in the order the tests run
+        let mut tests: Vec<(usize, &TestCase)> = Vec::new();
+        for failure in failures {
+            if !tests.iter().any(|&(index, test_case)| index == failure.test_index && *test_case == failure.test_case) {
+                tests.push((failure.test_index, &failure.test_case));
+            }
+        }
+        tests.sort_by_key(|&(index, _)| index);
+        for (test_index, test_case) in tests {
+            let runs: Vec<&FailedRun> = failures
+                .iter()
+                .filter(|f| f.test_index == test_index && f.test_case == *test_case)
+                .collect();
+            let attempts: Vec<String> = runs.iter().map(|f| f.attempt.to_string()).collect();
+            println!(
+                "{} {}",
+                Colors::label_info(&format!("TEST {}{}", test_index + 1, test_case.label_suffix())),
+                Colors::muted(&format!("failed on attempt{} {}", if attempts.len() == 1 { "" } else { "s" }, attempts.join(", ")))
+            );
+            if !test_case.input.trim().is_empty() {
+                println!("Input: {}", Colors::info(&test_case.input.trim_end().replace('\n', "\\n")));
+            }
+            if !test_case.args.is_empty() {
+                println!("Args: {}", Colors::info(&test_case.args.join(" ")));
+            }
+            println!("Expected: {}", Colors::warning(&test_case.expected_display()));
+
+            let now = match executor.execute(run_path, Some(&test_case.input), &test_case.args, test_case.expected_exit_code, None) {
+                Ok(result) => reported_output(test_case, &result),
+                Err(e) => e.to_string(),
+            };
+            let first = runs[0];
+            let then_title = format!("Attempt {}", first.attempt);
+            for row in render_side_by_side(&test_case.output, (&then_title, &first.output), ("Now", &now), TERMINAL_WIDTH - 2) {
+                println!("  {}", row);
+            }
+            println!();
+        }
+        if let Some(ref harness) = harness {
+            let _ = std::fs::remove_file(harness);
+        }
+    }
+
+    // Shows the next hidden hint, if the exercise has one and the budget allows it
+    fn reveal_hint(exercise: &crate::ollama::formatter::Exercise, allowance: usize, hints_used: &mut usize) {
+        if *hints_used >= allowance {
+            let reason = if allowance < exercise.hints.len() { "Your hint budget for this exercise is spent." } else { "No more hints for this exercise." };
+            println!("{}", Colors::warning(reason));
+            return;
+        }
+        *hints_used += 1;
+        println!("{}", Colors::label_tip(&format!("HINT {}/{}", hints_used, allowance)));
+        print_wrapped(&exercise.hints[*hints_used - 1], TERMINAL_WIDTH - 4, 4);
+        println!();
+    }
+
+    // Recognises "input <value>" (or a bare "input" for empty stdin), turning "\n" into new lines
+    fn parse_custom_input(line: &str) -> Option<String> {
+        let line = line.trim_start();
+        let rest = line.get(..5).filter(|cmd| cmd.eq_ignore_ascii_case("input")).map(|_| &line[5..])?;
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        Some(rest.trim().replace("\\n", "\n"))
+    }
+
+    fn run_with_custom_input(language: Language, file_path: &std::path::Path, input: &str) {
+        println!("{}", Colors::label_input("INPUT"));
+        println!("{}", Colors::text(if input.is_empty() { "(empty)" } else { input }));
+        match Executor::new(language).execute(file_path, Some(input), &[], None, None) {
+            Ok(result) => {
+                println!("{}", Colors::label_output("OUTPUT"));
+                println!("{}", Colors::text(result.output.trim_end()));
+            }
+            Err(e) => {
+                println!("{}", Colors::label_fail("ERROR"));
+                println!("{}", Colors::error(&e.to_string()));
+            }
+        }
+        println!();
+    }
+
+    // Shows why the toolchain can't be used and pauses, so 'continue' picks up once it's fixed
+    fn pause_for_toolchain(e: anyhow::Error) -> anyhow::Error {
+        println!("{}", Colors::label_fail("TOOLCHAIN"));
+        println!("{}", Colors::error(&e.to_string()));
+        println!();
+        println!("{}", Colors::warning("Lesson paused. Install or repair the toolchain, then run 'cursed-coddy continue'."));
+        e
+    }
+
+    // Progress is saved after every exercise, so stopping here loses nothing.
+    // Returns an error so journey mode knows the lesson wasn't completed.
+    fn pause_lesson() -> anyhow::Error {
+        println!();
+        println!("{}", Colors::warning("Lesson paused. Run 'cursed-coddy continue' to pick up from this exercise."));
+        exit_code::cancelled("Lesson interrupted by user")
+    }
+
+    // A break the learner asked for. The lesson and its content are already cached
+    // and progress is recorded after every exercise, so only the exercise's file needs
+    // keeping, and resuming keeps it.
+    fn announce_pause(file_path: &Path) {
+        println!();
+        println!("{}", Colors::success(&format!("Lesson paused {} resume with `cursed-coddy continue`", output::glyph("—", "-"))));
+        println!("{}", Colors::muted(&format!("Your code for this exercise stays in {}", file_path.display())));
+    }
+
+    fn print_lesson_summary(outcomes: &[ExerciseOutcome], quiz_score: Option<(usize, usize)>, elapsed: Duration) {
+        print_section_header("LESSON SUMMARY", Colors::success);
+
+        let title_width = outcomes.iter()
+            .map(|o| output::display_width(&o.title))
+            .max()
+            .unwrap_or(0)
+            .clamp(8, 40);
+
+        println!(
+            "  {}",
+            Colors::muted(&format!("{:>2}  {:<title_width$}  {:<8}  {}", "#", "Exercise", "Status", "Attempts"))
+        );
+        println!("  {}", Colors::muted(&Borders::separator(title_width + 26)));
+        for (idx, outcome) in outcomes.iter().enumerate() {
+            let title = Borders::pad(&Borders::truncate(&outcome.title, title_width), title_width);
+            let status = match outcome.status {
+                ExerciseStatus::Passed => Colors::success(&format!("{:<8}", "PASSED")),
+                ExerciseStatus::Skipped => Colors::warning(&format!("{:<8}", "SKIPPED")),
+                ExerciseStatus::Unsolved => Colors::error(&format!("{:<8}", "UNSOLVED")),
+            };
+            println!(
+                "  {:>2}  {}  {}  {}",
+                idx + 1,
+                title,
+                status,
+                outcome.attempts
+            );
+        }
+        println!();
+
+        let passed = outcomes.iter().filter(|o| o.status == ExerciseStatus::Passed).count();
+        let secs = elapsed.as_secs();
+        println!("Exercises passed: {}", Colors::success(&format!("{}/{}", passed, outcomes.len())));
+        if let Some((correct, answered)) = quiz_score {
+            println!("Quiz: {}", Colors::success(&format!("{}/{} correct", correct, answered)));
+        }
+        let hints_used: usize = outcomes.iter().map(|o| o.hints_used).sum();
+        if hints_used > 0 {
+            println!("Hints revealed: {}", Colors::warning(&hints_used.to_string()));
+        }
+        println!("Total time: {}", Colors::primary(&format!("{}m {:02}s", secs / 60, secs % 60)));
+        // Shows whether slowness comes from the model or from the toolchain
+        if let Some(breakdown) = timing::breakdown() {
+            println!("Time spent: {}", Colors::muted(&breakdown));
+        }
+
+        // Skipped or unsolved exercises and ones that took several tries are worth another look
+        let to_review: Vec<&ExerciseOutcome> = outcomes.iter()
+            .filter(|o| o.status != ExerciseStatus::Passed || o.attempts >= STRUGGLE_ATTEMPTS)
+            .collect();
+        if !to_review.is_empty() {
+            println!();
+            println!("{}", Colors::label_tip("REVIEW"));
+            println!("{}", Colors::warning("Topics worth reviewing:"));
+            for outcome in to_review {
+                let reason = match outcome.status {
+                    ExerciseStatus::Skipped => "skipped".to_string(),
+                    ExerciseStatus::Unsolved => format!("marked for review after {} attempts", outcome.attempts),
+                    ExerciseStatus::Passed => format!("took {} attempts", outcome.attempts),
+                };
+                println!("  - {} {}", outcome.title, Colors::muted(&format!("({})", reason)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // Returns a bare lesson, remembering how many exercises the generator it was handed asks for
+    struct StubSource {
+        exercise_count: Rc<Cell<usize>>,
+    }
+
+    impl ContentSource for StubSource {
+        fn get_content(
+            &self,
+            generator: &Generator,
+            _language: Language,
+            _difficulty: Difficulty,
+            lesson_type: LessonType,
+            topic: &str,
+        ) -> Result<GeneratedContent> {
+            self.exercise_count.set(generator.exercise_count(lesson_type));
+            Ok(serde_json::from_value(serde_json::json!({ "concept": format!("All about {}", topic) }))?)
+        }
+    }
+
+    #[test]
+    fn options_set_after_the_source_still_apply_to_it() {
+        crate::progress::set_data_dir(std::env::temp_dir().join("cursed-coddy-tests"));
+        let seen = Rc::new(Cell::new(0));
+        let manager = LessonManager::new()
+            .unwrap()
+            .with_source(Box::new(StubSource { exercise_count: seen.clone() }))
+            .with_exercise_count(Some(3))
+            .with_verify_examples(true);
+
+        let content = manager
+            .lesson_content(Language::Rust, Difficulty::Beginner, LessonType::Short, "stub source topic")
+            .unwrap();
+        assert_eq!(content.concept, "All about stub source topic");
+        assert_eq!(seen.get(), 3);
+    }
+
+    #[test]
+    fn wrap_text_keeps_a_line_of_exactly_the_width() {
+        let line = "a".repeat(20);
+        assert_eq!(wrap_text(&line, 20, 0), line);
+        assert_eq!(wrap_text("aaaaaaaaa bbbbbbbbbb", 20, 0), "aaaaaaaaa bbbbbbbbbb");
+        assert_eq!(wrap_text("aaaaaaaaa bbbbbbbbbbb", 20, 0), "aaaaaaaaa\nbbbbbbbbbbb");
+
+        // A word wider than the line is broken at the edge, not a column early
+        assert_eq!(wrap_text(&"a".repeat(25), 20, 0), format!("{}\n{}", "a".repeat(20), "a".repeat(5)));
+    }
+
+    #[test]
+    fn a_program_killed_by_a_signal_is_not_reported_as_exit_code_zero() {
+        assert_eq!(exit_status(Some(3)), "exit code 3");
+        assert_eq!(exit_status(None), "killed by signal");
+    }
+
+    #[test]
+    fn an_unused_variable_fails_a_clean_code_exercise() {
+        let dir = std::env::temp_dir().join(format!("cursed-coddy-clean-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("main.rs");
+
+        std::fs::write(&file, "fn main() {\n    let unused = 1;\n    println!(\"hi\");\n}\n").unwrap();
+        let (message, warnings) = warnings_failure(Language::Rust, &file).unwrap().expect("a warning");
+        assert!(message.contains("1 warning."), "{}", message);
+        assert!(warnings[0].contains("line 2") && warnings[0].contains("unused variable"), "{:?}", warnings);
+
+        std::fs::write(&file, "fn main() {\n    println!(\"hi\");\n}\n").unwrap();
+        assert!(warnings_failure(Language::Rust, &file).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reading_past_the_input_is_told_by_each_languages_own_error() {
+        let rust_eof = "thread 'main' panicked at src/main.rs:4:50:\ncalled `Result::unwrap()` on an `Err` value: Error { kind: UnexpectedEof, message: \"failed to fill whole buffer\" }";
+        let rust_empty = "called `Result::unwrap()` on an `Err` value: ParseIntError { kind: Empty }";
+        let node = "TypeError: Cannot read properties of undefined (reading 'trim')\n    at Object.<anonymous> (/tmp/exercise_1.js:3:28)";
+        assert!(read_past_input(Language::Rust, rust_eof));
+        assert!(read_past_input(Language::Rust, rust_empty));
+        assert!(read_past_input(Language::JavaScript, node));
+
+        // Errors that only look alike
+        assert!(!read_past_input(Language::Rust, "called `Result::unwrap()` on an `Err` value: ParseIntError { kind: InvalidDigit }"));
+        assert!(!read_past_input(Language::Rust, "called `Option::unwrap()` on a `None` value"));
+        assert!(!read_past_input(Language::JavaScript, "TypeError: Cannot read properties of undefined (reading 'name')"));
+        assert!(!read_past_input(Language::Cpp, "terminate called after throwing an instance of 'std::out_of_range'\n  what():  vector::_M_range_check: EOF"));
+        // Another language's message is not this one's
+        assert!(!read_past_input(Language::JavaScript, rust_empty));
     }
 }
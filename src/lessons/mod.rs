@@ -1,7 +1,16 @@
+pub mod content_source;
 pub mod human_lessons;
 pub mod journey;
 pub mod lesson_manager;
+pub mod project;
+pub mod roadmap;
+pub mod self_test;
+pub mod style_notes;
+pub mod watch;
 
+pub use content_source::*;
 pub use human_lessons::*;
 pub use journey::*;
 pub use lesson_manager::*;
+pub use self_test::*;
+pub use style_notes::*;
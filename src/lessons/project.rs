@@ -0,0 +1,160 @@
+use crate::cli::fs_error;
+use crate::execution::FileManager;
+use crate::ollama::formatter::{GeneratedContent, LessonPack, LessonPackMeta};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+// The lesson's notes, readable on their own or on a code hosting site
+const LESSON_NOTES_FILE: &str = "lesson.md";
+
+// The lesson as a pack, so it can be played again with 'cursed-coddy play'
+const LESSON_PACK_FILE: &str = "lesson.coddy.json";
+
+/// Writes lesson.md and lesson.coddy.json into a project lesson folder. Running
+/// the lesson again rewrites them, except for notes the learner has edited, which
+/// are kept; the pack's creation time only changes when the lesson did. Returns
+/// whether edited notes were kept.
+pub fn write_lesson_files(lesson_dir: &Path, meta: LessonPackMeta, content: &GeneratedContent) -> Result<bool> {
+    std::fs::create_dir_all(lesson_dir)
+        .map_err(|e| fs_error::write_error(e, "Failed to create the lesson folder", lesson_dir))?;
+
+    let pack_path = lesson_dir.join(LESSON_PACK_FILE);
+    let previous = std::fs::read_to_string(&pack_path).ok();
+    let previous_pack = previous.as_deref().and_then(|json| LessonPack::from_json(json).ok());
+
+    // The notes are ours to rewrite when they're missing or still what the previous pack produced
+    let notes_path = lesson_dir.join(LESSON_NOTES_FILE);
+    let notes = lesson_markdown(&meta, content);
+    let notes_kept = match std::fs::read_to_string(&notes_path) {
+        Ok(current) => {
+            current != notes
+                && previous_pack
+                    .as_ref()
+                    .is_none_or(|pack| current != lesson_markdown(&pack.meta, &pack.content))
+        }
+        Err(_) => false,
+    };
+    if !notes_kept {
+        std::fs::write(&notes_path, notes)
+            .map_err(|e| fs_error::write_error(e, "Failed to write the lesson notes", &notes_path))?;
+    }
+
+    // Same lesson as before: keep its creation time, and the file untouched
+    if let Some(pack) = previous_pack {
+        let unchanged = LessonPackMeta { created_at: pack.meta.created_at.clone(), ..meta.clone() };
+        let json = LessonPack::new(unchanged, content.clone()).to_json().context("Failed to serialize lesson pack")?;
+        if previous.as_deref() == Some(json.as_str()) {
+            return Ok(notes_kept);
+        }
+    }
+    let json = LessonPack::new(meta, content.clone()).to_json().context("Failed to serialize lesson pack")?;
+    std::fs::write(&pack_path, json)
+        .map_err(|e| fs_error::write_error(e, "Failed to write the lesson pack", &pack_path))?;
+    Ok(notes_kept)
+}
+
+// The lesson content as Markdown, with each exercise linking to the file it's solved in
+fn lesson_markdown(meta: &LessonPackMeta, content: &GeneratedContent) -> String {
+    let fence = meta.language.key();
+    let mut md = format!(
+        "# {}\n\n{} · {} · {} lesson\n",
+        meta.topic,
+        meta.language.display_name(),
+        meta.difficulty.display_name(),
+        meta.lesson_type
+    );
+
+    if !content.concept.trim().is_empty() {
+        md.push_str(&format!("\n## Concept\n\n{}\n", content.concept.trim()));
+    }
+    if !content.step_by_step.is_empty() {
+        md.push_str("\n## Step by step\n\n");
+        for (idx, step) in content.step_by_step.iter().enumerate() {
+            md.push_str(&format!("{}. {}\n", idx + 1, step.trim()));
+        }
+    }
+    if !content.syntax_guide.trim().is_empty() {
+        md.push_str(&format!("\n## Syntax\n\n{}\n", content.syntax_guide.trim()));
+    }
+    if !content.common_patterns.is_empty() {
+        md.push_str("\n## Common patterns\n\n");
+        for pattern in &content.common_patterns {
+            md.push_str(&format!("- {}\n", pattern.trim()));
+        }
+    }
+    if !content.code_examples.is_empty() {
+        md.push_str("\n## Examples\n");
+        for example in &content.code_examples {
+            md.push_str(&format!("\n```{}\n{}\n```\n", fence, example.code.trim_end()));
+            if !example.explanation.trim().is_empty() {
+                md.push_str(&format!("\n{}\n", example.explanation.trim()));
+            }
+        }
+    }
+
+    md.push_str("\n## Exercises\n");
+    for (idx, exercise) in content.exercises.iter().enumerate() {
+        let file_name = FileManager::project_exercise_file_name(&meta.language, idx + 1, &exercise.title);
+        md.push_str(&format!("\n### {}. {}\n\n{}\n\nSolution: [{}]({})\n", idx + 1, exercise.title, exercise.description.trim(), file_name, file_name));
+        for (test_idx, test_case) in exercise.test_cases.iter().enumerate() {
+            md.push_str(&format!("\n**Test {}{}**\n\n", test_idx + 1, test_case.label_suffix()));
+            if !test_case.args.is_empty() {
+                md.push_str(&format!("Arguments: `{}`\n\n", test_case.args.join(" ")));
+            }
+            if !test_case.input.trim().is_empty() {
+                md.push_str(&format!("Input:\n\n```text\n{}\n```\n\n", test_case.input.trim_end()));
+            }
+            md.push_str(&format!("Expected output:\n\n```text\n{}\n```\n", test_case.expected_display().trim_end()));
+        }
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Difficulty, Language, LessonType};
+
+    fn meta(created_at: &str) -> LessonPackMeta {
+        LessonPackMeta {
+            language: Language::Rust,
+            difficulty: Difficulty::Beginner,
+            lesson_type: LessonType::Short,
+            topic: "loops".to_string(),
+            created_at: created_at.to_string(),
+        }
+    }
+
+    fn content(concept: &str) -> GeneratedContent {
+        GeneratedContent::from_json(&format!(r#"{{"concept": "{}"}}"#, concept)).unwrap()
+    }
+
+    #[test]
+    fn edited_notes_are_kept_and_the_creation_time_follows_the_content() {
+        let dir = std::env::temp_dir().join(format!("cursed-coddy-project-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let created_at = |dir: &Path| {
+            LessonPack::from_json(&std::fs::read_to_string(dir.join(LESSON_PACK_FILE)).unwrap()).unwrap().meta.created_at
+        };
+
+        assert!(!write_lesson_files(&dir, meta("1"), &content("Loops repeat")).unwrap());
+        // Taking the same lesson again changes nothing
+        assert!(!write_lesson_files(&dir, meta("2"), &content("Loops repeat")).unwrap());
+        assert_eq!(created_at(&dir), "1");
+
+        // Untouched notes follow a changed lesson, and so does the creation time
+        assert!(!write_lesson_files(&dir, meta("3"), &content("Loops run again")).unwrap());
+        assert!(std::fs::read_to_string(dir.join(LESSON_NOTES_FILE)).unwrap().contains("Loops run again"));
+        assert_eq!(created_at(&dir), "3");
+
+        // Notes the learner wrote in are left alone
+        let notes_path = dir.join(LESSON_NOTES_FILE);
+        let edited = format!("{}\nMy own note\n", std::fs::read_to_string(&notes_path).unwrap());
+        std::fs::write(&notes_path, &edited).unwrap();
+        assert!(write_lesson_files(&dir, meta("4"), &content("Loops, once more")).unwrap());
+        assert_eq!(std::fs::read_to_string(&notes_path).unwrap(), edited);
+        assert_eq!(created_at(&dir), "4");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
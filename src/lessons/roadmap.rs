@@ -0,0 +1,102 @@
+use crate::cli::colors::Colors;
+use crate::cli::output;
+use crate::config::curriculum::{Curriculum, Stage};
+use crate::progress::LessonRecord;
+use colored::Colorize;
+
+/// How 'roadmap' writes the curriculum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RoadmapFormat {
+    /// Coloured, for reading in the terminal
+    #[default]
+    Text,
+    /// A Markdown document with a checkbox per topic, to print or share as a syllabus
+    Md,
+}
+
+/// A curriculum with each topic marked done or not from the learner's completed lessons
+pub struct Roadmap {
+    curriculum: Curriculum,
+    // Per stage, whether each of its topics is done
+    done: Vec<Vec<bool>>,
+}
+
+impl Roadmap {
+    /// A topic is done once a lesson on it was completed, or a combined lesson on its whole stage
+    pub fn new(curriculum: Curriculum, completed: &[LessonRecord]) -> Self {
+        let lessons: Vec<&LessonRecord> = completed.iter().filter(|r| r.language == curriculum.language).collect();
+        let finished = |topic: &str| lessons.iter().any(|r| r.topic.trim().eq_ignore_ascii_case(topic.trim()));
+        let done = curriculum
+            .stages
+            .iter()
+            .map(|stage| {
+                let whole_stage = finished(&stage.combined_topic());
+                stage.topics.iter().map(|topic| whole_stage || finished(topic)).collect()
+            })
+            .collect();
+        Self { curriculum, done }
+    }
+
+    fn topics_done(&self) -> usize {
+        self.done.iter().flatten().filter(|&&d| d).count()
+    }
+
+    fn topics_total(&self) -> usize {
+        self.done.iter().map(Vec::len).sum()
+    }
+
+    fn stages(&self) -> impl Iterator<Item = (usize, &Stage, &[bool])> {
+        self.curriculum.stages.iter().zip(&self.done).enumerate().map(|(idx, (stage, done))| (idx, stage, done.as_slice()))
+    }
+
+    /// The roadmap as Markdown, with a checked box for each topic that's done
+    pub fn to_markdown(&self) -> String {
+        let language = self.curriculum.language.display_name();
+        let mut md = format!(
+            "# {} roadmap\n\n{} stages · {} topics · {} done\n",
+            language,
+            self.curriculum.total_stages(),
+            self.topics_total(),
+            self.topics_done()
+        );
+        for (idx, stage, done) in self.stages() {
+            md.push_str(&format!(
+                "\n## Stage {}: {}\n\n{}. {} · {} lessons\n\n",
+                idx + 1,
+                stage.name,
+                stage.description.trim_end_matches('.'),
+                stage.difficulty.display_name(),
+                stage.lesson_type.display_name()
+            ));
+            for (topic, &done) in stage.topics.iter().zip(done) {
+                md.push_str(&format!("- [{}] {}\n", if done { "x" } else { " " }, topic));
+            }
+        }
+        md
+    }
+
+    /// The roadmap for the terminal, with the topics that are done ticked off
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{} {}\n",
+            Colors::primary(&format!("{} roadmap", self.curriculum.language.display_name())).bold(),
+            Colors::muted(&format!("({}/{} topics done)", self.topics_done(), self.topics_total()))
+        );
+        for (idx, stage, done) in self.stages() {
+            let title = format!("Stage {}: {}", idx + 1, stage.name);
+            let title = if done.iter().all(|&d| d) { Colors::success(&title).bold() } else { Colors::primary(&title).bold() };
+            text.push_str(&format!(
+                "\n{} {} {}\n  {}\n",
+                title,
+                Colors::difficulty_badge(stage.difficulty),
+                Colors::muted(&format!("{} lessons", stage.lesson_type.display_name())),
+                Colors::muted(&stage.description)
+            ));
+            for (topic, &done) in stage.topics.iter().zip(done) {
+                let mark = if done { Colors::success(output::glyph("✓", "[x]")) } else { Colors::muted(output::glyph("○", "[ ]")) };
+                text.push_str(&format!("  {} {}\n", mark, topic));
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,221 @@
+use crate::cli::{colors::{Borders, Colors}, fs_error};
+use crate::config::Language;
+use crate::execution::{ComparisonMode, Executor, FileManager};
+use crate::lessons::HumanLessons;
+use crate::ollama::{ApiMode, Generator};
+use anyhow::Result;
+use colored::Colorize;
+
+// Exercise number used for the self-test file, so it never clashes with a lesson's files
+const SELF_TEST_EXERCISE: usize = 0;
+
+// Reads two integers from stdin and prints their sum
+const SELF_TEST_CASES: &[(&str, &str)] = &[("2 3", "5"), ("10 -4", "6"), ("0 0", "0")];
+
+/// End-to-end smoke test: writes a known-good solution for each language and
+/// grades it through the same file, compile, run and compare path as a lesson.
+pub struct SelfTest;
+
+impl SelfTest {
+    /// Runs every stage and prints PASS/FAIL for each. Returns the number of languages that failed.
+    pub fn run() -> usize {
+        println!("{}", Colors::primary("Self-Test").bold());
+        Borders::print_rule();
+
+        // Lessons fall back to built-in content without Ollama, so this only warns
+        Self::check_ollama();
+
+        let mut failures = 0;
+        for language in Language::ALL {
+            println!("\n{}", Colors::primary(language.display_name()).bold());
+            if !language.is_enabled() {
+                println!("  {} {}", Colors::label_info("SKIP"), Colors::muted("disabled in this configuration"));
+                continue;
+            }
+            if !Self::run_language(language) {
+                failures += 1;
+            }
+        }
+        failures
+    }
+
+    /// Prints whether Ollama (or the OpenAI-compatible backend) is running with the
+    /// model of every enabled language available, and returns it
+    pub fn check_ollama() -> bool {
+        let generator = Generator::new();
+        let api = generator.api().display_name();
+        let installed = match generator.installed_models() {
+            Ok(installed) => installed,
+            Err(e) => {
+                println!(
+                    "{} {}",
+                    Colors::label_warn("WARN"),
+                    Colors::warning(&format!("{} is not reachable, so generated lessons will use fallback content: {}", api, e))
+                );
+                return false;
+            }
+        };
+
+        // Languages can each have their own model; every one in use is checked once
+        let mut models: Vec<(String, Vec<Language>)> = Vec::new();
+        for language in Language::enabled() {
+            let model = generator.model_for(language);
+            match models.iter_mut().find(|(m, _)| *m == model) {
+                Some((_, languages)) => languages.push(language),
+                None => models.push((model, vec![language])),
+            }
+        }
+        let single = models.len() == 1;
+
+        let mut all_installed = true;
+        for (model, languages) in &models {
+            let used_by = if single {
+                String::new()
+            } else {
+                let names: Vec<&str> = languages.iter().map(|l| l.display_name()).collect();
+                format!(" (for {})", names.join(", "))
+            };
+            if Generator::is_installed(&installed, model) {
+                println!("{} {}: model {} is installed{}", Colors::label_pass("PASS"), api, model, used_by);
+                Self::print_model_info(&generator, model);
+                continue;
+            }
+            all_installed = false;
+            let fix = match generator.api() {
+                ApiMode::Ollama => format!("Run 'ollama pull {}'.", model),
+                ApiMode::OpenAi if installed.is_empty() => "Load a model in the server.".to_string(),
+                ApiMode::OpenAi => format!(
+                    "Set {} to one it serves: {}.",
+                    generator.model_var_in_effect(languages[0]),
+                    installed.join(", ")
+                ),
+            };
+            println!(
+                "{} {}",
+                Colors::label_warn("WARN"),
+                Colors::warning(&format!("{}: model {} is not installed{}. {}", api, model, used_by, fix))
+            );
+        }
+        all_installed
+    }
+
+    // Size, quantization and context window of the configured model, so the token
+    // limit can be chosen to fit; details Ollama doesn't report are left out
+    fn print_model_info(generator: &Generator, model: &str) {
+        let info = match generator.model_info(model) {
+            Ok(info) => info,
+            Err(e) => {
+                println!("  {} {}", Colors::label_info("INFO"), Colors::muted(&format!("Model details unavailable: {}", e)));
+                return;
+            }
+        };
+        let mut details = Vec::new();
+        if let Some(ref size) = info.parameter_size {
+            details.push(format!("{} parameters", size));
+        }
+        if let Some(ref quantization) = info.quantization {
+            details.push(format!("{} quantization", quantization));
+        }
+        let window = generator.context_window(&info);
+        let source = if info.num_ctx.is_some() { "num_ctx; " } else { "" };
+        match info.context_length {
+            Some(trained) if trained != window => {
+                details.push(format!("context window {} tokens ({}the model supports {})", window, source, trained))
+            }
+            _ => details.push(format!("context window {} tokens", window)),
+        }
+        if !details.is_empty() {
+            println!("  {} {}", Colors::label_info("INFO"), Colors::muted(&details.join(", ")));
+        }
+
+        if let Some(warning) = generator.context_warning(model, &info) {
+            println!(
+                "  {} {}",
+                Colors::label_warn("WARN"),
+                Colors::warning(&format!("{} Use a model with a larger context window or shorter lessons.", warning))
+            );
+        }
+    }
+
+    // Later stages depend on earlier ones, so stop at the first failure
+    fn run_language(language: Language) -> bool {
+        // Built-in lessons come without solutions to grade, so this only checks the
+        // first one loads with tests; grading runs on a known-good sum program below
+        let lesson = Self::stage("Built-in lessons load", || {
+            let (lesson, _) = HumanLessons::get_next_lesson(None, language)
+                .ok_or_else(|| anyhow::anyhow!("no lessons available"))?;
+            if lesson.content.exercises.iter().any(|e| e.test_cases.is_empty()) {
+                return Err(anyhow::anyhow!("an exercise has no test cases"));
+            }
+            Ok(format!("first lesson has {} exercise(s) with tests", lesson.content.exercises.len()))
+        });
+        if lesson.is_none() {
+            return false;
+        }
+
+        let file_path = match Self::stage_value("Exercise file", || {
+            let path = FileManager::create_exercise_file(&FileManager::work_dir().join("self-test"), &language, SELF_TEST_EXERCISE, None)?;
+            std::fs::write(&path, Self::reference_solution(language)).map_err(|e| fs_error::write_error(e, "Failed to write reference solution", &path))?;
+            Ok((path.display().to_string(), path))
+        }) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        let executor = Executor::new(language);
+        let passed = Self::stage("Toolchain", || {
+            executor.check_toolchain()?;
+            Ok("installed and working".to_string())
+        })
+        .is_some()
+            && Self::stage("Compile and run", || {
+                executor.execute(&file_path, Some(SELF_TEST_CASES[0].0), &[], None, None)?;
+                Ok("program ran".to_string())
+            })
+            .is_some()
+            && Self::stage("Grading a known-good program", || {
+                for (idx, (input, expected)) in SELF_TEST_CASES.iter().enumerate() {
+                    let result = executor.execute(&file_path, Some(input), &[], None, None)?;
+                    if !Executor::compare_output(&result.output, expected, ComparisonMode::Trim) {
+                        return Err(anyhow::anyhow!(
+                            "test {} expected {:?}, got {:?}",
+                            idx + 1,
+                            expected,
+                            result.output.trim()
+                        ));
+                    }
+                }
+                Ok(format!("{}/{} tests passed", SELF_TEST_CASES.len(), SELF_TEST_CASES.len()))
+            })
+            .is_some();
+
+        let _ = std::fs::remove_file(&file_path);
+        passed
+    }
+
+    fn stage(name: &str, check: impl FnOnce() -> Result<String>) -> Option<()> {
+        Self::stage_value(name, || check().map(|detail| (detail, ())))
+    }
+
+    fn stage_value<T>(name: &str, check: impl FnOnce() -> Result<(String, T)>) -> Option<T> {
+        match check() {
+            Ok((detail, value)) => {
+                println!("  {} {} {}", Colors::label_pass("PASS"), name, Colors::muted(&format!("({})", detail)));
+                Some(value)
+            }
+            Err(e) => {
+                println!("  {} {}", Colors::label_fail("FAIL"), name);
+                println!("    {}", Colors::error(&e.to_string()));
+                None
+            }
+        }
+    }
+
+    fn reference_solution(language: Language) -> &'static str {
+        match language {
+            Language::JavaScript => "const [a, b] = require('fs').readFileSync(0, 'utf-8').trim().split(/\\s+/).map(Number);\nconsole.log(a + b);\n",
+            Language::Cpp => "#include <iostream>\n\nint main() {\n    long long a, b;\n    std::cin >> a >> b;\n    std::cout << a + b << std::endl;\n    return 0;\n}\n",
+            Language::Rust => "use std::io::Read;\n\nfn main() {\n    let mut input = String::new();\n    std::io::stdin().read_to_string(&mut input).unwrap();\n    let sum: i64 = input.split_whitespace().map(|n| n.parse::<i64>().unwrap()).sum();\n    println!(\"{}\", sum);\n}\n",
+        }
+    }
+}
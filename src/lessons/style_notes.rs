@@ -0,0 +1,126 @@
+use crate::cli::colors::Colors;
+use std::path::Path;
+
+// Line numbers listed in a note before the rest are summed up
+const MAX_LISTED_LINES: usize = 5;
+
+/// Whether whitespace notes are shown after an exercise passes; on by default,
+/// CURSED_CODDY_STYLE_NOTES=false turns them off
+pub fn style_notes_enabled() -> bool {
+    std::env::var("CURSED_CODDY_STYLE_NOTES")
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+        .unwrap_or(true)
+}
+
+/// Whitespace problems in the learner's code that make for confusing diffs:
+/// indentation mixing tabs and spaces, and trailing whitespace. Purely
+/// informational, so it works the same for every language.
+pub fn style_notes(code: &str) -> Vec<String> {
+    let mut tab_indented = Vec::new();
+    let mut space_indented = Vec::new();
+    let mut trailing = Vec::new();
+
+    for (idx, line) in code.lines().enumerate() {
+        let line_number = idx + 1;
+        if line.ends_with([' ', '\t']) {
+            trailing.push(line_number);
+        }
+        // Whitespace-only lines have no indentation to speak of
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Each line counts once, by what its indentation starts with, so tabs
+        // followed by alignment spaces are a tab-indented line
+        if line.starts_with('\t') {
+            tab_indented.push(line_number);
+        } else if line.starts_with(' ') {
+            space_indented.push(line_number);
+        }
+    }
+
+    let mut notes = Vec::new();
+    if !tab_indented.is_empty() && !space_indented.is_empty() {
+        // Point at the less common style, which is most likely the accident
+        let (odd, odd_kind, usual_kind) = if tab_indented.len() <= space_indented.len() {
+            (&tab_indented, "tabs", "spaces")
+        } else {
+            (&space_indented, "spaces", "tabs")
+        };
+        notes.push(format!(
+            "Mixed tabs and spaces detected: {} on {}, {} elsewhere.",
+            odd_kind,
+            line_list(odd),
+            usual_kind
+        ));
+    }
+    if !trailing.is_empty() {
+        notes.push(format!("Trailing whitespace detected on {}.", line_list(&trailing)));
+    }
+    notes
+}
+
+/// Prints the style notes for the learner's file, if there are any and they're enabled
+pub fn print_style_notes(file_path: &Path) {
+    if !style_notes_enabled() {
+        return;
+    }
+    let Ok(code) = std::fs::read_to_string(file_path) else {
+        return;
+    };
+    let notes = style_notes(&code);
+    if notes.is_empty() {
+        return;
+    }
+    println!();
+    for note in &notes {
+        println!("{} {}", Colors::label_info("STYLE"), Colors::muted(note));
+    }
+    println!("{}", Colors::muted("Not graded; most editors can show whitespace or fix it on save."));
+}
+
+// "line 3", "lines 2, 5" or "lines 1, 2, 3, 4, 5 and 4 more"
+fn line_list(lines: &[usize]) -> String {
+    let listed: Vec<String> = lines.iter().take(MAX_LISTED_LINES).map(|n| n.to_string()).collect();
+    let label = if lines.len() == 1 { "line" } else { "lines" };
+    let more = lines.len().saturating_sub(MAX_LISTED_LINES);
+    if more > 0 {
+        format!("{} {} and {} more", label, listed.join(", "), more)
+    } else {
+        format!("{} {}", label, listed.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consistent_code_has_no_notes() {
+        assert!(style_notes("fn main() {\n    let x = 1;\n\n    println!(\"{}\", x);\n}\n").is_empty());
+        assert!(style_notes("int main() {\n\treturn 0;\n}\n").is_empty());
+    }
+
+    #[test]
+    fn mixed_indentation_points_at_the_less_common_style() {
+        let code = "fn main() {\n    let a = 1;\n\tlet b = 2;\n    let c = 3;\n}";
+        assert_eq!(style_notes(code), vec!["Mixed tabs and spaces detected: tabs on line 3, spaces elsewhere."]);
+
+        let code = "{\n\ta;\n\tb;\n  c;\n}";
+        assert_eq!(style_notes(code), vec!["Mixed tabs and spaces detected: spaces on line 4, tabs elsewhere."]);
+    }
+
+    #[test]
+    fn a_line_indented_with_tabs_then_spaces_counts_once() {
+        // Tabs to indent and spaces to line up a continued call is one style, not two
+        let code = "fn main() {\n\tlet total = add(1,\n\t              2);\n\tprintln!(\"{}\", total);\n}";
+        assert!(style_notes(code).is_empty());
+    }
+
+    #[test]
+    fn trailing_whitespace_is_listed_by_line() {
+        let code = "a \nb\t\nc\n   \n";
+        assert_eq!(style_notes(code), vec!["Trailing whitespace detected on lines 1, 2, 4."]);
+        let many = "x \n".repeat(7);
+        assert_eq!(style_notes(&many), vec!["Trailing whitespace detected on lines 1, 2, 3, 4, 5 and 2 more."]);
+    }
+}
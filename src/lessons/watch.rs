@@ -0,0 +1,71 @@
+use crate::cli::output::RawMode;
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+// How often the exercise file is checked for a save
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Editors often write a file in more than one step; wait for them to finish
+const SAVE_SETTLE: Duration = Duration::from_millis(150);
+
+/// What ended a wait in watch mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The exercise file was saved
+    Saved,
+    /// Enter: test without saving again
+    TestNow,
+    Hint,
+    Skip,
+    /// Leave the exercise unsolved and marked for review
+    Review,
+    /// q: pause the lesson to pick up later with 'continue'
+    Pause,
+    /// Esc or Ctrl+C: stop, like Ctrl+C at the prompt
+    Quit,
+}
+
+/// CURSED_CODDY_WATCH=true turns watch mode on without --watch
+pub fn watch_from_env() -> bool {
+    std::env::var("CURSED_CODDY_WATCH")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Waits until the file's modification time changes or the learner presses one
+/// of the watch keys. `allow_review` enables the key that marks the exercise for review.
+pub fn wait_for_save(file_path: &Path, allow_review: bool) -> Result<WatchEvent> {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let baseline: Option<SystemTime> = modified(file_path);
+    let _raw = RawMode::enable().context("Watch mode needs an interactive terminal")?;
+
+    loop {
+        if event::poll(POLL_INTERVAL).context("Failed to read the keyboard")? {
+            if let Event::Key(key) = event::read().context("Failed to read the keyboard")? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                match key.code {
+                    KeyCode::Char('c') if ctrl => return Ok(WatchEvent::Quit),
+                    KeyCode::Char('q') => return Ok(WatchEvent::Pause),
+                    KeyCode::Esc => return Ok(WatchEvent::Quit),
+                    KeyCode::Enter => return Ok(WatchEvent::TestNow),
+                    KeyCode::Char('h') => return Ok(WatchEvent::Hint),
+                    KeyCode::Char('s') => return Ok(WatchEvent::Skip),
+                    KeyCode::Char('r') if allow_review => return Ok(WatchEvent::Review),
+                    _ => {}
+                }
+            }
+        }
+
+        // A save that replaces the file can leave it missing for a moment
+        let current = modified(file_path);
+        if current.is_some() && current != baseline {
+            std::thread::sleep(SAVE_SETTLE);
+            return Ok(WatchEvent::Saved);
+        }
+    }
+}
@@ -5,9 +5,10 @@ mod lessons;
 mod ollama;
 mod progress;
 
-use anyhow::Result;
-
-fn main() -> Result<()> {
-    cli::banner::display_banner();
-    cli::commands::run()
+fn main() {
+    let result = cli::commands::run();
+    if let Err(ref e) = result {
+        eprintln!("Error: {:?}", e);
+    }
+    std::process::exit(cli::exit_code::for_result(&result));
 }
@@ -1,44 +1,1288 @@
+use crate::config::{Difficulty, Language, LessonType, MAX_EXERCISES};
+use crate::execution::{ComparisonMode, Executor};
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Field names are snake_case as the prompts ask, but models also answer in camelCase,
+// PascalCase or kebab-case; the aliases keep those fields from silently defaulting
+// to empty. Only casing variants are aliased: a synonym such as "tests" for
+// "test_cases" makes serde reject an answer that has both as a duplicate field.
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeExample {
+    #[serde(alias = "Code")]
     pub code: String,
+    #[serde(alias = "Explanation")]
     pub explanation: String,
+    /// Why the example failed the compile check of `start --verify-examples`;
+    /// None when it compiled or wasn't checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Exercise {
+    #[serde(alias = "Title")]
     pub title: String,
+    #[serde(alias = "Description")]
     pub description: String,
+    #[serde(alias = "Hints")]
     pub hints: Vec<String>,
+    /// An empty or whitespace-only example is stored as None
+    #[serde(
+        default,
+        deserialize_with = "deserialize_non_empty",
+        alias = "exampleInput",
+        alias = "ExampleInput",
+        alias = "example-input",
+    )]
     pub example_input: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_non_empty",
+        alias = "exampleOutput",
+        alias = "ExampleOutput",
+        alias = "example-output",
+    )]
     pub example_output: Option<String>,
+    /// Partial solution the exercise file starts with instead of the language template
+    #[serde(
+        default,
+        deserialize_with = "deserialize_non_empty",
+        alias = "starterCode",
+        alias = "StarterCode",
+        alias = "starter-code",
+    )]
+    pub starter_code: Option<String>,
+    /// Function exercises may leave these out; their cases become the tests
+    #[serde(default, alias = "testCases", alias = "TestCases", alias = "test-cases")]
     pub test_cases: Vec<TestCase>,
+    /// Passing also takes code that compiles without warnings (Rust and C++)
+    #[serde(default, alias = "requireClean", alias = "RequireClean", alias = "require-clean")]
+    pub require_clean: bool,
+    /// A passing solution prints nothing; the tests check its exit code or the file it writes
+    #[serde(default, alias = "printsNothing", alias = "PrintsNothing", alias = "prints-nothing")]
+    pub prints_nothing: bool,
+    /// For Rust: only this function is written, and the program around it is generated
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "Function")]
+    pub function: Option<FunctionSpec>,
+}
+
+/// A Rust exercise where the learner writes a single function. The file starts
+/// as a stub of it, and a generated main calls it once per case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionSpec {
+    /// e.g. "fn fib(n: u32) -> u32"
+    #[serde(alias = "Signature")]
+    pub signature: String,
+    #[serde(alias = "Cases")]
+    pub cases: Vec<FunctionCase>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCase {
+    /// A call to the function, e.g. "fib(10)"
+    #[serde(alias = "Call")]
+    pub call: String,
+    /// What {:?} prints for the right result, e.g. "55", "\"hi\"" or "[1, 2]"
+    #[serde(alias = "Expected")]
+    pub expected: String,
+}
+
+impl Exercise {
+    /// Reads every test's `input_file`, relative to `base_dir`, into its input.
+    /// Paths that would leave `base_dir` (absolute, or going up with "..") are refused.
+    pub fn load_input_files(&mut self, base_dir: &Path) -> anyhow::Result<()> {
+        for (idx, test) in self.test_cases.iter_mut().enumerate() {
+            let Some(name) = test.input_file.take() else { continue };
+            let inside = Path::new(&name)
+                .components()
+                .all(|part| matches!(part, std::path::Component::Normal(_) | std::path::Component::CurDir));
+            if !inside {
+                anyhow::bail!(
+                    "Test {} of \"{}\" reads its input from {}, which is outside the lesson's folder",
+                    idx + 1,
+                    self.title,
+                    name
+                );
+            }
+            let path = base_dir.join(&name);
+            if !path.is_file() {
+                anyhow::bail!(
+                    "Test {} of \"{}\" reads its input from {}, but {} doesn't exist",
+                    idx + 1,
+                    self.title,
+                    name,
+                    path.display()
+                );
+            }
+            test.input = std::fs::read_to_string(&path).with_context(|| {
+                format!("Failed to read input file {} for test {} of \"{}\"", path.display(), idx + 1, self.title)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The exercise graded with the test cases in `path` (a JSON list, in the same
+    /// format as a lesson file's test_cases) instead of its own, or None if there's
+    /// no such file. Input files are read relative to it.
+    pub fn with_test_override(&self, path: &Path) -> anyhow::Result<Option<Exercise>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let test_cases: Vec<TestCase> =
+            serde_json::from_str(&json).with_context(|| format!("{} isn't a JSON list of test cases", path.display()))?;
+        if test_cases.is_empty() {
+            anyhow::bail!("{} has no test cases", path.display());
+        }
+        let mut exercise = Exercise { test_cases, ..self.clone() };
+        exercise.load_input_files(path.parent().unwrap_or(Path::new(".")))?;
+        Ok(Some(exercise))
+    }
+
+    /// Gives a function exercise without tests one per case. The test passes the
+    /// case's number to the generated main, which prints what its call returns.
+    pub fn add_function_tests(&mut self) {
+        let Some(ref function) = self.function else { return };
+        if !self.test_cases.is_empty() {
+            return;
+        }
+        self.test_cases = function
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(idx, case)| TestCase {
+                input: String::new(),
+                input_file: None,
+                output: case.expected.clone(),
+                label: Some(case.call.clone()),
+                args: vec![idx.to_string()],
+                output_pattern: None,
+                expected_exit_code: None,
+                expected_file: None,
+                comparison: None,
+            })
+            .collect();
+    }
+
+    /// Whether the exercise shows an example input
+    pub fn has_input(&self) -> bool {
+        self.example_input.as_deref().is_some_and(|s| !s.trim().is_empty())
+    }
+
+    /// Whether the exercise shows an example output
+    pub fn has_output(&self) -> bool {
+        self.example_output.as_deref().is_some_and(|s| !s.trim().is_empty())
+    }
+
+    /// Whether the program has to read stdin. The test cases and example input
+    /// decide this, not the description, which may well say "don't read input".
+    pub fn reads_input(&self) -> bool {
+        self.has_input() || self.test_cases.iter().any(|t| !t.input.trim().is_empty())
+    }
+
+    /// The exercise says it prints nothing, so it's graded on its exit code or the
+    /// file it writes. Tests without an expected output don't make it so on their
+    /// own: the output is more often missing than meant to be empty.
+    pub fn expects_no_output(&self) -> bool {
+        self.prints_nothing
+    }
+
+    /// A no-input exercise whose output depends on randomness, so a fixed expected
+    /// output can never pass reliably
+    pub fn is_random_without_input(&self) -> bool {
+        self.description.to_lowercase().contains("random")
+            && self.test_cases.iter().all(|t| t.input.trim().is_empty() && t.args.is_empty())
+    }
+
+    /// Neither the description nor the example says what the program prints, which
+    /// leaves stdout grading guessing (common for exercises that only define types)
+    pub fn lacks_observable_output(&self) -> bool {
+        !self.has_output() || !mentions_output(&self.description)
+    }
+
+    /// The same exercise reworded: nearly the same description, or the same tests
+    /// with a description that's still mostly the same. Short descriptions that
+    /// differ in one key word ("sum" vs "product") only count if the tests match,
+    /// and so do long ones, whose few different words can be the whole task, once
+    /// both exercises have tests that expect different things.
+    pub fn is_near_duplicate_of(&self, other: &Exercise) -> bool {
+        let similarity = word_overlap(&self.description, &other.description);
+        let tests_differ = !self.test_cases.is_empty() && !other.test_cases.is_empty() && !self.same_tests_as(other);
+        (similarity >= DUPLICATE_DESCRIPTION_OVERLAP && !tests_differ)
+            || (similarity >= DUPLICATE_TESTS_DESCRIPTION_OVERLAP && self.same_tests_as(other))
+    }
+
+    fn same_tests_as(&self, other: &Exercise) -> bool {
+        let key = |t: &TestCase| (t.input.trim().to_string(), t.output.trim().to_string(), t.args.clone());
+        !self.test_cases.is_empty()
+            && self.test_cases.len() == other.test_cases.len()
+            && self.test_cases.iter().map(key).eq(other.test_cases.iter().map(key))
+    }
+}
+
+// Words that show an exercise description asks for something stdout grading can see
+const OUTPUT_WORDS: &[&str] = &["print", "output", "display", "show", "log", "cout", "write", "writing", "wrote", "written"];
+
+// Endings an output word may have ("prints", "logged", "println", "printf", "shown"),
+// matched as whole words so "logic" or "showcase" don't count
+const OUTPUT_WORD_ENDINGS: &[&str] = &["", "s", "ed", "ing", "ged", "ging", "ln", "f", "n"];
+
+fn mentions_output(description: &str) -> bool {
+    description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| {
+            OUTPUT_WORDS.iter().any(|stem| {
+                word.strip_prefix(stem).is_some_and(|ending| OUTPUT_WORD_ENDINGS.contains(&ending))
+            })
+        })
+}
+
+// Share of description words two exercises need in common to count as duplicates,
+// and the lower share that's enough when their tests are identical too
+const DUPLICATE_DESCRIPTION_OVERLAP: f64 = 0.9;
+const DUPLICATE_TESTS_DESCRIPTION_OVERLAP: f64 = 0.5;
+
+// Jaccard similarity of the lowercased words of two texts
+fn word_overlap(a: &str, b: &str) -> f64 {
+    let words = |text: &str| -> std::collections::HashSet<String> {
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Drops exercises that repeat an earlier one with trivial wording changes and
+/// returns the titles of the ones removed
+pub fn remove_duplicate_exercises(exercises: &mut Vec<Exercise>) -> Vec<String> {
+    let mut kept: Vec<Exercise> = Vec::with_capacity(exercises.len());
+    let mut removed = Vec::new();
+    for exercise in exercises.drain(..) {
+        if kept.iter().any(|k| exercise.is_near_duplicate_of(k)) {
+            removed.push(exercise.title);
+        } else {
+            kept.push(exercise);
+        }
+    }
+    *exercises = kept;
+    removed
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizQuestion {
+    #[serde(alias = "Question")]
+    pub question: String,
+    #[serde(alias = "Options")]
+    pub options: Vec<String>,
+    /// Index of the right option
+    #[serde(deserialize_with = "deserialize_option_index", alias = "Correct")]
+    pub correct: usize,
+    #[serde(default, alias = "Explanation")]
+    pub explanation: String,
+}
+
+impl QuizQuestion {
+    /// At least two options, and the correct one is among them
+    pub fn is_valid(&self) -> bool {
+        !self.question.trim().is_empty() && self.options.len() >= 2 && self.correct < self.options.len()
+    }
+}
+
+// A malformed quiz shouldn't cost the whole lesson, so questions that don't parse are dropped
+fn deserialize_quiz<'de, D>(deserializer: D) -> Result<Vec<QuizQuestion>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = Option::<Vec<serde_json::Value>>::deserialize(deserializer)?.unwrap_or_default();
+    Ok(values
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect())
+}
+
+// Models give the correct option as 1, "1" or "B"; all are read as a 0-based index
+// (numbers as given, letters from A)
+fn deserialize_option_index<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Index {
+        Number(usize),
+        Text(String),
+    }
+
+    match Index::deserialize(deserializer)? {
+        Index::Number(n) => Ok(n),
+        Index::Text(text) => {
+            let text = text.trim();
+            if let Ok(n) = text.parse::<usize>() {
+                return Ok(n);
+            }
+            match text.chars().next() {
+                Some(c) if c.is_ascii_alphabetic() && text.len() == 1 => Ok((c.to_ascii_uppercase() as u8 - b'A') as usize),
+                _ => Err(serde::de::Error::custom(format!("'{}' is not an option index", text))),
+            }
+        }
+    }
+}
+
+// Models use both null and "" for "no example"; collapse them into None
+fn deserialize_non_empty<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|s| !s.trim().is_empty()))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TestCase {
+    #[serde(default, alias = "Input")]
     pub input: String,
+    /// File whose contents are piped to stdin instead of `input`, relative to the
+    /// lesson file. It's read into `input` when the lesson is loaded.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "inputFile",
+        alias = "InputFile",
+        alias = "input-file"
+    )]
+    pub input_file: Option<String>,
+    #[serde(alias = "Output")]
     pub output: String,
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "Label")]
+    pub label: Option<String>,
+    /// Command-line arguments passed to the program (for exercises that read argv)
+    #[serde(default, skip_serializing_if = "Vec::is_empty", alias = "Args")]
+    pub args: Vec<String>,
+    /// Regex the whole output must match, graded instead of `output` when the
+    /// output can't be fixed in advance (e.g. it includes a random number)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "outputPattern",
+        alias = "OutputPattern",
+        alias = "output-pattern"
+    )]
+    pub output_pattern: Option<String>,
+    /// Exit code the program must end with, for exercises such as "exit with code 1
+    /// on invalid input" (None means it must exit successfully)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "expectedExitCode",
+        alias = "ExpectedExitCode",
+        alias = "expected-exit-code"
+    )]
+    pub expected_exit_code: Option<i32>,
+    /// A file the program must write in its working directory, for exercises such
+    /// as "write the results to output.txt"
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "expectedFile",
+        alias = "ExpectedFile",
+        alias = "expected-file"
+    )]
+    pub expected_file: Option<ExpectedFile>,
+    /// How the output is compared; detected from the expected output when not given
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "Comparison")]
+    pub comparison: Option<ComparisonMode>,
+}
+
+/// A file a test expects the program to leave behind, and what it should contain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedFile {
+    #[serde(alias = "Name")]
+    pub name: String,
+    #[serde(alias = "Contents")]
+    pub contents: String,
+}
+
+impl ExpectedFile {
+    /// Only a plain file name is allowed, so a test can't read outside the run directory
+    pub fn has_valid_name(&self) -> bool {
+        let path = std::path::Path::new(&self.name);
+        path.file_name().is_some_and(|name| name == path.as_os_str())
+    }
+}
+
+impl TestCase {
+    /// Returns the label formatted for display after a test number, e.g. " (handles zero)"
+    pub fn label_suffix(&self) -> String {
+        self.label
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| format!(" ({})", l))
+            .unwrap_or_default()
+    }
+
+    /// Whether the program's output passes this test. An output_pattern that isn't a
+    /// valid regex is ignored so the test falls back to the exact expected output.
+    pub fn passes(&self, actual: &str) -> bool {
+        match self.output_regex() {
+            Some(regex) => regex.is_match(actual.trim()),
+            None => Executor::compare_output(actual, &self.output, self.comparison_mode(&self.output)),
+        }
+    }
+
+    /// Whether the test gives no output to compare against
+    pub fn has_no_output(&self) -> bool {
+        self.output.trim().is_empty() && self.output_pattern.is_none()
+    }
+
+    /// Whether the file the program wrote (None if it wrote none) is what this test
+    /// expects; always true for tests that don't check a file
+    pub fn file_matches(&self, actual: Option<&str>) -> bool {
+        match self.expected_file {
+            Some(ref expected) => {
+                actual.is_some_and(|a| Executor::compare_output(a, &expected.contents, self.comparison_mode(&expected.contents)))
+            }
+            None => true,
+        }
+    }
+
+    // The mode the test asks for, or the one its expected text calls for
+    fn comparison_mode(&self, expected: &str) -> ComparisonMode {
+        self.comparison.unwrap_or_else(|| ComparisonMode::detect(expected))
+    }
+
+    /// Whether the program exited the way this test expects (successfully by default)
+    pub fn exit_code_matches(&self, exit_code: Option<i32>) -> bool {
+        exit_code == Some(self.expected_exit_code.unwrap_or(0))
+    }
+
+    /// The expected output as shown to the learner
+    pub fn expected_display(&self) -> String {
+        let output = match self.output_regex() {
+            Some(_) => format!("{} (pattern, e.g. {})", self.output_pattern.as_deref().unwrap_or_default(), self.output),
+            None if self.has_no_output() => "(no output)".to_string(),
+            None => self.output.clone(),
+        };
+        let output = match self.expected_exit_code.filter(|&code| code != 0) {
+            Some(code) => format!("{} (then exit with code {})", output, code),
+            None => output,
+        };
+        match self.expected_file {
+            Some(ref file) => format!("{}\nand write {} containing:\n{}", output, file.name, file.contents),
+            None => output,
+        }
+    }
+
+    fn output_regex(&self) -> Option<regex::Regex> {
+        let pattern = self.output_pattern.as_deref()?;
+        regex::Regex::new(&format!("^(?:{})$", pattern)).ok()
+    }
+}
+
+/// Output pattern for an exercise that prints random numbers: the example output with
+/// every number replaced by a number matcher. None if the output has no numbers to vary.
+pub fn random_output_pattern(expected: &str) -> Option<String> {
+    let expected = expected.trim();
+    let numbers = regex::Regex::new(r"-?\d+(\.\d+)?").expect("valid number regex");
+    if !numbers.is_match(expected) {
+        return None;
+    }
+
+    let mut pattern = String::new();
+    let mut last = 0;
+    for number in numbers.find_iter(expected) {
+        pattern.push_str(&regex::escape(&expected[last..number.start()]));
+        pattern.push_str(r"-?\d+(\.\d+)?");
+        last = number.end();
+    }
+    pattern.push_str(&regex::escape(&expected[last..]));
+    Some(pattern)
+}
+
+/// The first string field named `key` in JSON that may still be arriving (such as a
+/// streamed response): its decoded text so far and whether the string is complete.
+/// None until the field's opening quote has arrived.
+pub fn partial_string_field(json: &str, key: &str) -> Option<(String, bool)> {
+    let quoted_key = format!("\"{}\"", key);
+    let after_key = &json[json.find(&quoted_key)? + quoted_key.len()..];
+    let value = after_key.trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some((text, true)),
+            '\\' => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some('r') => text.push('\r'),
+                Some('b') | Some('f') => {}
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if hex.len() < 4 {
+                        // The escape is cut off; the rest of it comes in a later chunk
+                        return Some((text, false));
+                    }
+                    let decoded = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                    text.push(decoded.unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                Some(escaped) => text.push(escaped),
+                None => return Some((text, false)),
+            },
+            c => text.push(c),
+        }
+    }
+    Some((text, false))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneratedContent {
+    #[serde(alias = "Concept")]
     pub concept: String,
-    #[serde(default)]
+    #[serde(default, alias = "stepByStep", alias = "StepByStep", alias = "step-by-step")]
     pub step_by_step: Vec<String>,
-    #[serde(default)]
+    #[serde(default, alias = "codeExamples", alias = "CodeExamples", alias = "code-examples")]
     pub code_examples: Vec<CodeExample>,
-    #[serde(default)]
+    #[serde(default, alias = "syntaxGuide", alias = "SyntaxGuide", alias = "syntax-guide")]
     pub syntax_guide: String,
-    #[serde(default)]
+    #[serde(default, alias = "commonPatterns", alias = "CommonPatterns", alias = "common-patterns")]
     pub common_patterns: Vec<String>,
-    #[serde(default)]
+    #[serde(default, alias = "Exercises")]
     pub exercises: Vec<Exercise>,
+    /// Multiple-choice questions asked after the exercises
+    #[serde(default, alias = "Quiz", deserialize_with = "deserialize_quiz")]
+    pub quiz: Vec<QuizQuestion>,
+    /// True when generation failed and the lesson was assembled from fallback content
+    #[serde(default)]
+    pub is_fallback: bool,
+    /// Why generation fell back, shown with the lesson so the learner knows whether to regenerate
+    #[serde(default)]
+    pub fallback_reason: Option<String>,
+    /// The model that generated the lesson; none for built-in and hand-written lessons
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// A quick refresher on a topic from `explain`: the concept and one example,
+/// without the rest of a lesson
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicReference {
+    #[serde(alias = "Concept")]
+    pub concept: String,
+    #[serde(alias = "Example")]
+    pub example: CodeExample,
+    /// The model that wrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+// Longest text kept in one section, step or exercise description; a model that
+// rambles on past this is cut off
+const DEFAULT_MAX_SECTION_CHARS: usize = 8000;
+
+// Most steps, patterns, code examples, hints or quiz questions a lesson keeps
+const MAX_SECTION_ITEMS: usize = 20;
+
+// Content this large (as JSON) is refused outright; no amount of cutting makes it a lesson
+const MAX_LESSON_BYTES: usize = 1_000_000;
+
+/// Marks where an overlong section was cut
+pub const TRUNCATED_NOTE: &str = "[content truncated]";
+
+/// Longest text kept per lesson section; CURSED_CODDY_MAX_SECTION_CHARS changes it
+pub fn max_section_chars() -> usize {
+    std::env::var("CURSED_CODDY_MAX_SECTION_CHARS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_SECTION_CHARS)
+}
+
+// Cuts text longer than `max` characters so that, with the note appended, it's
+// `max` at most; returns whether anything was cut
+fn cut_text(text: &mut String, max: usize, note: &str) -> bool {
+    if text.chars().count() <= max {
+        return false;
+    }
+    let keep = max.saturating_sub(note.chars().count());
+    let end = text.char_indices().nth(keep).map_or(text.len(), |(end, _)| end);
+    text.truncate(end);
+    text.truncate(text.trim_end().len());
+    text.push_str(note);
+    true
+}
+
+// Cuts every text in the list; returns whether any was cut
+fn cut_each(texts: &mut [String], max: usize, note: &str) -> bool {
+    let mut any = false;
+    for text in texts.iter_mut() {
+        any |= cut_text(text, max, note);
+    }
+    any
+}
+
+// Drops items past the cap; returns how many were dropped
+fn cut_list<T>(items: &mut Vec<T>, max: usize) -> usize {
+    let extra = items.len().saturating_sub(max);
+    items.truncate(max);
+    extra
+}
+
+// A syntax guide shorter than this is a placeholder rather than a guide
+const MIN_SYNTAX_GUIDE_LENGTH: usize = 40;
+
+/// Whether a syntax guide is missing or too short to be more than a placeholder
+pub fn is_stub_syntax_guide(guide: &str) -> bool {
+    guide.trim().len() < MIN_SYNTAX_GUIDE_LENGTH
+}
+
+/// A lesson section that came back empty or too thin to help, which can be asked
+/// for again on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeakSection {
+    StepByStep,
+    SyntaxGuide,
+    CommonPatterns,
+}
+
+impl WeakSection {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            WeakSection::StepByStep => "step-by-step explanation",
+            WeakSection::SyntaxGuide => "syntax guide",
+            WeakSection::CommonPatterns => "common patterns",
+        }
+    }
+
+    // How validate reports it
+    fn problem(&self) -> &'static str {
+        match self {
+            WeakSection::StepByStep => "no step-by-step explanation",
+            WeakSection::SyntaxGuide => "syntax guide is missing or a stub",
+            WeakSection::CommonPatterns => "no common patterns",
+        }
+    }
 }
 
 impl GeneratedContent {
     pub fn from_json(json_str: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(json_str)
     }
+
+    /// Reads every test's `input_file`, relative to `base_dir`, into its input, so
+    /// the lesson runs the same once it's cached. Fails on a file that is missing
+    /// or isn't text.
+    pub fn load_input_files(&mut self, base_dir: &Path) -> anyhow::Result<()> {
+        for exercise in &mut self.exercises {
+            exercise.load_input_files(base_dir)?;
+        }
+        Ok(())
+    }
+
+    /// The sections that are missing or too thin, each of which can be generated on its own
+    pub fn weak_sections(&self) -> Vec<WeakSection> {
+        let mut weak = Vec::new();
+        if self.step_by_step.iter().all(|step| step.trim().is_empty()) {
+            weak.push(WeakSection::StepByStep);
+        }
+        if is_stub_syntax_guide(&self.syntax_guide) {
+            weak.push(WeakSection::SyntaxGuide);
+        }
+        if self.common_patterns.iter().all(|pattern| pattern.trim().is_empty()) {
+            weak.push(WeakSection::CommonPatterns);
+        }
+        weak
+    }
+
+    /// Refuses content too large to be a lesson, such as a model that repeated
+    /// itself until it ran out of tokens, before any of it is shown
+    pub fn check_size(&self) -> anyhow::Result<()> {
+        let size = serde_json::to_string(self).map(|json| json.len()).unwrap_or(0);
+        if size > MAX_LESSON_BYTES {
+            return Err(anyhow::anyhow!(
+                "This lesson is too large to show ({} KB; the limit is {} KB). The model's output probably ran away; generate the lesson again.",
+                size / 1000,
+                MAX_LESSON_BYTES / 1000
+            ));
+        }
+        Ok(())
+    }
+
+    /// Cuts runaway model output down to a size that can be shown: at most
+    /// `max_exercises` exercises, a limited number of steps, examples, patterns,
+    /// hints and quiz questions, and every text at most max_section_chars() long,
+    /// ending in a note that it was cut. Returns one message per thing cut.
+    pub fn apply_size_limits(&mut self, max_exercises: usize) -> Vec<String> {
+        let max_chars = max_section_chars();
+        let prose_note = format!(" {}", TRUNCATED_NOTE);
+        let code_note = format!("\n// {}", TRUNCATED_NOTE);
+        let mut cut = Vec::new();
+
+        let extra = cut_list(&mut self.exercises, max_exercises.max(1));
+        if extra > 0 {
+            cut.push(format!("{} exercise(s) beyond the first {}", extra, max_exercises.max(1)));
+        }
+        for (name, extra) in [
+            ("step(s)", cut_list(&mut self.step_by_step, MAX_SECTION_ITEMS)),
+            ("common pattern(s)", cut_list(&mut self.common_patterns, MAX_SECTION_ITEMS)),
+            ("code example(s)", cut_list(&mut self.code_examples, MAX_SECTION_ITEMS)),
+            ("quiz question(s)", cut_list(&mut self.quiz, MAX_SECTION_ITEMS)),
+        ] {
+            if extra > 0 {
+                cut.push(format!("{} {} beyond the first {}", extra, name, MAX_SECTION_ITEMS));
+            }
+        }
+
+        if cut_text(&mut self.concept, max_chars, &prose_note) {
+            cut.push("the concept".to_string());
+        }
+        if cut_text(&mut self.syntax_guide, max_chars, &prose_note) {
+            cut.push("the syntax guide".to_string());
+        }
+        if cut_each(&mut self.step_by_step, max_chars, &prose_note) {
+            cut.push("a step-by-step explanation".to_string());
+        }
+        if cut_each(&mut self.common_patterns, max_chars, &prose_note) {
+            cut.push("a common pattern".to_string());
+        }
+        for (idx, example) in self.code_examples.iter_mut().enumerate() {
+            let code = cut_text(&mut example.code, max_chars, &code_note);
+            if cut_text(&mut example.explanation, max_chars, &prose_note) || code {
+                cut.push(format!("code example {}", idx + 1));
+            }
+        }
+        for exercise in self.exercises.iter_mut() {
+            let hints = cut_list(&mut exercise.hints, MAX_SECTION_ITEMS) > 0;
+            let hint_text = cut_each(&mut exercise.hints, max_chars, &prose_note);
+            if cut_text(&mut exercise.description, max_chars, &prose_note) || hints || hint_text {
+                cut.push(format!("exercise '{}'", exercise.title));
+            }
+        }
+        cut
+    }
+
+    /// Checks the lesson for missing or empty sections and returns one message per problem
+    pub fn validate(&self, language: Language) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.concept.trim().is_empty() {
+            problems.push("concept is empty".to_string());
+        }
+        problems.extend(self.weak_sections().iter().map(|section| section.problem().to_string()));
+        if self.code_examples.len() < 2 {
+            problems.push(format!("only {} code example(s)", self.code_examples.len()));
+        }
+        if self.code_examples.iter().any(|e| e.code.trim().is_empty()) {
+            problems.push("a code example has no code".to_string());
+        }
+        if self.exercises.is_empty() {
+            problems.push("no exercises".to_string());
+        }
+        for (i, exercise) in self.exercises.iter().enumerate() {
+            if let Some(earlier) = self.exercises[..i].iter().find(|e| exercise.is_near_duplicate_of(e)) {
+                problems.push(format!("exercise '{}' repeats exercise '{}'", exercise.title, earlier.title));
+            }
+            if exercise.description.trim().is_empty() {
+                problems.push(format!("exercise '{}' has no description", exercise.title));
+            }
+            // Only Rust builds a program around a function, so elsewhere it needs its own tests
+            let function = exercise.function.as_ref().filter(|_| language == Language::Rust);
+            if function.is_some_and(|f| f.cases.is_empty()) {
+                problems.push(format!("exercise '{}' has a function but no cases to call it with", exercise.title));
+            } else if exercise.test_cases.is_empty() && function.is_none() {
+                problems.push(format!("exercise '{}' has no test cases", exercise.title));
+            } else if !exercise.expects_no_output() && exercise.test_cases.iter().any(TestCase::has_no_output) {
+                problems.push(format!("exercise '{}' has a test case with no expected output", exercise.title));
+            } else if exercise.expects_no_output() && !exercise.test_cases.iter().all(TestCase::has_no_output) {
+                problems.push(format!("exercise '{}' prints nothing, but a test expects output", exercise.title));
+            }
+            if exercise.is_random_without_input() && exercise.test_cases.iter().any(|t| t.output_pattern.is_none()) {
+                problems.push(format!("exercise '{}' expects a fixed output from random values", exercise.title));
+            }
+            if exercise.test_cases.iter().any(|t| t.expected_file.as_ref().is_some_and(|f| !f.has_valid_name())) {
+                problems.push(format!("exercise '{}' expects a file outside the program's directory", exercise.title));
+            }
+            if exercise.test_cases.iter().any(|t| t.output_pattern.is_some() && t.output_regex().is_none()) {
+                problems.push(format!("exercise '{}' has an invalid output pattern", exercise.title));
+            }
+        }
+
+        if self.quiz.iter().any(|q| !q.is_valid()) {
+            problems.push("a quiz question has too few options or no valid answer".to_string());
+        }
+        if let Err(e) = self.check_size() {
+            problems.push(e.to_string());
+        }
+        problems.extend(self.clone().apply_size_limits(MAX_EXERCISES).into_iter().map(|part| format!("will be cut down: {}", part)));
+
+        problems
+    }
+}
+
+/// Version of the lesson pack format; bump it when older readers couldn't load a pack
+pub const LESSON_PACK_VERSION: u32 = 1;
+
+/// What a lesson pack is about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonPackMeta {
+    pub language: Language,
+    pub difficulty: Difficulty,
+    pub lesson_type: LessonType,
+    pub topic: String,
+    /// Unix timestamp of the export
+    pub created_at: String,
+}
+
+/// A portable, versioned lesson (a .coddy.json file) that can be shared and played
+/// without generating anything. Unlike the internal caches, its format is kept stable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonPack {
+    pub schema_version: u32,
+    pub meta: LessonPackMeta,
+    pub content: GeneratedContent,
+}
+
+impl LessonPack {
+    pub fn new(meta: LessonPackMeta, content: GeneratedContent) -> Self {
+        Self { schema_version: LESSON_PACK_VERSION, meta, content }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json_str: &str) -> anyhow::Result<Self> {
+        let pack: Self = serde_json::from_str(json_str)?;
+        if pack.schema_version > LESSON_PACK_VERSION {
+            anyhow::bail!(
+                "This lesson pack uses format version {}, but this version of cursed-coddy only reads up to {}. Update cursed-coddy to play it.",
+                pack.schema_version,
+                LESSON_PACK_VERSION
+            );
+        }
+        Ok(pack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_case(output: &str, output_pattern: Option<&str>) -> TestCase {
+        TestCase {
+            input: String::new(),
+            output: output.to_string(),
+            label: None,
+            args: vec![],
+            output_pattern: output_pattern.map(str::to_string),
+            expected_exit_code: None,
+            expected_file: None,
+            input_file: None,
+            comparison: None,
+        }
+    }
+
+    #[test]
+    fn output_pattern_matches_variable_output() {
+        let test = test_case("Random number: 42", Some(r"Random number: \d+"));
+
+        assert!(test.passes("Random number: 7\n"));
+        assert!(test.passes("Random number: 1234"));
+    }
+
+    #[test]
+    fn output_pattern_rejects_output_with_wrong_structure() {
+        let test = test_case("Random number: 42", Some(r"Random number: \d+"));
+
+        assert!(!test.passes("Random number: seven"));
+        assert!(!test.passes("Your number: 7"));
+        // The pattern has to match the whole output, not just part of it
+        assert!(!test.passes("Random number: 7\nRandom number: 8"));
+    }
+
+    #[test]
+    fn indented_expected_output_keeps_its_indentation() {
+        let test = test_case("  *\n ***\n*****", None);
+
+        assert!(test.passes("  *\n ***\n*****\n"));
+        // Whitespace at the end of a line doesn't count
+        assert!(test.passes("  *  \n ***\n*****"));
+        assert!(!test.passes("*\n***\n*****"));
+    }
+
+    #[test]
+    fn unindented_expected_output_ignores_surrounding_whitespace() {
+        let mut test = test_case("Hello", None);
+        assert!(test.passes("  Hello\n"));
+
+        test.comparison = Some(ComparisonMode::PreserveLeading);
+        assert!(!test.passes("  Hello\n"));
+    }
+
+    #[test]
+    fn invalid_output_pattern_falls_back_to_exact_output() {
+        let test = test_case("Random number: 42", Some("Random number: ("));
+
+        assert!(test.passes("Random number: 42"));
+        assert!(!test.passes("Random number: 7"));
+    }
+
+    #[test]
+    fn camel_case_fields_are_accepted() {
+        let json = r#"{
+            "concept": "Loops repeat code",
+            "stepByStep": ["Write the loop", "Run it"],
+            "codeExamples": [{"code": "for i in 0..3 {}", "explanation": "Counts to three"}],
+            "syntaxGuide": "for x in iter { }",
+            "commonPatterns": ["Iterating a range"],
+            "exercises": [{
+                "title": "Count",
+                "description": "Print 1 to 3",
+                "hints": ["Use a range"],
+                "exampleInput": "3",
+                "exampleOutput": "1 2 3",
+                "testCases": [{
+                    "input": "3",
+                    "Output": "1 2 3",
+                    "label": "counts up",
+                    "outputPattern": "1 2 \\d"
+                }]
+            }]
+        }"#;
+
+        let content = GeneratedContent::from_json(json).unwrap();
+
+        assert_eq!(content.step_by_step, vec!["Write the loop", "Run it"]);
+        assert_eq!(content.code_examples.len(), 1);
+        assert_eq!(content.code_examples[0].explanation, "Counts to three");
+        assert_eq!(content.syntax_guide, "for x in iter { }");
+        assert_eq!(content.common_patterns, vec!["Iterating a range"]);
+
+        let exercise = &content.exercises[0];
+        assert_eq!(exercise.example_input.as_deref(), Some("3"));
+        assert_eq!(exercise.example_output.as_deref(), Some("1 2 3"));
+        assert_eq!(exercise.test_cases.len(), 1);
+        assert_eq!(exercise.test_cases[0].output, "1 2 3");
+        assert_eq!(exercise.test_cases[0].label.as_deref(), Some("counts up"));
+        assert_eq!(exercise.test_cases[0].output_pattern.as_deref(), Some(r"1 2 \d"));
+
+        // Every casing of the multi-word fields, and PascalCase for the quiz answer
+        let casings = [
+            ["starterCode", "requireClean", "printsNothing", "inputFile", "expectedExitCode", "expectedFile"],
+            ["StarterCode", "RequireClean", "PrintsNothing", "InputFile", "ExpectedExitCode", "ExpectedFile"],
+            ["starter-code", "require-clean", "prints-nothing", "input-file", "expected-exit-code", "expected-file"],
+        ];
+        for [starter, clean, silent, input_file, exit_code, file] in casings {
+            let json = format!(
+                r#"{{"concept": "c",
+                    "exercises": [{{"title": "t", "description": "d", "hints": [],
+                        "{}": "fn main() {{}}", "{}": true, "{}": true,
+                        "test_cases": [{{"output": "", "{}": "in.txt", "{}": 2,
+                            "{}": {{"name": "out.txt", "contents": "42"}}}}]}}],
+                    "quiz": [{{"question": "q", "options": ["a", "b"], "Correct": "B"}}]}}"#,
+                starter, clean, silent, input_file, exit_code, file
+            );
+            let content = GeneratedContent::from_json(&json).unwrap();
+            let exercise = &content.exercises[0];
+            assert_eq!(exercise.starter_code.as_deref(), Some("fn main() {}"), "{}", json);
+            assert!(exercise.require_clean && exercise.prints_nothing, "{}", json);
+            let test = &exercise.test_cases[0];
+            assert_eq!(test.input_file.as_deref(), Some("in.txt"), "{}", json);
+            assert_eq!(test.expected_exit_code, Some(2), "{}", json);
+            assert_eq!(test.expected_file.as_ref().map(|f| f.contents.as_str()), Some("42"), "{}", json);
+            assert_eq!(content.quiz[0].correct, 1);
+        }
+    }
+
+    #[test]
+    fn a_synonym_next_to_its_field_does_not_fail_the_lesson() {
+        let json = r#"{
+            "concept": "Files",
+            "exercises": [{
+                "title": "Save",
+                "description": "Write 42 to out.txt",
+                "hints": [],
+                "test_cases": [{
+                    "output": "",
+                    "expected_file": {"name": "out.txt", "contents": "42", "content": "42"}
+                }],
+                "tests": [{"input": "", "output": ""}]
+            }]
+        }"#;
+
+        let content = GeneratedContent::from_json(json).unwrap();
+
+        let test = &content.exercises[0].test_cases[0];
+        assert_eq!(content.exercises[0].test_cases.len(), 1);
+        assert_eq!(test.expected_file.as_ref().map(|file| file.contents.as_str()), Some("42"));
+    }
+
+    fn exercise(title: &str, description: &str, output: &str) -> Exercise {
+        Exercise {
+            title: title.to_string(),
+            description: description.to_string(),
+            hints: vec![],
+            example_input: None,
+            example_output: None,
+            starter_code: None,
+            test_cases: vec![test_case(output, None)],
+            require_clean: false,
+            prints_nothing: false,
+            function: None,
+        }
+    }
+
+    #[test]
+    fn empty_and_stub_sections_are_weak() {
+        let json = r#"{
+            "concept": "Loops repeat code",
+            "step_by_step": ["Write the loop"],
+            "code_examples": [],
+            "syntax_guide": "for",
+            "common_patterns": [" "],
+            "exercises": []
+        }"#;
+        let mut content = GeneratedContent::from_json(json).unwrap();
+
+        assert_eq!(content.weak_sections(), vec![WeakSection::SyntaxGuide, WeakSection::CommonPatterns]);
+
+        content.syntax_guide = "Write `for item in items { ... }` to run the body once per item.".to_string();
+        content.common_patterns = vec!["Summing a list".to_string()];
+        content.step_by_step.clear();
+        assert_eq!(content.weak_sections(), vec![WeakSection::StepByStep]);
+    }
+
+    #[test]
+    fn runaway_content_is_cut_to_size() {
+        let json = r#"{"concept": "Loops", "syntax_guide": "for", "exercises": []}"#;
+        let mut content = GeneratedContent::from_json(json).unwrap();
+        content.concept = "word ".repeat(DEFAULT_MAX_SECTION_CHARS);
+        content.exercises = (0..50).map(|n| exercise(&format!("Exercise {}", n), "Print it.", "x")).collect();
+
+        let cut = content.apply_size_limits(3);
+
+        assert_eq!(cut, vec!["47 exercise(s) beyond the first 3", "the concept"]);
+        assert_eq!(content.exercises.len(), 3);
+        assert!(content.concept.ends_with(TRUNCATED_NOTE));
+        assert!(content.concept.chars().count() <= DEFAULT_MAX_SECTION_CHARS);
+        // A second pass finds nothing left to cut
+        assert!(content.apply_size_limits(3).is_empty());
+    }
+
+    #[test]
+    fn near_duplicate_exercises_are_removed() {
+        let mut exercises = vec![
+            exercise("Sum", "Write a program that prints the sum of 3 and 4.", "7"),
+            exercise("Add Numbers", "Write a program which prints the sum of 3 and 4!", "7"),
+            exercise("Product", "Write a program that prints the product of 3 and 4.", "12"),
+        ];
+
+        let removed = remove_duplicate_exercises(&mut exercises);
+
+        assert_eq!(removed, vec!["Add Numbers"]);
+        let titles: Vec<_> = exercises.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Sum", "Product"]);
+    }
+
+    #[test]
+    fn long_descriptions_alike_in_most_words_are_kept_apart_by_their_tests() {
+        let description = |task: &str| {
+            format!(
+                "Write a program that reads a list of whole numbers from standard input, one per line, \
+                 stops at the first empty line, and then prints the {} of all the numbers it read \
+                 on a single line, with no other text around it.",
+                task
+            )
+        };
+        let sum = exercise("Sum", &description("sum"), "6");
+        let largest = exercise("Largest", &description("largest"), "3");
+        assert!(word_overlap(&sum.description, &largest.description) >= DUPLICATE_DESCRIPTION_OVERLAP);
+        assert!(!largest.is_near_duplicate_of(&sum));
+
+        // The same tests make it a repeat after all
+        let reworded = exercise("Total", &description("total"), "6");
+        assert!(reworded.is_near_duplicate_of(&sum));
+        // And without tests to tell them apart, the description decides
+        let mut untested = largest.clone();
+        untested.test_cases.clear();
+        assert!(untested.is_near_duplicate_of(&sum));
+    }
+
+    #[test]
+    fn reading_input_is_decided_by_the_test_cases() {
+        let mut print_only = exercise("Greet", "Don't read input, just print a greeting.", "Hello");
+        assert!(!print_only.reads_input());
+
+        // No input words in the description, but the tests feed stdin
+        print_only.test_cases[0].input = "Ada".to_string();
+        assert!(print_only.reads_input());
+    }
+
+    #[test]
+    fn no_output_is_told_apart_from_missing_output() {
+        let mut silent = exercise("Exit code", "Exit with code 2 when no argument is given.", "");
+        silent.test_cases.push(test_case(" ", None));
+        silent.prints_nothing = true;
+        assert!(silent.expects_no_output());
+        assert!(silent.test_cases[0].passes(""));
+        assert!(!silent.test_cases[0].passes("done"));
+        assert_eq!(silent.test_cases[0].expected_display(), "(no output)");
+
+        // One test without an expected output among others with one is a gap in the tests
+        let mut missing = exercise("Greet", "Print a greeting.", "Hello");
+        missing.test_cases.push(test_case("", None));
+        assert!(!missing.expects_no_output());
+
+        // Nor do tests that are all empty, as generated from an exercise with no example output
+        let unknown = exercise("Greet", "Print a greeting.", "");
+        assert!(!unknown.expects_no_output());
+    }
+
+    #[test]
+    fn function_cases_become_tests_for_the_generated_main() {
+        let json = r#"{
+            "title": "Fibonacci",
+            "description": "Return the nth Fibonacci number.",
+            "hints": [],
+            "function": {
+                "signature": "fn fib(n: u32) -> u32",
+                "cases": [{ "call": "fib(0)", "expected": "0" }, { "call": "fib(10)", "expected": "55" }]
+            }
+        }"#;
+        let mut exercise: Exercise = serde_json::from_str(json).unwrap();
+        assert!(exercise.test_cases.is_empty());
+        exercise.add_function_tests();
+        assert_eq!(exercise.test_cases.len(), 2);
+        assert_eq!(exercise.test_cases[1].args, vec!["1"]);
+        assert_eq!(exercise.test_cases[1].output, "55");
+        assert_eq!(exercise.test_cases[1].label_suffix(), " (fib(10))");
+
+        // Tests already there, such as from a cached lesson, aren't added twice
+        exercise.add_function_tests();
+        assert_eq!(exercise.test_cases.len(), 2);
+    }
+
+    #[test]
+    fn input_files_are_read_relative_to_the_lesson() {
+        let dir = std::env::temp_dir().join(format!("cursed-coddy-input-files-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("fixtures")).unwrap();
+        std::fs::write(dir.join("fixtures/numbers.txt"), "1\n2\n3\n").unwrap();
+
+        let mut content = GeneratedContent::from_json(
+            r#"{"concept": "c", "exercises": [{"title": "Sum", "description": "d", "hints": [],
+                "test_cases": [{"input_file": "fixtures/numbers.txt", "output": "6"}]}]}"#,
+        )
+        .unwrap();
+        content.load_input_files(&dir).unwrap();
+        let test = &content.exercises[0].test_cases[0];
+        assert_eq!(test.input, "1\n2\n3\n");
+        assert_eq!(test.input_file, None);
+
+        content.exercises[0].test_cases[0].input_file = Some("fixtures/missing.txt".to_string());
+        let error = content.load_input_files(&dir).unwrap_err().to_string();
+        assert!(error.contains("fixtures/missing.txt"), "{}", error);
+
+        // Input files can't reach outside the lesson's folder
+        for name in ["../secret.txt", "fixtures/../../secret.txt", "/etc/passwd"] {
+            content.exercises[0].test_cases[0].input_file = Some(name.to_string());
+            let error = content.load_input_files(&dir).unwrap_err().to_string();
+            assert!(error.contains("outside the lesson's folder"), "{}", error);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_correct_option_is_read_as_a_number_a_numeral_or_a_letter() {
+        let correct = |value: &str| {
+            let json = format!(r#"{{"question": "q", "options": ["a", "b", "c"], "correct": {}}}"#, value);
+            serde_json::from_str::<QuizQuestion>(&json).map(|q| q.correct)
+        };
+        assert_eq!(correct("2").unwrap(), 2);
+        assert_eq!(correct("\"1\"").unwrap(), 1);
+        assert_eq!(correct("\" 0 \"").unwrap(), 0);
+        assert_eq!(correct("\"B\"").unwrap(), 1);
+        assert_eq!(correct("\"c\"").unwrap(), 2);
+
+        let error = correct("\"BC\"").unwrap_err().to_string();
+        assert!(error.contains("'BC' is not an option index"), "{}", error);
+        assert!(correct("\"\"").is_err());
+        assert!(correct("-1").is_err());
+    }
+
+    #[test]
+    fn quiz_questions_that_dont_parse_are_dropped_not_the_lesson() {
+        let content = GeneratedContent::from_json(
+            r#"{"concept": "c", "quiz": [
+                {"question": "Which?", "options": ["x", "y"], "correct": "A"},
+                {"question": "No options", "correct": 0},
+                {"question": "Bad index", "options": ["x", "y"], "correct": "??"},
+                "not a question",
+                {"Question": "Also?", "Options": ["x", "y"], "correct": 1, "Explanation": "y it is"}
+            ]}"#,
+        )
+        .unwrap();
+        let questions: Vec<&str> = content.quiz.iter().map(|q| q.question.as_str()).collect();
+        assert_eq!(questions, vec!["Which?", "Also?"]);
+        assert_eq!(content.quiz[1].explanation, "y it is");
+
+        // A missing or null quiz is no quiz
+        assert!(GeneratedContent::from_json(r#"{"concept": "c", "quiz": null}"#).unwrap().quiz.is_empty());
+        assert!(GeneratedContent::from_json(r#"{"concept": "c"}"#).unwrap().quiz.is_empty());
+    }
+
+    #[test]
+    fn output_words_are_matched_as_whole_words() {
+        assert!(mentions_output("Print the area of the rectangle"));
+        assert!(mentions_output("Use println! to show the total"));
+        assert!(mentions_output("The result is console.log()'d"));
+        assert!(mentions_output("Make sure the sum is logged"));
+        assert!(mentions_output("Use std::cout for the result"));
+        assert!(mentions_output("The greeting should be written to stdout"));
+
+        // "log" inside another word is not a request for output
+        assert!(!mentions_output("Implement the logic of a traffic light"));
+        assert!(!mentions_output("Compute the logarithm in a method of Shape"));
+        assert!(!mentions_output("A showcase of struct methods"));
+    }
+
+    #[test]
+    fn random_output_pattern_replaces_numbers() {
+        let pattern = random_output_pattern("Rolled 3 and 5").unwrap();
+        let test = test_case("Rolled 3 and 5", Some(&pattern));
+
+        assert!(test.passes("Rolled 6 and 1"));
+        assert!(!test.passes("Rolled six and 1"));
+        assert_eq!(random_output_pattern("Heads or tails?"), None);
+    }
+
+    #[test]
+    fn partial_string_field_reads_text_as_it_arrives() {
+        assert_eq!(partial_string_field(r#"{"conc"#, "concept"), None);
+        assert_eq!(partial_string_field(r#"{"concept": "#, "concept"), None);
+        assert_eq!(
+            partial_string_field(r#"{"concept": "Loops \"repeat\"\ncode \u00e9"#, "concept"),
+            Some(("Loops \"repeat\"\ncode \u{e9}".to_string(), false))
+        );
+        // A cut-off escape is left for the next chunk
+        assert_eq!(
+            partial_string_field(r#"{"concept": "Loops\u00"#, "concept"),
+            Some(("Loops".to_string(), false))
+        );
+        assert_eq!(
+            partial_string_field(r#"{"concept": "Loops repeat", "step_by_step": ["#, "concept"),
+            Some(("Loops repeat".to_string(), true))
+        );
+    }
 }
@@ -1,5 +1,7 @@
 pub mod generator;
 pub mod formatter;
+pub mod request_limit;
 pub mod ruleset;
+pub mod snippets;
 
-pub use generator::Generator;
+pub use generator::{ApiMode, Generator};
@@ -0,0 +1,46 @@
+use std::sync::{Condvar, Mutex, OnceLock};
+
+// One generation at a time suits a local server running a single model
+const DEFAULT_MAX_REQUESTS: usize = 1;
+
+// Generations in flight, and the signal that one has finished
+static SLOTS: OnceLock<(Mutex<usize>, Condvar)> = OnceLock::new();
+
+/// How many generation requests may be sent to Ollama at once;
+/// CURSED_CODDY_MAX_REQUESTS raises it for a server that can run several in parallel
+pub fn max_concurrent_requests() -> usize {
+    std::env::var("CURSED_CODDY_MAX_REQUESTS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_REQUESTS)
+}
+
+/// A held slot for one generation request; the slot is given back when it's dropped
+pub struct RequestPermit;
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        if let Some((in_flight, freed)) = SLOTS.get() {
+            let mut count = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            *count = count.saturating_sub(1);
+            freed.notify_one();
+        }
+    }
+}
+
+/// Returns a permit once a slot is free, calling `on_wait` first if the caller
+/// has to wait for another request to finish
+pub fn acquire(on_wait: impl FnOnce()) -> RequestPermit {
+    let max = max_concurrent_requests();
+    let (in_flight, freed) = SLOTS.get_or_init(|| (Mutex::new(0), Condvar::new()));
+    let mut count = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+    if *count >= max {
+        on_wait();
+        while *count >= max {
+            count = freed.wait(count).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+    *count += 1;
+    RequestPermit
+}
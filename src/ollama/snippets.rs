@@ -0,0 +1,396 @@
+use crate::config::Language;
+use crate::ollama::formatter::CodeExample;
+
+// A pair of runnable examples for every topic whose name contains one of the keywords.
+// Entries are checked in order, so more specific keywords come first.
+struct Snippet {
+    keywords: &'static [&'static str],
+    examples: [(&'static str, &'static str); 2],
+}
+
+/// Runnable examples for a topic, used when a generated lesson comes back with
+/// too few examples. Covers the beginner curriculum topics for each language.
+pub fn topic_examples(language: Language, topic: &str) -> Vec<CodeExample> {
+    let topic_lower = topic.to_lowercase();
+    let library = match language {
+        Language::JavaScript => JAVASCRIPT,
+        Language::Cpp => CPP,
+        Language::Rust => RUST,
+    };
+
+    library
+        .iter()
+        .find(|snippet| snippet.keywords.iter().any(|k| topic_lower.contains(k)))
+        .map(|snippet| {
+            snippet
+                .examples
+                .iter()
+                .map(|(code, explanation)| CodeExample {
+                    code: code.to_string(),
+                    explanation: explanation.to_string(),
+                    check_error: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+const JAVASCRIPT: &[Snippet] = &[
+    Snippet {
+        keywords: &["arrow"],
+        examples: [
+            (
+                "const square = (n) => n * n;\nconsole.log(square(4));",
+                "An arrow function with a single expression body returns that expression automatically, so no `return` keyword or braces are needed. This prints 16.",
+            ),
+            (
+                "const numbers = [1, 2, 3];\nconst doubled = numbers.map((n) => n * 2);\nconsole.log(doubled.join(', '));",
+                "Arrow functions are handy as short callbacks. `map` calls the arrow function for every element and collects the results, printing \"2, 4, 6\".",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["return"],
+        examples: [
+            (
+                "function add(a, b) {\n    return a + b;\n}\nconst total = add(3, 4);\nconsole.log(`Total: ${total}`);",
+                "`return` sends a value back to the caller. The result of `add(3, 4)` is stored in `total` and printed as \"Total: 7\".",
+            ),
+            (
+                "function isEven(n) {\n    if (n % 2 === 0) {\n        return true;\n    }\n    return false;\n}\nconsole.log(isEven(10));\nconsole.log(isEven(7));",
+                "A function can return early from inside an `if`. Once `return` runs, the rest of the function is skipped. This prints true, then false.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["parameter", "argument"],
+        examples: [
+            (
+                "function greet(name, greeting) {\n    console.log(`${greeting}, ${name}!`);\n}\ngreet('Alice', 'Hello');\ngreet('Bob', 'Hi');",
+                "`name` and `greeting` are parameters; 'Alice' and 'Hello' are the arguments passed in. Arguments are matched to parameters by position.",
+            ),
+            (
+                "function greet(name, greeting = 'Hello') {\n    console.log(`${greeting}, ${name}!`);\n}\ngreet('Alice');\ngreet('Bob', 'Welcome');",
+                "A default parameter value is used when the caller leaves that argument out. The first call prints \"Hello, Alice!\".",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["function"],
+        examples: [
+            (
+                "function sayHello() {\n    console.log('Hello from a function!');\n}\nsayHello();\nsayHello();",
+                "`function` declares a reusable block of code. Nothing runs until the function is called, and it can be called as many times as needed.",
+            ),
+            (
+                "function describe(animal, sound) {\n    return `The ${animal} says ${sound}`;\n}\nconsole.log(describe('dog', 'woof'));\nconsole.log(describe('cat', 'meow'));",
+                "Functions take inputs through parameters and hand back a result with `return`, so the same logic works for different values.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["switch"],
+        examples: [
+            (
+                "const day = 3;\nswitch (day) {\n    case 1:\n        console.log('Monday');\n        break;\n    case 2:\n        console.log('Tuesday');\n        break;\n    case 3:\n        console.log('Wednesday');\n        break;\n    default:\n        console.log('Another day');\n}",
+                "`switch` compares one value against several `case` labels using strict equality. `break` stops execution from falling through into the next case.",
+            ),
+            (
+                "const fruit = 'apple';\nswitch (fruit) {\n    case 'apple':\n    case 'pear':\n        console.log('Grows on trees');\n        break;\n    case 'strawberry':\n        console.log('Grows on plants');\n        break;\n    default:\n        console.log('Unknown fruit');\n}",
+                "Stacking cases without a `break` between them lets several values share the same code. Strings work as case labels too.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["while"],
+        examples: [
+            (
+                "let count = 1;\nwhile (count <= 5) {\n    console.log(`Count: ${count}`);\n    count++;\n}",
+                "A `while` loop repeats as long as its condition is true. Updating `count` inside the loop is what eventually makes the condition false.",
+            ),
+            (
+                "let n = 100;\nlet steps = 0;\nwhile (n > 1) {\n    n = Math.floor(n / 2);\n    steps++;\n}\nconsole.log(`Halved ${steps} times`);",
+                "`while` suits loops where the number of repetitions isn't known in advance. Here we keep halving until n reaches 1 and count the steps.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["for loop", "loop"],
+        examples: [
+            (
+                "for (let i = 1; i <= 5; i++) {\n    console.log(`Step ${i}`);\n}",
+                "A `for` loop has three parts: the start (`let i = 1`), the condition checked before each pass (`i <= 5`), and the update run after each pass (`i++`).",
+            ),
+            (
+                "const colors = ['red', 'green', 'blue'];\nfor (const color of colors) {\n    console.log(color);\n}",
+                "`for...of` walks through each element of an array directly, which avoids managing an index when you only need the values.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["data type", "type"],
+        examples: [
+            (
+                "const age = 25;\nconst name = 'Alice';\nconst isStudent = true;\nconsole.log(typeof age);\nconsole.log(typeof name);\nconsole.log(typeof isStudent);",
+                "JavaScript has several primitive types, including number, string and boolean. `typeof` reports the type of a value: number, string, boolean.",
+            ),
+            (
+                "let value;\nconsole.log(typeof value);\nvalue = null;\nconsole.log(value === null);\nconst scores = [90, 85];\nconsole.log(Array.isArray(scores));",
+                "A variable with no value is `undefined`, while `null` means \"intentionally empty\". Arrays are objects, so use `Array.isArray` to check for them.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["operator"],
+        examples: [
+            (
+                "const a = 17;\nconst b = 5;\nconsole.log(a + b);\nconsole.log(a - b);\nconsole.log(a * b);\nconsole.log(a / b);\nconsole.log(a % b);",
+                "Arithmetic operators work on numbers. Division always produces a decimal result (3.4), and `%` gives the remainder (2).",
+            ),
+            (
+                "const x = 5;\nconsole.log(x === 5);\nconsole.log(x === '5');\nconsole.log(x > 3 && x < 10);\nconsole.log(x < 3 || x > 4);",
+                "`===` compares both value and type, so 5 and '5' are not equal. `&&` needs both sides true, `||` needs at least one.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["variable"],
+        examples: [
+            (
+                "const name = 'Alice';\nlet score = 10;\nscore = score + 5;\nconsole.log(`${name} has ${score} points`);",
+                "`const` declares a variable that can't be reassigned, while `let` allows the value to change later. This prints \"Alice has 15 points\".",
+            ),
+            (
+                "let message = 'Hello';\nmessage += ', World';\nconsole.log(message);\nconst PI = 3.14159;\nconsole.log(PI * 2);",
+                "`+=` appends to a string stored in a `let` variable. Values that never change, like PI, are usually declared with `const`.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["console", "output", "print"],
+        examples: [
+            (
+                "console.log('Hello, World!');\nconsole.log('Line two');\nconsole.log(42);",
+                "`console.log` prints its argument followed by a newline. It accepts strings, numbers and any other value.",
+            ),
+            (
+                "const item = 'coffee';\nconst price = 3.5;\nconsole.log('Item:', item);\nconsole.log(`A ${item} costs $${price}`);",
+                "Passing several arguments prints them separated by spaces. Template literals (backticks) insert values with `${...}`.",
+            ),
+        ],
+    },
+];
+
+const CPP: &[Snippet] = &[
+    Snippet {
+        keywords: &["overload"],
+        examples: [
+            (
+                "#include <iostream>\n\nint add(int a, int b) {\n    return a + b;\n}\n\ndouble add(double a, double b) {\n    return a + b;\n}\n\nint main() {\n    std::cout << add(2, 3) << std::endl;\n    std::cout << add(2.5, 1.25) << std::endl;\n    return 0;\n}",
+                "Two functions can share a name if their parameter types differ. The compiler picks the version whose parameters match the arguments, printing 5 and 3.75.",
+            ),
+            (
+                "#include <iostream>\n#include <string>\n\nvoid show(int value) {\n    std::cout << \"int: \" << value << std::endl;\n}\n\nvoid show(const std::string& value) {\n    std::cout << \"string: \" << value << std::endl;\n}\n\nint main() {\n    show(7);\n    show(std::string(\"seven\"));\n    return 0;\n}",
+                "Overloads can also differ by parameter type category, such as a number versus a string. Each call is resolved at compile time.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["return"],
+        examples: [
+            (
+                "#include <iostream>\n\nint square(int n) {\n    return n * n;\n}\n\nint main() {\n    int result = square(6);\n    std::cout << \"Result: \" << result << std::endl;\n    return 0;\n}",
+                "The type before the function name (`int`) is its return type. `return` hands the value back to the caller, so this prints \"Result: 36\".",
+            ),
+            (
+                "#include <iostream>\n\nbool isPositive(int n) {\n    return n > 0;\n}\n\nvoid printSign(int n) {\n    if (isPositive(n)) {\n        std::cout << n << \" is positive\" << std::endl;\n    } else {\n        std::cout << n << \" is not positive\" << std::endl;\n    }\n}\n\nint main() {\n    printSign(4);\n    printSign(-2);\n    return 0;\n}",
+                "A `bool` function returns true or false, while a `void` function returns nothing and is called only for its effects.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["parameter"],
+        examples: [
+            (
+                "#include <iostream>\n#include <string>\n\nvoid greet(const std::string& name, int times) {\n    for (int i = 0; i < times; i++) {\n        std::cout << \"Hello, \" << name << \"!\" << std::endl;\n    }\n}\n\nint main() {\n    greet(\"Alice\", 2);\n    return 0;\n}",
+                "Each parameter declares a type and a name. Passing a string as `const std::string&` avoids copying it while preventing the function from changing it.",
+            ),
+            (
+                "#include <iostream>\n\nvoid addOne(int& value) {\n    value += 1;\n}\n\nint main() {\n    int count = 5;\n    addOne(count);\n    std::cout << \"Count: \" << count << std::endl;\n    return 0;\n}",
+                "A reference parameter (`int&`) lets the function modify the caller's variable. Without the `&`, the function would change only a copy and this would print 5, not 6.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["function"],
+        examples: [
+            (
+                "#include <iostream>\n\nvoid sayHello() {\n    std::cout << \"Hello from a function!\" << std::endl;\n}\n\nint main() {\n    sayHello();\n    sayHello();\n    return 0;\n}",
+                "A function is defined with a return type, a name and a parameter list. It must be declared before it is called, which is why it appears above `main`.",
+            ),
+            (
+                "#include <iostream>\n\nint max(int a, int b);\n\nint main() {\n    std::cout << max(8, 3) << std::endl;\n    return 0;\n}\n\nint max(int a, int b) {\n    return a > b ? a : b;\n}",
+                "A prototype (`int max(int a, int b);`) declares the function early so its definition can come after `main`.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["switch"],
+        examples: [
+            (
+                "#include <iostream>\n\nint main() {\n    int day = 3;\n    switch (day) {\n        case 1:\n            std::cout << \"Monday\" << std::endl;\n            break;\n        case 2:\n            std::cout << \"Tuesday\" << std::endl;\n            break;\n        case 3:\n            std::cout << \"Wednesday\" << std::endl;\n            break;\n        default:\n            std::cout << \"Another day\" << std::endl;\n    }\n    return 0;\n}",
+                "`switch` jumps to the `case` matching an integer value. `break` stops execution from falling through into the following cases.",
+            ),
+            (
+                "#include <iostream>\n\nint main() {\n    char grade = 'B';\n    switch (grade) {\n        case 'A':\n        case 'B':\n            std::cout << \"Well done\" << std::endl;\n            break;\n        case 'C':\n            std::cout << \"Passed\" << std::endl;\n            break;\n        default:\n            std::cout << \"Keep practicing\" << std::endl;\n    }\n    return 0;\n}",
+                "Characters work as case labels too. Listing cases back to back lets several values share the same code.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["while"],
+        examples: [
+            (
+                "#include <iostream>\n\nint main() {\n    int count = 1;\n    while (count <= 5) {\n        std::cout << \"Count: \" << count << std::endl;\n        count++;\n    }\n    return 0;\n}",
+                "A `while` loop checks its condition before every pass and stops once it is false. Forgetting `count++` would make this loop run forever.",
+            ),
+            (
+                "#include <iostream>\n\nint main() {\n    int number = 0;\n    do {\n        std::cout << \"Runs at least once: \" << number << std::endl;\n        number++;\n    } while (number < 0);\n    return 0;\n}",
+                "A `do...while` loop checks the condition after the body, so the body always runs at least once even when the condition starts out false.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["for loop", "loop"],
+        examples: [
+            (
+                "#include <iostream>\n\nint main() {\n    for (int i = 1; i <= 5; i++) {\n        std::cout << \"Step \" << i << std::endl;\n    }\n    return 0;\n}",
+                "A `for` loop combines the counter setup, the condition and the update in one line. The counter `i` only exists inside the loop.",
+            ),
+            (
+                "#include <iostream>\n#include <vector>\n\nint main() {\n    std::vector<int> scores = {90, 75, 82};\n    int total = 0;\n    for (int score : scores) {\n        total += score;\n    }\n    std::cout << \"Total: \" << total << std::endl;\n    return 0;\n}",
+                "A range-based `for` visits every element of a container without an index. This adds up the scores and prints \"Total: 247\".",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["input", "output", "cin", "cout"],
+        examples: [
+            (
+                "#include <iostream>\n#include <string>\n\nint main() {\n    std::string name;\n    std::cin >> name;\n    std::cout << \"Hello, \" << name << \"!\" << std::endl;\n    return 0;\n}",
+                "`std::cin >>` reads one whitespace-separated value from standard input into a variable, and `std::cout <<` writes values to standard output.",
+            ),
+            (
+                "#include <iostream>\n\nint main() {\n    int a, b;\n    std::cin >> a >> b;\n    std::cout << a << \" + \" << b << \" = \" << a + b << std::endl;\n    return 0;\n}",
+                "Reads can be chained to take several values at once. Given the input \"3 4\", this prints \"3 + 4 = 7\".",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["operator"],
+        examples: [
+            (
+                "#include <iostream>\n\nint main() {\n    int a = 17;\n    int b = 5;\n    std::cout << a + b << std::endl;\n    std::cout << a / b << std::endl;\n    std::cout << a % b << std::endl;\n    std::cout << 17.0 / 5 << std::endl;\n    return 0;\n}",
+                "Dividing two integers throws away the fraction (17 / 5 is 3), and `%` gives the remainder (2). Making one side a double gives 3.4.",
+            ),
+            (
+                "#include <iostream>\n\nint main() {\n    int x = 7;\n    bool inRange = x > 0 && x < 10;\n    std::cout << std::boolalpha << inRange << std::endl;\n    std::cout << (x == 7) << std::endl;\n    std::cout << (x != 7 || x > 5) << std::endl;\n    return 0;\n}",
+                "Comparison operators produce `bool` values, and `&&` / `||` combine them. `std::boolalpha` prints true/false instead of 1/0.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["variable", "type"],
+        examples: [
+            (
+                "#include <iostream>\n#include <string>\n\nint main() {\n    int age = 25;\n    double height = 1.75;\n    char initial = 'A';\n    bool isStudent = true;\n    std::string name = \"Alice\";\n    std::cout << name << \" \" << initial << \" \" << age << \" \" << height << \" \" << isStudent << std::endl;\n    return 0;\n}",
+                "Every C++ variable has a fixed type declared up front: `int` for whole numbers, `double` for decimals, `char` for one character, `bool` for true/false and `std::string` for text.",
+            ),
+            (
+                "#include <iostream>\n\nint main() {\n    const int maxScore = 100;\n    int score = 40;\n    score = score + 15;\n    auto ratio = static_cast<double>(score) / maxScore;\n    std::cout << \"Score: \" << score << \", ratio: \" << ratio << std::endl;\n    return 0;\n}",
+                "`const` variables can't be changed after initialization. `auto` lets the compiler deduce the type, and `static_cast<double>` converts before dividing.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["syntax", "basic"],
+        examples: [
+            (
+                "#include <iostream>\n\nint main() {\n    std::cout << \"Hello, World!\" << std::endl;\n    return 0;\n}",
+                "Every C++ program starts in `main`. `#include <iostream>` brings in console output, statements end with semicolons, and `return 0;` reports success.",
+            ),
+            (
+                "#include <iostream>\n\n// Single-line comment\nint main() {\n    /* Multi-line\n       comment */\n    int total = 2 + 3;\n    std::cout << \"Total: \" << total << std::endl;\n    return 0;\n}",
+                "Comments start with `//` or sit between `/*` and `*/` and are ignored by the compiler. Curly braces group statements into blocks.",
+            ),
+        ],
+    },
+];
+
+const RUST: &[Snippet] = &[
+    Snippet {
+        keywords: &["match", "pattern"],
+        examples: [
+            (
+                "fn main() {\n    let number = 3;\n    match number {\n        1 => println!(\"One\"),\n        2 | 3 => println!(\"Two or three\"),\n        4..=9 => println!(\"Between four and nine\"),\n        _ => println!(\"Something else\"),\n    }\n}",
+                "`match` compares a value against patterns in order and runs the first arm that fits. `|` combines patterns, `..=` matches a range, and `_` catches everything else.",
+            ),
+            (
+                "fn describe(point: (i32, i32)) -> String {\n    match point {\n        (0, 0) => String::from(\"origin\"),\n        (x, 0) => format!(\"on the x axis at {}\", x),\n        (0, y) => format!(\"on the y axis at {}\", y),\n        (x, y) => format!(\"at ({}, {})\", x, y),\n    }\n}\n\nfn main() {\n    println!(\"{}\", describe((0, 0)));\n    println!(\"{}\", describe((4, 0)));\n    println!(\"{}\", describe((2, 7)));\n}",
+                "Patterns can destructure values and bind parts of them to names. `match` is an expression, so each arm produces the function's return value.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["ownership"],
+        examples: [
+            (
+                "fn main() {\n    let first = String::from(\"hello\");\n    let second = first;\n    println!(\"{}\", second);\n    // println!(\"{}\", first); // error: value borrowed after move\n}",
+                "Assigning a `String` to another variable moves ownership. `first` can no longer be used, which prevents two owners from freeing the same memory.",
+            ),
+            (
+                "fn print_length(text: &String) {\n    println!(\"Length: {}\", text.len());\n}\n\nfn main() {\n    let message = String::from(\"ownership\");\n    print_length(&message);\n    println!(\"Still usable: {}\", message);\n}",
+                "Passing `&message` lends the value to the function instead of moving it, so `message` is still valid after the call.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["function"],
+        examples: [
+            (
+                "fn greet(name: &str) {\n    println!(\"Hello, {}!\", name);\n}\n\nfn main() {\n    greet(\"Alice\");\n    greet(\"Bob\");\n}",
+                "Functions are declared with `fn`. Every parameter needs a type annotation, here `&str` for a string slice.",
+            ),
+            (
+                "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn main() {\n    let total = add(3, 4);\n    println!(\"Total: {}\", total);\n}",
+                "The return type follows `->`. The last expression without a semicolon is returned, so `a + b` is the result. Adding a semicolon would make it a statement and cause a type error.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["loop"],
+        examples: [
+            (
+                "fn main() {\n    for i in 1..=5 {\n        println!(\"Step {}\", i);\n    }\n\n    let mut count = 3;\n    while count > 0 {\n        println!(\"{}...\", count);\n        count -= 1;\n    }\n}",
+                "`for` iterates over a range (`1..=5` includes 5), and `while` repeats while its condition holds. The counter must be `mut` to change it.",
+            ),
+            (
+                "fn main() {\n    let mut attempts = 0;\n    let result = loop {\n        attempts += 1;\n        if attempts == 4 {\n            break attempts * 10;\n        }\n    };\n    println!(\"Result: {}\", result);\n}",
+                "`loop` repeats forever until `break`. Because `loop` is an expression, `break` can return a value, here 40.",
+            ),
+        ],
+    },
+    Snippet {
+        keywords: &["data type", "type"],
+        examples: [
+            (
+                "fn main() {\n    let age: u32 = 30;\n    let temperature: f64 = -3.5;\n    let is_active: bool = true;\n    let initial: char = 'R';\n    println!(\"{} {} {} {}\", age, temperature, is_active, initial);\n}",
+                "Rust's scalar types include integers (`u32` is unsigned), floats (`f64`), `bool` and `char`. Annotations are optional when the type can be inferred.",
+            ),
+            (
+                "fn main() {\n    let point: (i32, i32) = (3, 4);\n    let scores: [u8; 3] = [90, 75, 82];\n    println!(\"x = {}, y = {}\", point.0, point.1);\n    println!(\"First score: {}, count: {}\", scores[0], scores.len());\n}",
+                "Tuples group values of different types and are read with `.0`, `.1`. Arrays hold a fixed number of values of one type.",
+            ),
+        ],
+    },
+];
@@ -0,0 +1,300 @@
+use crate::config::Language;
+use crate::progress::{JourneyProgress, LessonState, Progress};
+
+/// How progress imported from another machine is combined with this one's.
+/// Finished lessons, ratings and finished journeys are always combined; the
+/// strategy decides what happens where both sides have their own version of
+/// something: a language's journey, the lesson in progress and the preferences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MergeStrategy {
+    /// The journey that got further, the lesson in progress that was touched last
+    #[default]
+    Union,
+    /// The imported side wins
+    Theirs,
+    /// This machine's side wins
+    Ours,
+}
+
+impl MergeStrategy {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            MergeStrategy::Union => "union",
+            MergeStrategy::Theirs => "theirs",
+            MergeStrategy::Ours => "ours",
+        }
+    }
+}
+
+/// The result of merging imported progress: the progress to save, and what
+/// changed compared with this machine's, one line per change
+pub struct ProgressMerge {
+    pub progress: Progress,
+    pub changes: Vec<String>,
+}
+
+/// Combines `theirs` into `ours` with the given strategy
+pub fn merge_progress(ours: Progress, theirs: Progress, strategy: MergeStrategy) -> ProgressMerge {
+    let mut merged = ours;
+    let mut changes = Vec::new();
+
+    // The same lesson finished on both machines is one record, not two
+    let mut added_lessons = 0;
+    for record in theirs.completed_lessons {
+        let known = merged.completed_lessons.iter().any(|r| {
+            r.language == record.language
+                && r.topic == record.topic
+                && r.difficulty == record.difficulty
+                && r.completed_at == record.completed_at
+        });
+        if known {
+            continue;
+        }
+        merged.statistics.total_lessons_completed += 1;
+        *merged.statistics.lessons_by_language.entry(record.language.display_name().to_string()).or_insert(0) += 1;
+        *merged.statistics.lessons_by_difficulty.entry(record.difficulty.display_name().to_string()).or_insert(0) += 1;
+        merged.completed_lessons.push(record);
+        added_lessons += 1;
+    }
+    if added_lessons > 0 {
+        changes.push(format!("{} completed lesson(s) added", added_lessons));
+    }
+    merged.completed_lessons.sort_by_key(|r| timestamp(&r.completed_at));
+
+    let mut added_ratings = 0;
+    for rating in theirs.lesson_ratings {
+        let known = merged.lesson_ratings.iter().any(|r| {
+            r.language == rating.language && r.topic == rating.topic && r.model == rating.model && r.rated_at == rating.rated_at
+        });
+        if !known {
+            merged.lesson_ratings.push(rating);
+            added_ratings += 1;
+        }
+    }
+    if added_ratings > 0 {
+        changes.push(format!("{} lesson rating(s) added", added_ratings));
+    }
+
+    let mut added_finished = 0;
+    for record in theirs.completed_journeys {
+        if !merged.completed_journeys.iter().any(|r| r.language == record.language && r.completed_at == record.completed_at) {
+            merged.completed_journeys.push(record);
+            added_finished += 1;
+        }
+    }
+    if added_finished > 0 {
+        changes.push(format!("{} finished journey(s) added", added_finished));
+    }
+
+    for journey in theirs.journeys {
+        let language = journey.language;
+        match merged.journeys.iter().position(|j| j.language == language) {
+            None => {
+                changes.push(format!("{} journey added ({})", language.display_name(), journey_position(&journey)));
+                merged.journeys.push(journey);
+            }
+            Some(idx) => {
+                let take_theirs = match strategy {
+                    MergeStrategy::Union => journey_rank(&journey) > journey_rank(&merged.journeys[idx]),
+                    MergeStrategy::Theirs => journey_rank(&journey) != journey_rank(&merged.journeys[idx]),
+                    MergeStrategy::Ours => false,
+                };
+                if take_theirs {
+                    changes.push(journey_change(language, &merged.journeys[idx], &journey));
+                    merged.journeys[idx] = journey;
+                }
+            }
+        }
+    }
+
+    let take_lesson = match (&merged.current_lesson, &theirs.current_lesson) {
+        (_, None) => false,
+        (None, Some(_)) => strategy != MergeStrategy::Ours,
+        (Some(mine), Some(other)) => match strategy {
+            MergeStrategy::Union => last_active(other) > last_active(mine),
+            MergeStrategy::Theirs => !same_lesson(mine, other),
+            MergeStrategy::Ours => false,
+        },
+    };
+    if let Some(lesson) = theirs.current_lesson.filter(|_| take_lesson) {
+        changes.push(format!(
+            "Lesson in progress: {} ({}), {}/{} exercises done",
+            lesson.topic,
+            lesson.language.display_name(),
+            lesson.current_exercise,
+            lesson.total_exercises
+        ));
+        merged.current_lesson = Some(lesson);
+    }
+
+    // Preferences are only filled in where this machine has none, unless theirs win
+    let before = merged.preferences;
+    let prefs = &mut merged.preferences;
+    let other = theirs.preferences;
+    match strategy {
+        MergeStrategy::Theirs => {
+            prefs.language = other.language.or(prefs.language);
+            prefs.difficulty = other.difficulty.or(prefs.difficulty);
+            prefs.lesson_type = other.lesson_type.or(prefs.lesson_type);
+        }
+        MergeStrategy::Union => {
+            prefs.language = prefs.language.or(other.language);
+            prefs.difficulty = prefs.difficulty.or(other.difficulty);
+            prefs.lesson_type = prefs.lesson_type.or(other.lesson_type);
+        }
+        MergeStrategy::Ours => {}
+    }
+    if (before.language, before.difficulty, before.lesson_type) != (prefs.language, prefs.difficulty, prefs.lesson_type) {
+        changes.push("Default language, difficulty or lesson type updated".to_string());
+    }
+
+    ProgressMerge { progress: merged, changes }
+}
+
+// Timestamps are epoch seconds; anything unreadable sorts first
+fn timestamp(value: &str) -> u64 {
+    value.parse().unwrap_or(0)
+}
+
+fn last_active(lesson: &LessonState) -> u64 {
+    lesson.last_active_at.as_deref().map(timestamp).unwrap_or(0)
+}
+
+fn same_lesson(a: &LessonState, b: &LessonState) -> bool {
+    a.language == b.language && a.topic == b.topic && a.current_exercise == b.current_exercise
+}
+
+// How far a journey got: its stage, then the topics done in it
+fn journey_rank(journey: &JourneyProgress) -> (usize, usize, usize) {
+    (journey.current_stage, journey.completed_topics.len(), journey.skipped_topics.len())
+}
+
+// current_stage is the index of the last lesson finished among every language's
+// lessons, so it isn't shown; the lessons done are what the learner recognises
+fn journey_position(journey: &JourneyProgress) -> String {
+    let mut position = format!("{} lesson(s) done", journey.completed_topics.len());
+    if !journey.skipped_topics.is_empty() {
+        position.push_str(&format!(", {} skipped", journey.skipped_topics.len()));
+    }
+    position
+}
+
+fn journey_change(language: Language, from: &JourneyProgress, to: &JourneyProgress) -> String {
+    format!("{} journey: {} -> {}", language.display_name(), journey_position(from), journey_position(to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Difficulty, LessonType};
+    use crate::progress::LessonRecord;
+
+    fn record(topic: &str, completed_at: &str) -> LessonRecord {
+        LessonRecord {
+            language: Language::Rust,
+            difficulty: Difficulty::Beginner,
+            lesson_type: LessonType::Short,
+            topic: topic.to_string(),
+            completed_at: completed_at.to_string(),
+        }
+    }
+
+    fn journey(current_stage: usize, done: &[&str]) -> JourneyProgress {
+        JourneyProgress {
+            language: Language::Rust,
+            current_stage,
+            current_topic_index: 0,
+            completed_topics: done.iter().map(|t| t.to_string()).collect(),
+            started_at: "100".to_string(),
+            skipped_topics: vec![],
+            checkpoint: None,
+            history: vec![],
+            last_active_at: None,
+        }
+    }
+
+    fn lesson(topic: &str, last_active_at: &str) -> LessonState {
+        LessonState {
+            language: Language::Rust,
+            difficulty: Difficulty::Beginner,
+            lesson_type: LessonType::Short,
+            topic: topic.to_string(),
+            current_exercise: 1,
+            total_exercises: 3,
+            last_active_at: Some(last_active_at.to_string()),
+            session_id: None,
+        }
+    }
+
+    // This machine is further along the journey; the other worked on a lesson more recently
+    fn sides() -> (Progress, Progress) {
+        let mut ours = Progress {
+            completed_lessons: vec![record("loops", "200")],
+            journeys: vec![journey(3, &["Lesson 1", "Lesson 2", "Lesson 3"])],
+            current_lesson: Some(lesson("structs", "300")),
+            ..Progress::default()
+        };
+        ours.statistics.total_lessons_completed = 1;
+        ours.preferences.difficulty = Some(Difficulty::Beginner);
+
+        let mut theirs = Progress {
+            completed_lessons: vec![record("loops", "200"), record("closures", "150")],
+            journeys: vec![journey(1, &["Lesson 1"])],
+            current_lesson: Some(lesson("traits", "400")),
+            ..Progress::default()
+        };
+        theirs.preferences.difficulty = Some(Difficulty::Advanced);
+        theirs.preferences.language = Some(Language::Cpp);
+        (ours, theirs)
+    }
+
+    #[test]
+    fn finished_lessons_are_combined_whatever_the_strategy() {
+        for strategy in [MergeStrategy::Union, MergeStrategy::Theirs, MergeStrategy::Ours] {
+            let (ours, theirs) = sides();
+            let merge = merge_progress(ours, theirs, strategy);
+
+            // The lesson finished on both machines is kept once, and the list stays in order
+            let topics: Vec<_> = merge.progress.completed_lessons.iter().map(|r| r.topic.as_str()).collect();
+            assert_eq!(topics, vec!["closures", "loops"], "{}", strategy.display_name());
+            assert_eq!(merge.progress.statistics.total_lessons_completed, 2);
+            assert!(merge.changes.contains(&"1 completed lesson(s) added".to_string()));
+        }
+    }
+
+    #[test]
+    fn union_keeps_the_further_journey_and_the_latest_lesson() {
+        let (ours, theirs) = sides();
+        let merge = merge_progress(ours, theirs, MergeStrategy::Union);
+
+        assert_eq!(merge.progress.journeys[0].completed_topics.len(), 3);
+        assert_eq!(merge.progress.current_lesson.unwrap().topic, "traits");
+        // Preferences only fill gaps
+        assert_eq!(merge.progress.preferences.difficulty, Some(Difficulty::Beginner));
+        assert_eq!(merge.progress.preferences.language, Some(Language::Cpp));
+    }
+
+    #[test]
+    fn theirs_takes_the_imported_side() {
+        let (ours, theirs) = sides();
+        let merge = merge_progress(ours, theirs, MergeStrategy::Theirs);
+
+        assert_eq!(merge.progress.journeys[0].completed_topics, vec!["Lesson 1"]);
+        assert!(merge.changes.contains(&"Rust journey: 3 lesson(s) done -> 1 lesson(s) done".to_string()));
+        assert_eq!(merge.progress.current_lesson.unwrap().topic, "traits");
+        assert_eq!(merge.progress.preferences.difficulty, Some(Difficulty::Advanced));
+    }
+
+    #[test]
+    fn ours_keeps_this_machines_side() {
+        let (ours, mut theirs) = sides();
+        theirs.journeys[0].completed_topics = (1..=5).map(|n| format!("Lesson {}", n)).collect();
+        theirs.journeys[0].current_stage = 5;
+        let merge = merge_progress(ours, theirs, MergeStrategy::Ours);
+
+        assert_eq!(merge.progress.journeys[0].completed_topics.len(), 3);
+        assert_eq!(merge.progress.current_lesson.unwrap().topic, "structs");
+        assert_eq!(merge.progress.preferences.language, None);
+        assert_eq!(merge.changes, vec!["1 completed lesson(s) added"]);
+    }
+}
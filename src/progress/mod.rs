@@ -1,3 +1,5 @@
+pub mod merge;
 pub mod tracker;
 
+pub use merge::*;
 pub use tracker::*;
@@ -1,15 +1,142 @@
-use crate::config::{Difficulty, Language, LessonType};
+use crate::cli::fs_error;
+use crate::config::{env_vars, Difficulty, Language, LessonType};
+use crate::ollama::formatter::{GeneratedContent, TopicReference};
+use crate::progress::{merge_progress, MergeStrategy, ProgressMerge};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+// Number of lessons kept in the lesson history; the oldest are dropped first
+const MAX_LESSON_HISTORY: usize = 20;
+
+// Number of journey steps 'journey back' can undo, per journey
+const MAX_JOURNEY_HISTORY: usize = 10;
+
+// Set once from --home before anything reads or writes the data directory
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep progress, caches and templates in this directory instead of ~/.cursed-coddy
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+// Set once the data directory has been found writable, so the check runs once per run
+static DATA_DIR_WRITABLE: OnceLock<()> = OnceLock::new();
+
+// Creates the data directory and makes sure something can be written in it, so a
+// read-only mount or a dangling symlink is reported up front rather than by the
+// first save, with a pointer to where progress can be kept instead
+fn ensure_writable(dir: &Path) -> Result<()> {
+    if DATA_DIR_WRITABLE.get().is_some() {
+        return Ok(());
+    }
+
+    // create_dir_all only says "File exists" for a symlink to nowhere
+    if let Ok(target) = std::fs::read_link(dir) {
+        if !dir.exists() {
+            return Err(anyhow::anyhow!(
+                "The progress directory {} is a symlink to {}, which doesn't exist. Create it, or set CURSED_CODDY_HOME or CODDY_HOME, or pass --home, to keep progress somewhere else.",
+                dir.display(),
+                target.display()
+            ));
+        }
+    }
+
+    let what = format!(
+        "Cannot save progress in {} (set CURSED_CODDY_HOME or CODDY_HOME, or pass --home, to keep it in a directory you can write to)",
+        dir.display()
+    );
+    std::fs::create_dir_all(dir).map_err(|e| fs_error::write_error(e, &what, dir))?;
+    let probe = dir.join(format!(".write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"")
+        .and_then(|_| std::fs::remove_file(&probe))
+        .map_err(|e| fs_error::write_error(e, &what, &probe))?;
+
+    let _ = DATA_DIR_WRITABLE.set(());
+    Ok(())
+}
+
+/// Where progress, lesson caches and templates live: --home, then
+/// CURSED_CODDY_HOME (or CODDY_HOME), then .cursed-coddy in the home directory
+pub fn data_dir() -> Result<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
+    if let Some(dir) = env_vars::value("CURSED_CODDY_HOME") {
+        return Ok(PathBuf::from(dir));
+    }
+    let home_dir = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .context("Could not find home directory. Set CURSED_CODDY_HOME or CODDY_HOME to choose where progress is kept")?;
+    Ok(PathBuf::from(home_dir).join(".cursed-coddy"))
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Progress {
     pub completed_lessons: Vec<LessonRecord>,
     pub current_lesson: Option<LessonState>,
     pub statistics: Statistics,
+    /// One journey per language. Older progress files stored a single
+    /// `journey_progress` object, which is migrated on load.
+    #[serde(default, alias = "journey_progress", deserialize_with = "deserialize_journeys")]
+    pub journeys: Vec<JourneyProgress>,
+    #[serde(default)]
+    pub completed_journeys: Vec<JourneyRecord>,
+    /// The most recently reset journey, kept until the next reset so it can be restored
     #[serde(default)]
-    pub journey_progress: Option<JourneyProgress>,
+    pub last_reset_journey: Option<JourneyProgress>,
+    #[serde(default)]
+    pub preferences: Preferences,
+    #[serde(default)]
+    pub lesson_ratings: Vec<LessonRating>,
+}
+
+/// The learner's usual choices for a new lesson, pre-selected by 'start'
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Preferences {
+    pub language: Option<Language>,
+    pub difficulty: Option<Difficulty>,
+    pub lesson_type: Option<LessonType>,
+}
+
+// Accepts the current list format as well as the old single-journey (or null) format
+fn deserialize_journeys<'de, D>(deserializer: D) -> std::result::Result<Vec<JourneyProgress>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Journeys {
+        Many(Vec<JourneyProgress>),
+        One(JourneyProgress),
+        Empty,
+    }
+
+    Ok(match Journeys::deserialize(deserializer)? {
+        Journeys::Many(journeys) => journeys,
+        Journeys::One(journey) => vec![journey],
+        Journeys::Empty => Vec::new(),
+    })
+}
+
+/// What the learner was last doing, for 'continue'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// The free lesson in progress
+    Lesson,
+    Journey(Language),
+}
+
+// Seconds since the Unix epoch, the format every timestamp in the progress file uses
+fn now() -> String {
+    format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs())
+}
+
+// The start time and process, which no other lesson shares
+fn new_session_id() -> String {
+    format!("{}-{}", now(), std::process::id())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +148,18 @@ pub struct LessonRecord {
     pub completed_at: String,
 }
 
+/// How the learner rated a completed lesson, kept to see which models and topics give good lessons
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonRating {
+    /// None for built-in and hand-written lessons
+    pub model: Option<String>,
+    pub language: Language,
+    pub topic: String,
+    /// 1 (poor) to 5 (great)
+    pub rating: u8,
+    pub rated_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LessonState {
     pub language: Language,
@@ -29,6 +168,15 @@ pub struct LessonState {
     pub topic: String,
     pub current_exercise: usize,
     pub total_exercises: usize,
+    /// When the lesson was last started or an exercise finished, so 'continue'
+    /// can tell it apart from a journey touched more recently
+    #[serde(default)]
+    pub last_active_at: Option<String>,
+    /// Names the lesson's folder in the work directory, so lessons run at the same
+    /// time, even on one topic, keep their files apart. None for lessons started
+    /// before lessons had one, whose folder is named after the topic alone.
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +193,146 @@ pub struct JourneyProgress {
     pub current_topic_index: usize,
     pub completed_topics: Vec<String>,
     pub started_at: String,
+    #[serde(default)]
+    pub skipped_topics: Vec<String>,
+    /// How far the learner got in the lesson they're partway through
+    #[serde(default)]
+    pub checkpoint: Option<JourneyCheckpoint>,
+    /// States from before each recent lesson was completed or skipped, newest last
+    #[serde(default)]
+    pub history: Vec<JourneySnapshot>,
+    /// When the journey last moved on: started, or a lesson or exercise finished
+    #[serde(default)]
+    pub last_active_at: Option<String>,
+}
+
+/// A journey's position before a lesson was completed or skipped, so 'journey back' can return to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneySnapshot {
+    /// The lesson that was completed or skipped after this snapshot
+    pub lesson_title: String,
+    pub current_stage: usize,
+    pub completed_topics: Vec<String>,
+    pub skipped_topics: Vec<String>,
+    pub checkpoint: Option<JourneyCheckpoint>,
+}
+
+/// Exercises finished in a journey lesson that isn't completed yet, so the
+/// journey can resume mid-lesson
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct JourneyCheckpoint {
+    pub lesson_index: usize,
+    pub exercises_completed: usize,
+}
+
+impl JourneyProgress {
+    /// Start date as YYYY-MM-DD
+    pub fn started_date(&self) -> String {
+        let (year, month, day) = civil_date(&self.started_at);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    // Remembers the current position before the journey moves past `lesson_title`
+    fn push_snapshot(&mut self, lesson_title: &str) {
+        self.history.push(JourneySnapshot {
+            lesson_title: lesson_title.to_string(),
+            current_stage: self.current_stage,
+            completed_topics: self.completed_topics.clone(),
+            skipped_topics: self.skipped_topics.clone(),
+            checkpoint: self.checkpoint,
+        });
+        let excess = self.history.len().saturating_sub(MAX_JOURNEY_HISTORY);
+        self.history.drain(..excess);
+    }
+}
+
+/// A journey the learner finished, kept after its progress is cleared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneyRecord {
+    pub language: Language,
+    pub completed_at: String,
+    pub stages_completed: usize,
+}
+
+impl JourneyRecord {
+    /// Completion month as YYYY-MM
+    pub fn completed_month(&self) -> String {
+        let (year, month, _) = civil_date(&self.completed_at);
+        format!("{:04}-{:02}", year, month)
+    }
+}
+
+// Converts a stored epoch-seconds timestamp to (year, month, day) in UTC
+fn civil_date(timestamp: &str) -> (i64, i64, i64) {
+    let secs = timestamp.parse::<i64>().unwrap_or(0);
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let days = secs.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// A topic as a file-name-safe string, e.g. "for loops" -> "for-loops"
+pub fn topic_slug(topic: &str) -> String {
+    topic
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// The most recently started lesson's content, kept so parts of it can be
+/// regenerated or replayed without generating the whole lesson again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedLesson {
+    pub language: Language,
+    pub difficulty: Difficulty,
+    pub lesson_type: LessonType,
+    pub topic: String,
+    pub content: GeneratedContent,
+}
+
+/// A recently started lesson, kept so it can be picked again with 'resume'
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonHistoryEntry {
+    pub lesson: CachedLesson,
+    pub started_at: String,
+    #[serde(default)]
+    pub completed: bool,
+}
+
+impl LessonHistoryEntry {
+    /// Start date as YYYY-MM-DD
+    pub fn started_date(&self) -> String {
+        let (year, month, day) = civil_date(&self.started_at);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl CachedLesson {
+    /// Whether both hold content for the same language, difficulty, lesson type and topic
+    pub fn is_same_lesson(&self, other: &CachedLesson) -> bool {
+        self.language == other.language
+            && self.difficulty == other.difficulty
+            && self.lesson_type == other.lesson_type
+            && self.topic == other.topic
+    }
+
+    /// Whether this cached content belongs to the given lesson in progress
+    pub fn matches(&self, state: &LessonState) -> bool {
+        self.language == state.language
+            && self.difficulty == state.difficulty
+            && self.lesson_type == state.lesson_type
+            && self.topic == state.topic
+            && self.content.exercises.len() == state.total_exercises
+    }
 }
 
 impl Default for Progress {
@@ -57,26 +345,57 @@ impl Default for Progress {
                 lessons_by_language: std::collections::HashMap::new(),
                 lessons_by_difficulty: std::collections::HashMap::new(),
             },
-            journey_progress: None,
+            journeys: Vec::new(),
+            completed_journeys: Vec::new(),
+            last_reset_journey: None,
+            preferences: Preferences::default(),
+            lesson_ratings: Vec::new(),
         }
     }
 }
 
 pub struct Tracker {
     progress_file: PathBuf,
+    lesson_cache_file: PathBuf,
+    lesson_history_file: PathBuf,
+    prefetch_dir: PathBuf,
+    reference_dir: PathBuf,
+    // Exists once the first-run onboarding has been shown
+    onboarded_marker: PathBuf,
 }
 
 impl Tracker {
     pub fn new() -> Result<Self> {
-        let home_dir = std::env::var("HOME")
-            .or_else(|_| std::env::var("USERPROFILE"))
-            .context("Could not find home directory")?;
-        let progress_dir = PathBuf::from(home_dir).join(".cursed-coddy");
-        std::fs::create_dir_all(&progress_dir)
-            .context("Failed to create progress directory")?;
+        let progress_dir = data_dir()?;
+        ensure_writable(&progress_dir)?;
         let progress_file = progress_dir.join("progress.json");
+        let lesson_cache_file = progress_dir.join("last_lesson.json");
+        let lesson_history_file = progress_dir.join("lesson_history.json");
+        let prefetch_dir = progress_dir.join("prefetch");
+        let reference_dir = progress_dir.join("reference");
+        let onboarded_marker = progress_dir.join(".onboarded");
+
+        Ok(Self { progress_file, lesson_cache_file, lesson_history_file, prefetch_dir, reference_dir, onboarded_marker })
+    }
 
-        Ok(Self { progress_file })
+    pub fn is_onboarded(&self) -> bool {
+        self.onboarded_marker.exists()
+    }
+
+    /// Records that the onboarding was shown, so a bare run shows the help from now on
+    pub fn mark_onboarded(&self) -> Result<()> {
+        std::fs::write(&self.onboarded_marker, "")
+            .map_err(|e| fs_error::write_error(e, "Failed to write onboarding marker", &self.onboarded_marker))
+    }
+
+    pub fn preferences(&self) -> Result<Preferences> {
+        Ok(self.load()?.preferences)
+    }
+
+    pub fn save_preferences(&self, preferences: Preferences) -> Result<()> {
+        let mut progress = self.load()?;
+        progress.preferences = preferences;
+        self.save(&progress)
     }
 
     pub fn load(&self) -> Result<Progress> {
@@ -98,35 +417,200 @@ impl Tracker {
     pub fn save(&self, progress: &Progress) -> Result<()> {
         let content = serde_json::to_string_pretty(progress)
             .context("Failed to serialize progress")?;
-        std::fs::write(&self.progress_file, content)
-            .context("Failed to write progress file")?;
-        Ok(())
+        Self::write_atomic(&self.progress_file, &content, "Failed to write progress file")
+    }
+
+    // Write to a temp file in the same directory, then rename it over the
+    // real file so an interrupted write never leaves it truncated. The temp
+    // name is unique to the write, so two saves at once (two terminals, or a
+    // prefetch thread) can't rename each other's half-written file into place.
+    fn write_atomic(path: &Path, content: &str, what: &str) -> Result<()> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let temp_file = path.with_file_name(format!(
+            ".{}.{}-{}.tmp",
+            file_name,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = std::fs::write(&temp_file, content)
+            .map_err(|e| fs_error::write_error(e, what, &temp_file))
+            .and_then(|_| std::fs::rename(&temp_file, path).map_err(|e| fs_error::write_error(e, what, path)));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp_file);
+        }
+        written
+    }
+
+    pub fn save_lesson_cache(&self, lesson: &CachedLesson) -> Result<()> {
+        let content = serde_json::to_string_pretty(lesson)
+            .context("Failed to serialize lesson")?;
+        Self::write_atomic(&self.lesson_cache_file, &content, "Failed to write lesson cache")?;
+        self.record_lesson_history(lesson)
+    }
+
+    // Updates the latest history entry if this is still the same unfinished lesson
+    // (e.g. its exercises were regenerated), otherwise adds a new entry
+    fn record_lesson_history(&self, lesson: &CachedLesson) -> Result<()> {
+        // A damaged history file shouldn't stop a lesson from starting
+        let mut history = self.load_lesson_history().unwrap_or_default();
+        match history.last_mut() {
+            Some(entry) if !entry.completed && entry.lesson.is_same_lesson(lesson) => {
+                entry.lesson = lesson.clone();
+            }
+            _ => history.push(LessonHistoryEntry {
+                lesson: lesson.clone(),
+                started_at: format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()),
+                completed: false,
+            }),
+        }
+        let excess = history.len().saturating_sub(MAX_LESSON_HISTORY);
+        history.drain(..excess);
+        self.save_lesson_history(&history)
+    }
+
+    /// Recently started lessons, oldest first
+    pub fn load_lesson_history(&self) -> Result<Vec<LessonHistoryEntry>> {
+        if !self.lesson_history_file.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&self.lesson_history_file)
+            .context("Failed to read lesson history")?;
+
+        serde_json::from_str::<Vec<LessonHistoryEntry>>(&content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse lesson history. Delete it to start a new one."))
+    }
+
+    fn save_lesson_history(&self, history: &[LessonHistoryEntry]) -> Result<()> {
+        let content = serde_json::to_string_pretty(history)
+            .context("Failed to serialize lesson history")?;
+        Self::write_atomic(&self.lesson_history_file, &content, "Failed to write lesson history")
+    }
+
+    pub fn load_lesson_cache(&self) -> Result<Option<CachedLesson>> {
+        if !self.lesson_cache_file.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&self.lesson_cache_file)
+            .context("Failed to read lesson cache")?;
+
+        serde_json::from_str::<CachedLesson>(&content)
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("Failed to parse cached lesson. Start a new lesson to replace it."))
+    }
+
+    /// Number of lessons generated ahead of time with 'prefetch'
+    pub fn prefetched_count(&self) -> usize {
+        std::fs::read_dir(&self.prefetch_dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).filter(|e| e.path().extension().is_some_and(|x| x == "json")).count())
+            .unwrap_or(0)
+    }
+
+    /// Stores generated content so a later lesson on the same topic can start without generating
+    pub fn save_prefetched(&self, lesson: &CachedLesson) -> Result<()> {
+        std::fs::create_dir_all(&self.prefetch_dir)
+            .map_err(|e| fs_error::write_error(e, "Failed to create prefetch directory", &self.prefetch_dir))?;
+        let content = serde_json::to_string_pretty(&lesson.content)
+            .context("Failed to serialize lesson")?;
+        let path = self.prefetch_path(lesson.language, lesson.difficulty, lesson.lesson_type, &lesson.topic);
+        Self::write_atomic(&path, &content, "Failed to write prefetched lesson")
+    }
+
+    /// Prefetched content for this lesson, if any. An unreadable entry counts as missing
+    /// so the lesson is simply generated instead.
+    pub fn load_prefetched(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        lesson_type: LessonType,
+        topic: &str,
+    ) -> Option<GeneratedContent> {
+        let path = self.prefetch_path(language, difficulty, lesson_type, topic);
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn prefetch_path(&self, language: Language, difficulty: Difficulty, lesson_type: LessonType, topic: &str) -> PathBuf {
+        self.prefetch_dir.join(format!(
+            "{}_{}_{}_{}.json",
+            language.file_extension(),
+            difficulty.display_name().to_lowercase(),
+            lesson_type.display_name().to_lowercase(),
+            topic_slug(topic)
+        ))
+    }
+
+    /// Merges a progress file exported on another machine ('progress --json') into
+    /// this one's, without saving; the caller shows the changes and saves the result
+    pub fn import_from(&self, path: &Path, strategy: MergeStrategy) -> Result<ProgressMerge> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let theirs: Progress = serde_json::from_str(&content).with_context(|| {
+            format!(
+                "{} isn't a progress file. Export one with 'cursed-coddy progress --json > progress.json'.",
+                path.display()
+            )
+        })?;
+        Ok(merge_progress(self.load()?, theirs, strategy))
+    }
+
+    /// Keeps an `explain` answer so looking the topic up again is instant
+    pub fn save_reference(&self, language: Language, topic: &str, reference: &TopicReference) -> Result<()> {
+        std::fs::create_dir_all(&self.reference_dir)
+            .map_err(|e| fs_error::write_error(e, "Failed to create reference directory", &self.reference_dir))?;
+        let content = serde_json::to_string_pretty(reference).context("Failed to serialize explanation")?;
+        Self::write_atomic(&self.reference_path(language, topic), &content, "Failed to write explanation")
+    }
+
+    /// The saved `explain` answer for the topic, if any; an unreadable one counts as missing
+    pub fn load_reference(&self, language: Language, topic: &str) -> Option<TopicReference> {
+        let content = std::fs::read_to_string(self.reference_path(language, topic)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn reference_path(&self, language: Language, topic: &str) -> PathBuf {
+        self.reference_dir.join(format!("{}_{}.json", language.key(), topic_slug(topic)))
     }
 
+    /// Makes this the lesson in progress and returns the lesson it replaces, if any.
+    /// A lesson picked up partway, on the language and topic in progress, keeps its
+    /// session and with it the files written so far.
     pub fn start_lesson(
         &self,
         language: Language,
         difficulty: Difficulty,
         lesson_type: LessonType,
         topic: String,
+        current_exercise: usize,
         total_exercises: usize,
-    ) -> Result<()> {
+    ) -> Result<Option<LessonState>> {
         let mut progress = self.load()?;
+        let (session_id, replaced) = match progress.current_lesson.take() {
+            Some(previous) if current_exercise > 0 && previous.language == language && previous.topic == topic => {
+                (previous.session_id, None)
+            }
+            previous => (Some(new_session_id()), previous),
+        };
         progress.current_lesson = Some(LessonState {
             language,
             difficulty,
             lesson_type,
             topic,
-            current_exercise: 0,
+            current_exercise,
             total_exercises,
+            last_active_at: Some(now()),
+            session_id,
         });
-        self.save(&progress)
+        self.save(&progress)?;
+        Ok(replaced)
     }
 
     pub fn complete_exercise(&self) -> Result<()> {
         let mut progress = self.load()?;
         if let Some(ref mut lesson) = progress.current_lesson {
             lesson.current_exercise += 1;
+            lesson.last_active_at = Some(now());
         }
         self.save(&progress)
     }
@@ -153,31 +637,93 @@ impl Tracker {
                 .lessons_by_difficulty
                 .entry(lesson.difficulty.display_name().to_string())
                 .or_insert(0) += 1;
+
+            let mut history = self.load_lesson_history().unwrap_or_default();
+            if let Some(entry) = history.last_mut().filter(|e| e.lesson.matches(&lesson)) {
+                entry.completed = true;
+                self.save_lesson_history(&history)?;
+            }
         }
         self.save(&progress)
     }
 
+    pub fn rate_lesson(&self, model: Option<String>, language: Language, topic: String, rating: u8) -> Result<()> {
+        let mut progress = self.load()?;
+        progress.lesson_ratings.push(LessonRating {
+            model,
+            language,
+            topic,
+            rating,
+            rated_at: format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()),
+        });
+        self.save(&progress)
+    }
+
+    /// Starts a fresh journey for a language, replacing any existing one for it
     pub fn start_journey(&self, language: Language) -> Result<()> {
         let mut progress = self.load()?;
-        progress.journey_progress = Some(JourneyProgress {
+        progress.journeys.retain(|j| j.language != language);
+        progress.journeys.push(JourneyProgress {
             language,
             current_stage: 0,
             current_topic_index: 0,
             completed_topics: Vec::new(),
+            skipped_topics: Vec::new(),
+            checkpoint: None,
+            history: Vec::new(),
+            last_active_at: Some(now()),
             started_at: format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()),
         });
         self.save(&progress)
     }
 
-    pub fn get_journey_progress(&self) -> Result<Option<JourneyProgress>> {
+    pub fn get_journey_progress(&self, language: Language) -> Result<Option<JourneyProgress>> {
         let progress = self.load()?;
-        Ok(progress.journey_progress.clone())
+        Ok(progress.journeys.into_iter().find(|j| j.language == language))
+    }
+
+    pub fn get_journeys(&self) -> Result<Vec<JourneyProgress>> {
+        Ok(self.load()?.journeys)
+    }
+
+    /// Whichever was active last: the free lesson in progress or one of the
+    /// journeys in an enabled language. Anything saved before activity was
+    /// timestamped counts as older than everything that was.
+    pub fn last_activity(&self) -> Result<Option<Activity>> {
+        let progress = self.load()?;
+        let seconds = |at: &Option<String>| at.as_deref().and_then(|at| at.parse::<u64>().ok());
+
+        let journey = progress
+            .journeys
+            .iter()
+            .filter(|j| j.language.is_enabled())
+            .filter_map(|j| seconds(&j.last_active_at).map(|at| (at, j.language)))
+            .max_by_key(|&(at, _)| at);
+        let lesson = progress.current_lesson.as_ref().map(|lesson| seconds(&lesson.last_active_at));
+
+        Ok(match (lesson, journey) {
+            // A journey lesson touches its journey right after the lesson, so a tie goes to the journey
+            (Some(lesson_at), Some((journey_at, language))) if lesson_at.is_none_or(|at| journey_at >= at) => {
+                Some(Activity::Journey(language))
+            }
+            (Some(_), _) => Some(Activity::Lesson),
+            (None, Some((_, language))) => Some(Activity::Journey(language)),
+            (None, None) => progress
+                .journeys
+                .iter()
+                .find(|j| j.language.is_enabled())
+                .map(|j| Activity::Journey(j.language)),
+        })
     }
 
-    pub fn complete_journey_lesson(&self, lesson_index: usize, lesson_title: String) -> Result<()> {
+    pub fn complete_journey_lesson(&self, language: Language, lesson_index: usize, lesson_title: String) -> Result<()> {
         let mut progress = self.load()?;
-        if let Some(ref mut journey) = progress.journey_progress {
+        if let Some(journey) = progress.journeys.iter_mut().find(|j| j.language == language) {
+            journey.push_snapshot(&lesson_title);
             journey.current_stage = lesson_index;
+            journey.checkpoint = None;
+            journey.last_active_at = Some(now());
+            journey.skipped_topics.retain(|t| t != &lesson_title);
             if !journey.completed_topics.contains(&lesson_title) {
                 journey.completed_topics.push(lesson_title);
             }
@@ -185,10 +731,129 @@ impl Tracker {
         self.save(&progress)
     }
 
-    pub fn reset_journey(&self) -> Result<()> {
+    /// Records that the first `exercises_completed` exercises of a journey lesson are done
+    pub fn checkpoint_journey_lesson(&self, language: Language, lesson_index: usize, exercises_completed: usize) -> Result<()> {
+        let mut progress = self.load()?;
+        if let Some(journey) = progress.journeys.iter_mut().find(|j| j.language == language) {
+            journey.checkpoint = Some(JourneyCheckpoint { lesson_index, exercises_completed });
+            journey.last_active_at = Some(now());
+        }
+        self.save(&progress)
+    }
+
+    /// Records a finished journey and clears its progress so it can be started again
+    pub fn complete_journey(&self, language: Language) -> Result<()> {
+        let mut progress = self.load()?;
+        if let Some(pos) = progress.journeys.iter().position(|j| j.language == language) {
+            let journey = progress.journeys.remove(pos);
+            progress.completed_journeys.push(JourneyRecord {
+                language,
+                completed_at: format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()),
+                stages_completed: journey.completed_topics.len(),
+            });
+        }
+        self.save(&progress)
+    }
+
+    /// Clears a language's journey, keeping it as the one reset that can be undone
+    pub fn reset_journey(&self, language: Language) -> Result<()> {
         let mut progress = self.load()?;
-        progress.journey_progress = None;
+        if let Some(pos) = progress.journeys.iter().position(|j| j.language == language) {
+            progress.last_reset_journey = Some(progress.journeys.remove(pos));
+        }
         self.save(&progress)
     }
 
+    pub fn get_last_reset_journey(&self) -> Result<Option<JourneyProgress>> {
+        Ok(self.load()?.last_reset_journey)
+    }
+
+    /// Puts the last reset journey back, replacing any journey started in its
+    /// language since, and returns it
+    pub fn undo_journey_reset(&self) -> Result<Option<JourneyProgress>> {
+        let mut progress = self.load()?;
+        let journey = match progress.last_reset_journey.take() {
+            Some(journey) => journey,
+            None => return Ok(None),
+        };
+        progress.journeys.retain(|j| j.language != journey.language);
+        progress.journeys.push(journey.clone());
+        self.save(&progress)?;
+        Ok(Some(journey))
+    }
+
+    /// Returns a journey to where it was before its most recent lesson was completed
+    /// or skipped. Returns the snapshot it went back to, if there was one.
+    pub fn journey_back(&self, language: Language) -> Result<Option<JourneySnapshot>> {
+        let mut progress = self.load()?;
+        let journey = match progress.journeys.iter_mut().find(|j| j.language == language) {
+            Some(journey) => journey,
+            None => return Ok(None),
+        };
+        let snapshot = match journey.history.pop() {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+        journey.current_stage = snapshot.current_stage;
+        journey.completed_topics = snapshot.completed_topics.clone();
+        journey.skipped_topics = snapshot.skipped_topics.clone();
+        journey.checkpoint = snapshot.checkpoint;
+        self.save(&progress)?;
+        Ok(Some(snapshot))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_writes_replace_the_file_and_leave_no_temp_files() {
+        let dir = std::env::temp_dir().join(format!("cursed-coddy-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("progress.json");
+
+        std::thread::scope(|scope| {
+            for n in 0..8 {
+                let path = &path;
+                scope.spawn(move || Tracker::write_atomic(path, &format!("{{\"n\": {}}}", n), "write").unwrap());
+            }
+        });
+        let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(saved["n"].as_u64().unwrap() < 8);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn civil_date_reads_epoch_seconds_as_a_utc_date() {
+        assert_eq!(civil_date("0"), (1970, 1, 1));
+        assert_eq!(civil_date("1704067199"), (2023, 12, 31));
+        assert_eq!(civil_date("1704067200"), (2024, 1, 1));
+        assert_eq!(civil_date("-1"), (1969, 12, 31));
+        assert_eq!(civil_date("253402300799"), (9999, 12, 31));
+    }
+
+    #[test]
+    fn civil_date_knows_leap_days() {
+        assert_eq!(civil_date("951782400"), (2000, 2, 29));
+        assert_eq!(civil_date("951868800"), (2000, 3, 1));
+        assert_eq!(civil_date("1709164800"), (2024, 2, 29));
+        // 2100 is divisible by 100 but not 400, so March follows February 28th
+        assert_eq!(civil_date("4107456000"), (2100, 2, 28));
+        assert_eq!(civil_date("4107542400"), (2100, 3, 1));
+    }
+
+    #[test]
+    fn completed_month_falls_back_to_the_epoch_for_a_bad_timestamp() {
+        let record = |completed_at: &str| JourneyRecord {
+            language: Language::Rust,
+            completed_at: completed_at.to_string(),
+            stages_completed: 3,
+        };
+        assert_eq!(record("1709164800").completed_month(), "2024-02");
+        assert_eq!(record("not a time").completed_month(), "1970-01");
+    }
 }